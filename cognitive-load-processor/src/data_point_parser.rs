@@ -1,16 +1,137 @@
+use crate::format::CognitiveLoadFormat;
+use mteam_dashboard_utils::json::{parse_json_array_root, replace_nan_tokens};
 use serde_json::Value;
-use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
-
-pub(crate) fn map_time_to_date(item: Value, first_timestamp: Option<f64>) -> Option<(String, Option<f64>, Option<f64>)> {
-    if let Value::Array(time_and_load) = item {
-        if let (Some(elapsed_seconds), cognitive_load) = (time_and_load.get(0).and_then(Value::as_f64), time_and_load.get(1).and_then(Value::as_f64)) {
-            let start_seconds = first_timestamp.unwrap_or(elapsed_seconds);
-            let normalized_seconds = (elapsed_seconds - start_seconds) as u32;
-           
-            let csv_row_time = seconds_to_csv_row_time(normalized_seconds);
-           
-            return Some((csv_row_time.date_string, cognitive_load, Some(start_seconds)));
+
+/// Sample as read from the file, `time` is in the file's own time base.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RawSample {
+    pub time: f64,
+    pub load: Option<f64>,
+    pub confidence: Option<f64>,
+}
+
+/// Parses the content in the detected format. Rows without a time are skipped.
+pub(crate) fn parse_samples(content: &str) -> Result<Vec<RawSample>, String> {
+    match CognitiveLoadFormat::detect(content) {
+        CognitiveLoadFormat::JsonArray => Ok(parse_json_array_root(content.as_bytes())?.iter().filter_map(map_json_item).collect()),
+        CognitiveLoadFormat::Ndjson => parse_ndjson(content),
+        CognitiveLoadFormat::Csv => parse_csv(content),
+    }
+}
+
+/// Maps `[elapsed_seconds, load, confidence?]` arrays and `{"time", "load", "confidence"?}` objects.
+fn map_json_item(item: &Value) -> Option<RawSample> {
+    let (time, load, confidence) = match item {
+        Value::Array(values) => (values.first(), values.get(1), values.get(2)),
+        Value::Object(fields) => (fields.get("time"), fields.get("load"), fields.get("confidence")),
+        _ => return None,
+    };
+    Some(RawSample {
+        time: time.and_then(Value::as_f64)?,
+        load: load.and_then(Value::as_f64),
+        confidence: confidence.and_then(Value::as_f64),
+    })
+}
+
+fn parse_ndjson(content: &str) -> Result<Vec<RawSample>, String> {
+    let mut samples = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
         }
+        let item: Value = serde_json::from_str(&replace_nan_tokens(line))
+            .map_err(|e| format!("Error deserializing NDJSON line {}: {}", line_index + 1, e))?;
+        samples.extend(map_json_item(&item));
+    }
+    Ok(samples)
+}
+
+fn parse_number(field: Option<&str>) -> Option<f64> {
+    field.and_then(|field| field.parse::<f64>().ok()).filter(|value| value.is_finite())
+}
+
+/// Columns are matched by the `time`, `load` and `confidence` header names (case-insensitive),
+/// a file whose first row is numeric has no header and is read as `time,load[,confidence]`.
+fn parse_csv(content: &str) -> Result<Vec<RawSample>, String> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let mut records = csv_reader.records();
+
+    let first_record = match records.next() {
+        Some(record) => record.map_err(|e| format!("Error reading cognitive load CSV: {}", e))?,
+        None => return Ok(Vec::new()),
+    };
+    let mut samples = Vec::new();
+    let (time_column, load_column, confidence_column) = if parse_number(first_record.get(0)).is_some() {
+        samples.extend(map_csv_record(&first_record, 0, 1, Some(2)));
+        (0, 1, Some(2))
+    } else {
+        let column = |name: &str| first_record.iter().position(|header| header.eq_ignore_ascii_case(name));
+        let time_column = column("time").ok_or("Cognitive load CSV header has no time column")?;
+        let load_column = column("load").ok_or("Cognitive load CSV header has no load column")?;
+        (time_column, load_column, column("confidence"))
+    };
+
+    for record in records {
+        let record = record.map_err(|e| format!("Error reading cognitive load CSV: {}", e))?;
+        samples.extend(map_csv_record(&record, time_column, load_column, confidence_column));
+    }
+    Ok(samples)
+}
+
+fn map_csv_record(record: &csv::StringRecord, time_column: usize, load_column: usize, confidence_column: Option<usize>) -> Option<RawSample> {
+    Some(RawSample {
+        time: parse_number(record.get(time_column))?,
+        load: parse_number(record.get(load_column)),
+        confidence: confidence_column.and_then(|column| parse_number(record.get(column))),
+    })
+}
+
+#[cfg(test)]
+mod tests_data_point_parser {
+    use super::*;
+
+    fn sample(time: f64, load: Option<f64>, confidence: Option<f64>) -> RawSample {
+        RawSample { time, load, confidence }
+    }
+
+    #[test]
+    fn time_and_load_arrays() {
+        let samples = parse_samples("[[10.5, 0.5], [11.5, NaN], [\"x\", 1], [12.5, 0.7, 0.9]]").unwrap();
+        assert_eq!(samples, vec![sample(10.5, Some(0.5), None), sample(11.5, None, None), sample(12.5, Some(0.7), Some(0.9))]);
+    }
+
+    #[test]
+    fn objects() {
+        let samples = parse_samples(r#"[{"time": 1, "load": 0.5, "confidence": 0.8}, {"time": 2, "load": null}, {"load": 3}]"#).unwrap();
+        assert_eq!(samples, vec![sample(1.0, Some(0.5), Some(0.8)), sample(2.0, None, None)]);
+    }
+
+    #[test]
+    fn ndjson() {
+        let samples = parse_samples("{\"time\": 1, \"load\": 0.5}\n\n{\"time\": 2, \"load\": NaN, \"confidence\": 0.1}\n").unwrap();
+        assert_eq!(samples, vec![sample(1.0, Some(0.5), None), sample(2.0, None, Some(0.1))]);
+        assert!(parse_samples("{\"time\": 1}\n{\"time\":").is_err());
+    }
+
+    #[test]
+    fn csv_with_header() {
+        let samples = parse_samples("Confidence, Time, Load\n0.9, 1, 0.5\n,2,\n").unwrap();
+        assert_eq!(samples, vec![sample(1.0, Some(0.5), Some(0.9)), sample(2.0, None, None)]);
+    }
+
+    #[test]
+    fn csv_without_header() {
+        let samples = parse_samples("1,0.5\n2,NaN,0.4\n").unwrap();
+        assert_eq!(samples, vec![sample(1.0, Some(0.5), None), sample(2.0, None, Some(0.4))]);
+    }
+
+    #[test]
+    fn csv_without_required_columns() {
+        assert!(parse_samples("time,value\n1,0.5").is_err());
     }
-    None
-}
\ No newline at end of file
+}
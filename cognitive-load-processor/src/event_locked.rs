@@ -0,0 +1,179 @@
+/// Largest number of seconds the window extends on either side of the event, the averages hold a value per second.
+pub const MAX_WINDOW_SECS: u32 = 600;
+
+/// Time window extracted around each event, in seconds before and after the event time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EventLockedWindow {
+    pub pre_secs: u32,
+    pub post_secs: u32,
+}
+
+impl Default for EventLockedWindow {
+    fn default() -> Self {
+        Self {
+            pre_secs: 30,
+            post_secs: 60,
+        }
+    }
+}
+
+impl EventLockedWindow {
+    /// An error when either side is longer than `MAX_WINDOW_SECS`.
+    pub fn new(pre_secs: u32, post_secs: u32) -> Result<Self, String> {
+        if pre_secs > MAX_WINDOW_SECS || post_secs > MAX_WINDOW_SECS {
+            return Err(format!("The event-locked window is at most {} seconds before and after the event, got {} and {}", MAX_WINDOW_SECS, pre_secs, post_secs));
+        }
+        Ok(Self { pre_secs, post_secs })
+    }
+
+    /// Seconds before and after the event, each capped at `MAX_WINDOW_SECS`.
+    fn bounds(&self) -> (i64, i64) {
+        (i64::from(self.pre_secs.min(MAX_WINDOW_SECS)), i64::from(self.post_secs.min(MAX_WINDOW_SECS)))
+    }
+
+    /// Offsets (in whole seconds, relative to the event) covered by the window, e.g. -30..=60.
+    pub fn offsets(&self) -> Vec<i32> {
+        let (pre_secs, post_secs) = self.bounds();
+        (-pre_secs..=post_secs).filter_map(|offset| i32::try_from(offset).ok()).collect()
+    }
+}
+
+/// Event-related average of cognitive load.
+///
+/// `mean[i]` is the average load at `offsets[i]` seconds from the event, where every event is weighted
+/// equally regardless of how many samples fell into that second. `contributing_events[i]` tells how many
+/// events had at least one sample at that offset, `event_count` how many events had any sample in the window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventLockedAverage {
+    pub offsets: Vec<i32>,
+    pub mean: Vec<Option<f64>>,
+    pub contributing_events: Vec<usize>,
+    pub event_count: usize,
+}
+
+/// Averages the cognitive load samples around each event time.
+///
+/// `samples` are `(seconds, load)` pairs sorted by time, `event_times` are in the same time base as the samples.
+/// Samples are binned into 1 second slots relative to each event (rounded to the nearest second), averaged
+/// within the event first and then across the events.
+pub fn event_locked_average(samples: &[(f64, Option<f64>)], event_times: &[f64], window: &EventLockedWindow) -> EventLockedAverage {
+    let offsets = window.offsets();
+    let (pre_secs, post_secs) = window.bounds();
+    let (pre_secs, post_secs) = (pre_secs as f64, post_secs as f64);
+
+    let mut sums = vec![0.0; offsets.len()];
+    let mut contributing_events = vec![0; offsets.len()];
+    let mut event_count = 0;
+
+    for event_time in event_times {
+        let window_start = event_time - pre_secs - 0.5;
+        let window_end = event_time + post_secs + 0.5;
+        let first = samples.partition_point(|(time, _)| *time < window_start);
+
+        let mut event_sums = vec![0.0; offsets.len()];
+        let mut event_counts = vec![0usize; offsets.len()];
+        for (time, load) in samples[first..].iter().take_while(|(time, _)| *time < window_end) {
+            if let Some(load) = load {
+                let bin = ((time - event_time).round() + pre_secs) as i64;
+                if bin >= 0 && (bin as usize) < offsets.len() {
+                    event_sums[bin as usize] += load;
+                    event_counts[bin as usize] += 1;
+                }
+            }
+        }
+
+        if event_counts.iter().all(|count| *count == 0) {
+            continue;
+        }
+        event_count += 1;
+        for (bin, count) in event_counts.iter().enumerate() {
+            if *count > 0 {
+                sums[bin] += event_sums[bin] / *count as f64;
+                contributing_events[bin] += 1;
+            }
+        }
+    }
+
+    let mean = sums.iter().zip(contributing_events.iter())
+        .map(|(sum, count)| if *count > 0 { Some(sum / *count as f64) } else { None })
+        .collect();
+
+    EventLockedAverage {
+        offsets,
+        mean,
+        contributing_events,
+        event_count,
+    }
+}
+
+#[cfg(test)]
+mod tests_event_locked_average {
+    use super::*;
+
+    const WINDOW: EventLockedWindow = EventLockedWindow { pre_secs: 2, post_secs: 2 };
+
+    fn samples() -> Vec<(f64, Option<f64>)> {
+        (0..20).map(|second| (second as f64, Some(second as f64))).collect()
+    }
+
+    #[test]
+    fn offsets_cover_the_window() {
+        assert_eq!(WINDOW.offsets(), vec![-2, -1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn windows_are_capped() {
+        assert_eq!(EventLockedWindow::new(MAX_WINDOW_SECS, 5), Ok(EventLockedWindow { pre_secs: MAX_WINDOW_SECS, post_secs: 5 }));
+        assert!(EventLockedWindow::new(30, MAX_WINDOW_SECS + 1).is_err());
+        assert!(EventLockedWindow::new(u32::MAX, 60).is_err());
+
+        let unchecked = EventLockedWindow { pre_secs: u32::MAX, post_secs: 2_000_000_000 };
+        let offsets = unchecked.offsets();
+        assert_eq!((offsets.len(), offsets[0]), (2 * MAX_WINDOW_SECS as usize + 1, -(MAX_WINDOW_SECS as i32)));
+        assert_eq!(event_locked_average(&samples(), &[5.0], &unchecked).mean.len(), offsets.len());
+    }
+
+    #[test]
+    fn single_event() {
+        let average = event_locked_average(&samples(), &[5.0], &WINDOW);
+        assert_eq!(average.mean, vec![Some(3.0), Some(4.0), Some(5.0), Some(6.0), Some(7.0)]);
+        assert_eq!(average.contributing_events, vec![1, 1, 1, 1, 1]);
+        assert_eq!(average.event_count, 1);
+    }
+
+    #[test]
+    fn events_are_averaged() {
+        let average = event_locked_average(&samples(), &[5.0, 11.0], &WINDOW);
+        assert_eq!(average.mean, vec![Some(6.0), Some(7.0), Some(8.0), Some(9.0), Some(10.0)]);
+        assert_eq!(average.event_count, 2);
+    }
+
+    #[test]
+    fn event_near_the_start_has_missing_offsets() {
+        let average = event_locked_average(&samples(), &[0.0, 10.0], &WINDOW);
+        assert_eq!(average.mean, vec![Some(8.0), Some(9.0), Some(5.0), Some(6.0), Some(7.0)]);
+        assert_eq!(average.contributing_events, vec![1, 1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn missing_loads_are_skipped() {
+        let samples = vec![(4.0, None), (5.0, Some(1.0)), (6.0, None)];
+        let average = event_locked_average(&samples, &[5.0], &WINDOW);
+        assert_eq!(average.mean, vec![None, None, Some(1.0), None, None]);
+        assert_eq!(average.contributing_events, vec![0, 0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn event_without_samples_is_not_counted() {
+        let average = event_locked_average(&samples(), &[100.0], &WINDOW);
+        assert_eq!(average.event_count, 0);
+        assert!(average.mean.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn samples_within_a_second_are_averaged_per_event_first() {
+        let samples = vec![(5.0, Some(1.0)), (5.2, Some(3.0)), (15.0, Some(8.0))];
+        let average = event_locked_average(&samples, &[5.0, 15.0], &WINDOW);
+        assert_eq!(average.mean[2], Some(5.0));
+    }
+}
@@ -1,14 +1,65 @@
 use std::io::Read;
-use mteam_dashboard_utils::json::parse_json_array_root;
-use crate::data_point_parser::map_time_to_date;
+use mteam_dashboard_utils::alignment::StreamOffset;
+use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+use crate::data_point_parser::parse_samples;
 
-pub async fn process_cognitive_load_data(reader: &mut dyn Read) -> Result<impl Iterator<Item = (String, Option<f64>)>, String> {
-    let root_array = parse_json_array_root(reader)?;
+/// Cognitive load sample with the elapsed seconds relative to the first sample of the file, shifted by the alignment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CognitiveLoadSample {
+    pub seconds: f64,
+    pub load: Option<f64>,
+    pub confidence: Option<f64>,
+}
 
-    Ok(root_array.into_iter().scan(None, |state, item| {
-        map_time_to_date(item, *state).map(|(date_time, cognitive_load, first_timestamp)| {
-            *state = first_timestamp;
-            (date_time, cognitive_load)
-        })
+/// Yields `(plot date string, load, confidence)` for each sample.
+pub async fn process_cognitive_load_data(reader: &mut dyn Read, alignment: &StreamOffset) -> Result<impl Iterator<Item = (String, Option<f64>, Option<f64>)>, String> {
+    Ok(read_cognitive_load_samples(reader, alignment)?.into_iter().map(|sample| {
+        (seconds_to_csv_row_time(sample.seconds as u32).date_string, sample.load, sample.confidence)
     }))
-}
\ No newline at end of file
+}
+
+/// Reads the samples of a cognitive load file in any of the supported formats (see `CognitiveLoadFormat`). Samples the
+/// alignment shifts before the start of the timeline are out of range and left out.
+pub fn read_cognitive_load_samples(reader: &mut dyn Read, alignment: &StreamOffset) -> Result<Vec<CognitiveLoadSample>, String> {
+    let mut content = String::new();
+    reader.read_to_string(&mut content).map_err(|e| format!("Error reading cognitive load data: {}", e))?;
+    let raw_samples = parse_samples(&content)?;
+
+    let start_seconds = raw_samples.first().map(|sample| sample.time).unwrap_or_default();
+    Ok(raw_samples.into_iter()
+        .map(|sample| CognitiveLoadSample {
+            seconds: alignment.apply(sample.time - start_seconds),
+            load: sample.load,
+            confidence: sample.confidence,
+        })
+        .filter(|sample| sample.seconds >= 0.0)
+        .collect())
+}
+
+/// Same as `process_cognitive_load_data` but keeps the elapsed seconds (relative to the first sample, shifted by the
+/// alignment) instead of converting them to plot date strings, so the samples can be lined up with CSV action times.
+pub fn normalize_cognitive_load_data(reader: &mut dyn Read, alignment: &StreamOffset) -> Result<impl Iterator<Item = (f64, Option<f64>)>, String> {
+    Ok(read_cognitive_load_samples(reader, alignment)?.into_iter().map(|sample| (sample.seconds, sample.load)))
+}
+
+#[cfg(test)]
+mod tests_file_processor {
+    use super::*;
+
+    #[test]
+    fn samples_are_normalized_to_the_first_sample() {
+        let mut reader = "time,load,confidence\n100,0.5,0.9\n102.5,0.6,\n".as_bytes();
+        let samples = read_cognitive_load_samples(&mut reader, &StreamOffset { offset_secs: 1.0, drift_factor: 0.0 }).unwrap();
+        assert_eq!(samples, vec![
+            CognitiveLoadSample { seconds: 1.0, load: Some(0.5), confidence: Some(0.9) },
+            CognitiveLoadSample { seconds: 3.5, load: Some(0.6), confidence: None },
+        ]);
+    }
+
+    #[test]
+    fn samples_before_the_start_are_left_out() {
+        let mut reader = "time,load\n100,0.5\n101,0.6\n103,0.7\n".as_bytes();
+        let samples = read_cognitive_load_samples(&mut reader, &StreamOffset { offset_secs: -2.0, drift_factor: 0.0 }).unwrap();
+        assert_eq!(samples, vec![CognitiveLoadSample { seconds: 1.0, load: Some(0.7), confidence: None }]);
+    }
+}
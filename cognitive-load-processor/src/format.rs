@@ -0,0 +1,65 @@
+use mteam_dashboard_utils::json::replace_nan_tokens;
+
+/// File extensions of the cognitive load formats, the format itself is detected from the content.
+pub const SUPPORTED_FILE_EXTENSIONS: &[&str] = &["json", "ndjson", "jsonl", "csv"];
+
+/// Layout of a cognitive load file.
+///
+/// - `JsonArray`: a JSON array of `[elapsed_seconds, load, confidence?]` arrays or `{"time", "load", "confidence"?}` objects.
+/// - `Ndjson`: one such array or object per line.
+/// - `Csv`: `time,load[,confidence]` columns, with or without a header row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CognitiveLoadFormat {
+    JsonArray,
+    Ndjson,
+    Csv,
+}
+
+impl CognitiveLoadFormat {
+    /// Detects the format from the first non-blank line of the content.
+    /// A first line holding a complete JSON value followed by more lines is NDJSON, a pretty-printed or single-line
+    /// array is a JSON array and anything that doesn't start like JSON is CSV.
+    pub fn detect(content: &str) -> Self {
+        let mut lines = content.lines().map(str::trim).filter(|line| !line.is_empty());
+        let first_line = match lines.next() {
+            Some(line) => line,
+            None => return CognitiveLoadFormat::JsonArray,
+        };
+        if first_line.starts_with('{') {
+            CognitiveLoadFormat::Ndjson
+        } else if first_line.starts_with('[') {
+            let first_line_is_value = serde_json::from_str::<serde_json::Value>(&replace_nan_tokens(first_line)).is_ok();
+            if first_line_is_value && lines.next().is_some() {
+                CognitiveLoadFormat::Ndjson
+            } else {
+                CognitiveLoadFormat::JsonArray
+            }
+        } else {
+            CognitiveLoadFormat::Csv
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_cognitive_load_format {
+    use super::*;
+
+    #[test]
+    fn json_array() {
+        assert_eq!(CognitiveLoadFormat::detect("[[0, 0.5], [1, 0.6]]"), CognitiveLoadFormat::JsonArray);
+        assert_eq!(CognitiveLoadFormat::detect("[\n  {\"time\": 0, \"load\": 0.5}\n]"), CognitiveLoadFormat::JsonArray);
+        assert_eq!(CognitiveLoadFormat::detect(""), CognitiveLoadFormat::JsonArray);
+    }
+
+    #[test]
+    fn ndjson() {
+        assert_eq!(CognitiveLoadFormat::detect("{\"time\": 0, \"load\": 0.5}\n{\"time\": 1, \"load\": 0.6}"), CognitiveLoadFormat::Ndjson);
+        assert_eq!(CognitiveLoadFormat::detect("[0, NaN]\n[1, 0.6]\n"), CognitiveLoadFormat::Ndjson);
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(CognitiveLoadFormat::detect("time,load,confidence\n0,0.5,0.9"), CognitiveLoadFormat::Csv);
+        assert_eq!(CognitiveLoadFormat::detect("0,0.5\n1,0.6"), CognitiveLoadFormat::Csv);
+    }
+}
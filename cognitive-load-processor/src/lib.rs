@@ -1,2 +1,4 @@
 pub mod file_processor;
+pub mod event_locked;
+pub mod format;
 mod data_point_parser;
@@ -1,18 +1,20 @@
 use crate::annotation::Annotation;
 use crate::actions::compression_line::CompressionLine;
+use crate::config::layout_settings::{IconSettings, LayoutSettings, Theme};
 use crate::font::Font;
 use crate::image::Image;
 use crate::line::Line;
 use crate::shape::Shape;
+use crate::value::PlotValue;
 use mteam_dashboard_action_processor::plot_structures::PlotLocation;
 
-pub fn create_image(x: String, y: String, source: String)->Image{
+pub fn create_image(x: PlotValue, y: PlotValue, source: String, icon: &IconSettings)->Image{
     Image{
         x,
         y,
         source,
-        sizex: 10500.0,
-        sizey: 1.5,
+        sizex: icon.size_x,
+        sizey: icon.size_y,
         xref: "x".to_owned(),
         yref: "y".to_owned(),
         xanchor: "center".to_owned(),
@@ -23,9 +25,9 @@ pub fn create_image(x: String, y: String, source: String)->Image{
         opacity: 1,
     }
 }
-pub fn create_compression_line(start: PlotLocation, end: PlotLocation, y:String) -> CompressionLine {
+pub fn create_compression_line(start: PlotLocation, end: PlotLocation, y: PlotValue, theme: &Theme) -> CompressionLine {
     CompressionLine {
-        x: vec![start.timestamp.date_string, end.timestamp.date_string],
+        x: vec![PlotValue::date_time(&start.timestamp.date_string), PlotValue::date_time(&end.timestamp.date_string)],
         y: vec![y.clone(), y.clone()],
         hovertext: vec![start.timestamp.timestamp, end.timestamp.timestamp],
         text: "".to_owned(),
@@ -41,34 +43,32 @@ pub fn create_compression_line(start: PlotLocation, end: PlotLocation, y:String)
         },
         line: Line{
             width: None,
-            color: Some("rgb(0, 150, 0)".to_owned()),
+            color: Some(theme.cpr_line_color.clone()),
         }
     }
 }
-pub fn create_stage_annotation(stage_name: String) -> Annotation {
+pub fn create_stage_annotation(stage_name: String, settings: &LayoutSettings, theme: &Theme) -> Annotation {
     Annotation {
         text: stage_name,
         xref: "x".to_owned(),
         yref: "paper".to_owned(),
-        x: "".to_owned(),
+        x: PlotValue::default(),
         y: 0.9,
         xanchor: "left".to_owned(),
         yanchor: "middle".to_owned(),
         showarrow: false,
-        font: Font {
-            size: 16,
-            color: Some("".to_owned()),
-            family: Some("Arial, sans-serif".to_owned()),
-            weight: Some(700),
-        },
-        bgcolor: "rgba(255, 255, 255, 0.8)".to_owned(),
+        font: settings.stage_annotation_font.clone(),
+        bgcolor: theme.stage_annotation_background_color.clone(),
         bordercolor: "".to_owned(),
         borderwidth: 1,
         borderpad: 3,
     }
 }
 
-pub fn create_plot_actions_section_annotation(section_name: String, x:String, y: f32, yref: String) -> Annotation{
+pub fn create_plot_actions_section_annotation(section_name: String, x: PlotValue, y: f32, yref: String, settings: &LayoutSettings) -> Annotation{
+    let mut font = settings.section_annotation_font.clone();
+    // the section names have always been sent with an empty font color
+    font.color = font.color.or_else(|| Some("".to_owned()));
     Annotation {
         text: section_name,
         xref: "x".to_owned(),
@@ -78,12 +78,7 @@ pub fn create_plot_actions_section_annotation(section_name: String, x:String, y:
         xanchor: "left".to_owned(),
         yanchor: "middle".to_owned(),
         showarrow: false,
-        font: Font {
-            size: 14,
-            color: Some("".to_owned()),
-            family: Some("Arial, sans-serif".to_owned()),
-            weight: Some(550),
-        },
+        font,
         bgcolor: "".to_string(),
         bordercolor: "".to_string(),
         borderwidth: 0,
@@ -93,12 +88,12 @@ pub fn create_plot_actions_section_annotation(section_name: String, x:String, y:
 
 pub fn create_shape(start: &PlotLocation, end: &PlotLocation) -> Shape {
     Shape {
-        x0: start.timestamp.date_string.to_owned(),
-        x1: end.timestamp.date_string.to_owned(),
+        x0: PlotValue::date_time(&start.timestamp.date_string),
+        x1: PlotValue::date_time(&end.timestamp.date_string),
         fillcolor: "#".to_owned(),
         name: start.stage.1.to_owned(),
-        y0: "".to_owned(),
-        y1: "".to_owned(),
+        y0: PlotValue::default(),
+        y1: PlotValue::default(),
         shape_type: "rect".to_owned(),
         xref: "x".to_owned(),
         yref: "y".to_owned(),
@@ -1,11 +1,12 @@
 use serde::{Serialize, Deserialize};
 use crate::font::Font;
 use crate::line::Line;
+use crate::value::PlotValue;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CompressionLine {
-    pub x: Vec<String>,
-    pub y: Vec<String>,
+    pub x: Vec<PlotValue>,
+    pub y: Vec<PlotValue>,
     pub text: String,
     pub mode: String,
     #[serde(rename = "type")]
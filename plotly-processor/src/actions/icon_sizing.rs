@@ -0,0 +1,135 @@
+use crate::config::layout_settings::IconSettings;
+use crate::value::PlotValue;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Icon height relative to the distance between two action group rows.
+const ROW_HEIGHT_RATIO: f64 = 1.5;
+/// Icons are at most this fraction of the y axis range tall, plots with few action groups get huge icons otherwise.
+const MAX_Y_RANGE_RATIO: f64 = 0.25;
+
+/// Size of the action icons at a zoom level of the time axis.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IconSize {
+    /// Factor the time axis is zoomed in by, 1 shows the whole session.
+    pub zoom: f64,
+    #[serde(rename = "sizeX")]
+    pub size_x: f64,
+    #[serde(rename = "sizeY")]
+    pub size_y: f64,
+}
+
+/// Computes the icon size of a session: the width starts at `iconsAcross` icons on the visible time span, is reduced
+/// to the typical distance between neighbouring actions of an action group so that the icons don't overlap and is
+/// kept within `minSizeX` and `maxSizeX`. The height follows the distance between the action group rows.
+pub struct IconSizing {
+    x_span_ms: f64,
+    y_span: f64,
+    row_spacing: f64,
+    neighbour_gap_ms: Option<f64>,
+}
+
+impl IconSizing {
+    /// `x` and `y` are the coordinates of the performed actions, a row is an action group.
+    pub fn new(x_span_ms: f64, y_span: f64, row_spacing: f64, x: &[PlotValue], y: &[PlotValue]) -> Self {
+        Self { x_span_ms, y_span, row_spacing, neighbour_gap_ms: neighbour_gap_ms(x, y) }
+    }
+
+    pub fn size(&self, settings: &IconSettings, zoom: f64) -> IconSize {
+        if !settings.auto_size {
+            return IconSize { zoom, size_x: settings.size_x, size_y: settings.size_y };
+        }
+        let mut size_x = self.x_span_ms / zoom.max(1.0) / settings.icons_across.max(1.0);
+        if let Some(gap) = self.neighbour_gap_ms {
+            size_x = size_x.min(gap);
+        }
+        let size_y = (self.row_spacing.abs() * ROW_HEIGHT_RATIO).min(self.y_span.abs() * MAX_Y_RANGE_RATIO);
+        IconSize {
+            zoom,
+            size_x: size_x.max(settings.min_size_x).min(settings.max_size_x),
+            size_y: size_y.max(settings.min_size_y).min(settings.max_size_y),
+        }
+    }
+
+    /// The sizes at the configured zoom levels, none when the size is fixed.
+    pub fn zoom_level_sizes(&self, settings: &IconSettings) -> Vec<IconSize> {
+        if !settings.auto_size {
+            return Vec::new();
+        }
+        settings.zoom_levels.iter().map(|zoom| self.size(settings, *zoom)).collect()
+    }
+}
+
+/// Lower quartile of the distances between neighbouring actions of the same row, wider icons overlap there.
+fn neighbour_gap_ms(x: &[PlotValue], y: &[PlotValue]) -> Option<f64> {
+    let mut rows: BTreeMap<u64, Vec<i64>> = BTreeMap::new();
+    for (x, y) in x.iter().zip(y) {
+        if let (Some(x), Some(y)) = (x.as_date_time(), y.as_number()) {
+            rows.entry(y.to_bits()).or_default().push(x.and_utc().timestamp_millis());
+        }
+    }
+    let mut gaps: Vec<i64> = rows.into_values()
+        .flat_map(|mut row| {
+            row.sort_unstable();
+            row.windows(2).map(|pair| pair[1] - pair[0]).filter(|gap| *gap > 0).collect::<Vec<_>>()
+        })
+        .collect();
+    gaps.sort_unstable();
+    gaps.get(gaps.len() / 4).map(|gap| *gap as f64)
+}
+
+#[cfg(test)]
+mod tests_icon_sizing {
+    use super::*;
+    use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+
+    fn auto_settings() -> IconSettings {
+        IconSettings { auto_size: true, zoom_levels: vec![2.0, 8.0], ..Default::default() }
+    }
+
+    fn actions(seconds_and_rows: &[(u32, f64)]) -> (Vec<PlotValue>, Vec<PlotValue>) {
+        seconds_and_rows.iter()
+            .map(|(seconds, row)| (PlotValue::date_time(&seconds_to_csv_row_time(*seconds).date_string), PlotValue::Number(*row)))
+            .unzip()
+    }
+
+    #[test]
+    fn width_follows_the_session_duration() {
+        let (x, y) = actions(&[(10, 2.0), (300, 2.0)]);
+        let short = IconSizing::new(300_000.0, 10.0, 1.0, &x, &y).size(&auto_settings(), 1.0);
+        let long = IconSizing::new(1_200_000.0, 10.0, 1.0, &x, &y).size(&auto_settings(), 1.0);
+        assert_eq!(short, IconSize { zoom: 1.0, size_x: 5000.0, size_y: 1.5 });
+        assert_eq!(long.size_x, 20000.0);
+    }
+
+    #[test]
+    fn dense_actions_get_narrower_icons() {
+        // the actions of the second row are 4 seconds apart, the first row is ignored since its actions are far apart
+        let (x, y) = actions(&[(0, 3.0), (600, 3.0), (100, 2.0), (104, 2.0), (108, 2.0), (112, 2.0)]);
+        let sizing = IconSizing::new(1_200_000.0, 10.0, 1.0, &x, &y);
+        assert_eq!(sizing.size(&auto_settings(), 1.0).size_x, 4000.0);
+
+        let (x, y) = actions(&[(100, 2.0), (100, 3.0), (101, 2.0)]);
+        assert_eq!(IconSizing::new(1_200_000.0, 10.0, 1.0, &x, &y).size(&auto_settings(), 1.0).size_x, 2000.0);
+    }
+
+    #[test]
+    fn sizes_stay_within_the_limits() {
+        let settings = IconSettings { min_size_x: 3000.0, max_size_x: 8000.0, max_size_y: 1.0, ..auto_settings() };
+        let sizing = IconSizing::new(3_600_000.0, 2.0, 1.0, &[], &[]);
+        assert_eq!(sizing.size(&settings, 1.0), IconSize { zoom: 1.0, size_x: 8000.0, size_y: 0.5 });
+        assert_eq!(sizing.size(&settings, 100.0).size_x, 3000.0);
+    }
+
+    #[test]
+    fn zoom_levels() {
+        let sizing = IconSizing::new(1_200_000.0, 10.0, 1.0, &[], &[]);
+        assert_eq!(sizing.zoom_level_sizes(&auto_settings()), vec![
+            IconSize { zoom: 2.0, size_x: 10000.0, size_y: 1.5 },
+            IconSize { zoom: 8.0, size_x: 2500.0, size_y: 1.5 },
+        ]);
+        let fixed = IconSettings { zoom_levels: vec![2.0], ..Default::default() };
+        assert!(sizing.zoom_level_sizes(&fixed).is_empty());
+        assert_eq!(sizing.size(&fixed, 1.0), IconSize { zoom: 1.0, size_x: 10500.0, size_y: 1.5 });
+    }
+}
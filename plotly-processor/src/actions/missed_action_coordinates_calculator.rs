@@ -1,8 +1,9 @@
+use crate::config::plotly_mappings::MissedActionsPlotSettings;
 use chrono::{Timelike, Utc};
 use std::collections::HashMap;
+use std::hash::Hash;
 
 pub struct Rectangle {
-    pub name: String,
     pub x0: f32,
     pub x1: f32,
     pub y0: f32,
@@ -68,80 +69,102 @@ pub fn calculate_point_coordinates(rectangle: &Rectangle, points_per_row: &Vec<u
     point_coordinates
 }
 
-pub struct MissedActionsCoordinatesIterator<'a> {
-    hover_text: &'a Vec<String>,
-    stages: &'a Vec<(u32, String)>,
-    rectangle_map: &'a HashMap<String, Rectangle>,
-    rectangle_point_counts: &'a HashMap<String, u16>,
-    max_points_per_row: usize,
-    current_index: usize,
-    current_stage_index: usize,
-    current_stage_name: &'a str,
-    current_stage_points_coordinates: Vec<(String, f32)>
+/// Icons of crowded stages are shrunk down to this scale before they wrap to additional rows.
+pub const MIN_ICON_SCALE: f32 = 0.5;
+
+/// How the missed action icons of a stage are arranged.
+#[derive(Debug, PartialEq)]
+pub struct StageFit {
+    pub columns: u16,
+    pub rows: u16,
+    pub icon_scale: f32,
 }
 
-impl<'a> MissedActionsCoordinatesIterator<'a> {
-    pub fn new(
-        hover_text: &'a Vec<String>,
-        stages: &'a Vec<(u32, String)>,
-        rectangle_map: &'a HashMap<String, Rectangle>,
-        rectangle_point_counts: &'a HashMap<String, u16>,
-        max_points_per_row: usize
-    ) -> Self {
-        assert_eq!(
-            hover_text.len(),
-            stages.len(),
-            "All missed actions should have assigned stages"
-        );
-        let counts_total = rectangle_point_counts.iter().fold(0, |mut total, (_, count)| {
-            total+=count;
-            total
-        }) as usize;
-        assert_eq!(
-            hover_text.len(),
-            counts_total,
-            "All missed actions count in stages should add up to total number of missed actions"
-        );
-        Self {
-            hover_text,
-            stages,
-            rectangle_map,
-            rectangle_point_counts,
-            current_index: 0,
-            current_stage_index: 0,
-            max_points_per_row,
-            current_stage_name: "",
-            current_stage_points_coordinates: Vec::new()
-        }
+/// Arranges `point_count` icons of `icon_width` in a stage of `stage_width` (both in seconds). Rows hold up to
+/// `max_points_per_row` icons, the icons are shrunk when the stage is too short for its rows and wrap to additional
+/// rows when even `MIN_ICON_SCALE` doesn't make them fit.
+pub fn fit_points_in_stage(point_count: u16, stage_width: f32, icon_width: f32, max_points_per_row: u16) -> StageFit {
+    let point_count = point_count.max(1);
+    let rows = point_count.div_ceil(max_points_per_row.max(1));
+    let columns = point_count.div_ceil(rows);
+    // the icons are spread with a gap to both ends of the stage, see calculate_gaps_between_points_within_line
+    let scale_for = |columns: u16| stage_width / ((columns as f32 + 1.0) * icon_width);
+    if icon_width <= 0.0 || scale_for(columns) >= 1.0 {
+        return StageFit { columns, rows, icon_scale: 1.0 };
+    }
+    if scale_for(columns) >= MIN_ICON_SCALE {
+        return StageFit { columns, rows, icon_scale: scale_for(columns) };
+    }
+    let columns = ((stage_width / (icon_width * MIN_ICON_SCALE) - 1.0).floor().max(1.0) as u16).min(columns);
+    StageFit {
+        columns,
+        rows: point_count.div_ceil(columns),
+        icon_scale: scale_for(columns).clamp(MIN_ICON_SCALE, 1.0),
     }
 }
 
-impl<'a> Iterator for MissedActionsCoordinatesIterator<'a> {
-    type Item = (String, String, f32);
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current_index >= self.hover_text.len() {
-            return None;
+#[derive(Debug, PartialEq)]
+pub struct MissedActionPlacement {
+    pub x: String,
+    pub y: f32,
+    pub icon_scale: f32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MissedActionsLayout {
+    /// One placement for every missed action, in the order of the missed actions.
+    pub placements: Vec<MissedActionPlacement>,
+    /// Bottom of the missed actions area, the area is as tall as the most crowded stage needs.
+    pub y_max: f32,
+    /// Whether missed actions of stages without a stage shape were placed in the overflow span.
+    pub uses_overflow: bool,
+}
+
+/// Places the missed actions in the stage spans (start and end second by stage) given the stages of the missed
+/// actions, a stage that occurs more than once needs a key for each occurrence. Missed actions of a stage without a
+/// span are placed in `overflow_span`, e.g. after the last stage.
+pub fn layout_missed_actions<K: Eq + Hash>(
+    stages: &[K],
+    stage_spans: &HashMap<K, (f32, f32)>,
+    overflow_span: (f32, f32),
+    icon_width: f32,
+    settings: &MissedActionsPlotSettings,
+) -> MissedActionsLayout {
+    let span_of = |stage: &K| stage_spans.get(stage).copied();
+    let mut stage_points: Vec<(Option<&K>, Vec<usize>)> = Vec::new();
+    for (index, stage) in stages.iter().enumerate() {
+        let key = span_of(stage).map(|_| stage);
+        match stage_points.iter_mut().find(|(stage, _)| *stage == key) {
+            Some((_, indices)) => indices.push(index),
+            None => stage_points.push((key, vec![index])),
         }
-        
-        let rectangle_name = &self.stages[self.current_index];
-        let rectangle = &self.rectangle_map.get(&rectangle_name.1).unwrap();
-        let point_count = *self.rectangle_point_counts.get(&rectangle_name.1).unwrap();
-        
-        if self.current_stage_name != self.stages[self.current_index].1 {
-            let points_per_row = calculate_points_per_row(point_count, self.max_points_per_row as u16);
+    }
 
-            self.current_stage_points_coordinates = calculate_point_coordinates(&rectangle, &points_per_row);
-            self.current_stage_index=0;
+    let max_points_per_row = settings.max_count_per_row.max(1);
+    let fits: Vec<((f32, f32), StageFit)> = stage_points.iter().map(|(stage, indices)| {
+        let span = stage.and_then(span_of).unwrap_or(overflow_span);
+        let point_count = u16::try_from(indices.len()).unwrap_or(u16::MAX);
+        (span, fit_points_in_stage(point_count, span.1 - span.0, icon_width, max_points_per_row))
+    }).collect();
+    // the area height of a stage that wraps to additional rows is the one of a stage filling them
+    let height_count = stage_points.iter().zip(&fits)
+        .map(|((_, indices), (_, fit))| (indices.len() as u16).max((fit.rows - 1).saturating_mul(max_points_per_row).saturating_add(1)))
+        .max()
+        .unwrap_or(0);
+    let y_max = settings.calculate_y_max(height_count);
+
+    let mut placements: Vec<Option<MissedActionPlacement>> = stages.iter().map(|_| None).collect();
+    for ((_, indices), (span, fit)) in stage_points.iter().zip(&fits) {
+        let rectangle = Rectangle { x0: span.0, x1: span.1, y0: settings.y_min, y1: y_max };
+        let points_per_row = calculate_points_per_row(indices.len() as u16, fit.columns);
+        for (index, (x, y)) in indices.iter().zip(calculate_point_coordinates(&rectangle, &points_per_row)) {
+            placements[*index] = Some(MissedActionPlacement { x, y, icon_scale: fit.icon_scale });
         }
-        
-        let x = self.current_stage_points_coordinates[self.current_stage_index].0.clone();
-        let y = self.current_stage_points_coordinates[self.current_stage_index].1.clone();
-        self.current_stage_index+=1;
-        
-        self.current_stage_name = &self.stages[self.current_index].1;
-        let hover_text = &self.hover_text[self.current_index].clone();
-        self.current_index += 1;
-        Some((hover_text.clone(), x, y))
+    }
+    MissedActionsLayout {
+        placements: placements.into_iter().flatten().collect(),
+        y_max,
+        uses_overflow: stage_points.iter().any(|(stage, _)| stage.is_none()),
     }
 }
 
@@ -172,77 +195,86 @@ mod tests_calculate_missed_actions_stage_coordinates {
     }
 }
 #[cfg(test)]
-mod tests_missed_actions_coordinates_iterator {
-    const MAX_POINTS_PER_ROW: usize = 2;
+mod tests_layout_missed_actions {
     use super::*;
-    
-    fn create_rectangle_map() -> HashMap<String, Rectangle> {
+
+    const ICON_WIDTH: f32 = 10.0;
+
+    fn settings() -> MissedActionsPlotSettings {
+        MissedActionsPlotSettings { max_count_per_row: 2, y_increment: -6.0, y_min: 0.0 }
+    }
+
+    fn stage_spans() -> HashMap<&'static str, (f32, f32)> {
         HashMap::from([
-            ("stageA".to_owned(), Rectangle { x0: 0.0, x1: 100.0, y0: 0.0, y1: -4.0, name: "stageA".to_owned() }),
-            ("stageB".to_owned(), Rectangle { x0: 100.0, x1: 200.0, y0: 0.0, y1: -4.0, name: "stageB".to_owned() }),
-            ("stageC".to_owned(), Rectangle { x0: 200.0, x1: 300.0, y0: 0.0, y1: -4.0, name: "stageC".to_owned() }),
+            ("stageA", (0.0, 100.0)),
+            ("stageB", (100.0, 200.0)),
+            ("stageC", (200.0, 300.0)),
         ])
     }
+
+    fn placement(x: f32, y: f32, icon_scale: f32) -> MissedActionPlacement {
+        MissedActionPlacement { x: seconds_to_date_time_string(x), y, icon_scale }
+    }
+
     #[test]
-    fn single_point_per_row() {
-        let hover_text = vec!["text1".to_owned(), "text2".to_owned(), "text3".to_owned()];
-        let rectangle_map = create_rectangle_map();
-        let stages = vec![(1, "stageA".to_owned()),(2, "stageB".to_owned()),(3, "stageC".to_owned())];
-        let mut rectangle_point_counts = HashMap::new();
-        rectangle_point_counts.insert("stageA".to_owned(), 1);
-        rectangle_point_counts.insert("stageB".to_owned(), 1);
-        rectangle_point_counts.insert("stageC".to_owned(), 1);
-
-        let iterator = MissedActionsCoordinatesIterator::new(&hover_text, &stages, &rectangle_map, &rectangle_point_counts, MAX_POINTS_PER_ROW);
-
-        let expected_coordinates = vec![
-            ("text1".to_owned(), seconds_to_date_time_string(50.0), -2.0),
-            ("text2".to_owned(), seconds_to_date_time_string(150.0), -2.0),
-            ("text3".to_owned(), seconds_to_date_time_string(250.0), -2.0),
-        ];
-
-        for (expected, actual) in expected_coordinates.iter().zip(iterator) {
-            assert_eq!(expected, &actual);
-        }
+    fn single_point_per_stage() {
+        let layout = layout_missed_actions(&["stageA", "stageB", "stageC"], &stage_spans(), (300.0, 315.0), ICON_WIDTH, &settings());
+        assert_eq!(layout.y_max, -2.0);
+        assert_eq!(layout.placements, vec![placement(50.0, -1.0, 1.0), placement(150.0, -1.0, 1.0), placement(250.0, -1.0, 1.0)]);
+        assert!(!layout.uses_overflow);
     }
 
     #[test]
-    fn multiple_points_per_row() {
-        let hover_text = vec!["text1".to_owned(), "text2".to_owned(), "text3".to_owned(), "text4".to_owned(), "text5".to_owned(), "text6".to_owned(), "text7".to_owned()];
-        let rectangle_map = create_rectangle_map();
-        let stages = vec![(1, "stageA".to_owned()),(1, "stageA".to_owned()),(1, "stageA".to_owned()),(2, "stageB".to_owned()),(2, "stageB".to_owned()),(2, "stageB".to_owned()),(3, "stageC".to_owned())];
-        let mut rectangle_point_counts = HashMap::new();
-        rectangle_point_counts.insert("stageA".to_owned(), 3);
-        rectangle_point_counts.insert("stageB".to_owned(), 3);
-        rectangle_point_counts.insert("stageC".to_owned(), 1);
-
-        let iterator = MissedActionsCoordinatesIterator::new(&hover_text, &stages, &rectangle_map, &rectangle_point_counts, MAX_POINTS_PER_ROW);
-
-        let expected_coordinates = vec![
-            ("text1".to_owned(), seconds_to_date_time_string(33.0), -1.33),
-            ("text2".to_owned(), seconds_to_date_time_string(66.0), -1.33),
-            ("text3".to_owned(), seconds_to_date_time_string(50.0), -2.66),
-            ("text4".to_owned(), seconds_to_date_time_string(133.0), -1.33),
-            ("text5".to_owned(), seconds_to_date_time_string(166.0), -1.33),
-            ("text6".to_owned(), seconds_to_date_time_string(150.0), -2.66),
-            ("text7".to_owned(), seconds_to_date_time_string(250.0), -2.0)
-        ];
-
-        for (expected, actual) in expected_coordinates.iter().zip(iterator) {
-            assert_eq!(expected, &actual);
-        }
+    fn multiple_points_per_stage() {
+        let stage_names = ["stageA", "stageB", "stageA", "stageA", "stageB", "stageB", "stageC"];
+        let layout = layout_missed_actions(&stage_names, &stage_spans(), (300.0, 315.0), ICON_WIDTH, &settings());
+        assert_eq!(layout.y_max, -6.0);
+        assert_eq!(layout.placements, vec![
+            placement(33.0, -2.0, 1.0),
+            placement(133.0, -2.0, 1.0),
+            placement(66.0, -2.0, 1.0),
+            placement(50.0, -4.0, 1.0),
+            placement(166.0, -2.0, 1.0),
+            placement(150.0, -4.0, 1.0),
+            placement(250.0, -3.0, 1.0),
+        ]);
     }
 
     #[test]
-    fn empty() {
-        let hover_text: Vec<String> = vec![];
-        let stages: Vec<(u32,String)> = vec![];
-        let rectangle_map: HashMap<String, Rectangle> = HashMap::new();
-        let rectangle_point_counts: HashMap<String, u16> = HashMap::new();
+    fn stages_without_a_shape_use_the_overflow_span() {
+        let layout = layout_missed_actions(&["stageA", "Unknown", "Other"], &stage_spans(), (300.0, 330.0), ICON_WIDTH, &settings());
+        assert!(layout.uses_overflow);
+        assert_eq!(layout.placements.len(), 3);
+        assert_eq!(layout.placements[1], placement(310.0, -2.0, 1.0));
+        assert_eq!(layout.placements[2], placement(320.0, -2.0, 1.0));
+    }
+
+    #[test]
+    fn crowded_stages_shrink_and_wrap() {
+        let spans = HashMap::from([("short", (0.0, 24.0))]);
+        let layout = layout_missed_actions(&["short"; 2], &spans, (24.0, 40.0), ICON_WIDTH, &settings());
+        assert_eq!(layout.placements.iter().map(|placement| placement.icon_scale).collect::<Vec<_>>(), vec![0.8, 0.8]);
+
+        let spans = HashMap::from([("short", (0.0, 12.0))]);
+        let layout = layout_missed_actions(&["short"; 3], &spans, (12.0, 40.0), ICON_WIDTH, &settings());
+        assert_eq!(layout.placements.len(), 3);
+        assert!(layout.placements.iter().all(|placement| placement.icon_scale == 0.6));
+        // one icon per row, the area grows as if the stage had filled two rows and started the third
+        assert_eq!(layout.y_max, settings().calculate_y_max(5));
+    }
 
-        let mut iterator = MissedActionsCoordinatesIterator::new(&hover_text, &stages, &rectangle_map, &rectangle_point_counts, MAX_POINTS_PER_ROW);
+    #[test]
+    fn empty() {
+        let layout = layout_missed_actions::<&str>(&[], &HashMap::new(), (0.0, 15.0), ICON_WIDTH, &settings());
+        assert!(layout.placements.is_empty());
+        assert!(!layout.uses_overflow);
+    }
 
-        assert!(iterator.next().is_none());
+    #[test]
+    fn fit_points_in_stage_never_divides_by_zero() {
+        assert_eq!(fit_points_in_stage(0, 0.0, 0.0, 0), StageFit { columns: 1, rows: 1, icon_scale: 1.0 });
+        assert_eq!(fit_points_in_stage(4, 0.0, ICON_WIDTH, 0), StageFit { columns: 1, rows: 4, icon_scale: MIN_ICON_SCALE });
+        assert_eq!(fit_points_in_stage(u16::MAX, 10.0, ICON_WIDTH, u16::MAX).rows, u16::MAX);
     }
 }
 #[cfg(test)]
@@ -368,7 +400,7 @@ mod tests_utils {
 
         #[test]
         fn single_point() {
-            let rectangle = Rectangle { x0: 0.0, x1: 100.0, y0: 0.0, y1: 100.0, name: "stageA".to_owned() };
+            let rectangle = Rectangle { x0: 0.0, x1: 100.0, y0: 0.0, y1: 100.0 };
             let points_per_row = vec![1];
             let expected_coordinates = vec![(seconds_to_date_time_string(50.0), 50.0)];
             assert_eq!(calculate_point_coordinates(&rectangle, &points_per_row), expected_coordinates);
@@ -376,7 +408,7 @@ mod tests_utils {
 
         #[test]
         fn multiple_points_full_rows() {
-            let rectangle = Rectangle { x0: 0.0, x1: 100.0, y0: -1.0, y1: -4.0, name: "stageA".to_owned() };
+            let rectangle = Rectangle { x0: 0.0, x1: 100.0, y0: -1.0, y1: -4.0 };
             let points_per_row = vec![2, 2];
             let expected_coordinates = vec![
                 (seconds_to_date_time_string(33.0), -2.0),
@@ -389,7 +421,7 @@ mod tests_utils {
 
         #[test]
         fn multiple_points_incomplete_row() {
-            let rectangle = Rectangle { x0: 0.0, x1: 100.0, y0: -1.0, y1: -4.0, name: "stageA".to_owned() };
+            let rectangle = Rectangle { x0: 0.0, x1: 100.0, y0: -1.0, y1: -4.0 };
             let points_per_row = vec![2, 1];
             let expected_coordinates = vec![
                 (seconds_to_date_time_string(33.0), -2.0),
@@ -401,7 +433,7 @@ mod tests_utils {
 
         #[test]
         fn zero_points() {
-            let rectangle = Rectangle { x0: 0.0, x1: 100.0, y0: 0.0, y1: 100.0, name: "stageA".to_owned() };
+            let rectangle = Rectangle { x0: 0.0, x1: 100.0, y0: 0.0, y1: 100.0 };
             let points_per_row = vec![0];
             assert!(calculate_point_coordinates(&rectangle, &points_per_row).is_empty());
         }
@@ -1,5 +1,7 @@
 mod compression_line;
 pub mod plot_data;
-mod builders;
+pub(crate) mod builders;
+pub mod icon_sizing;
 pub mod transformers;
-mod missed_action_coordinates_calculator;
\ No newline at end of file
+pub(crate) mod missed_action_coordinates_calculator;
+pub mod renderer;
\ No newline at end of file
@@ -1,12 +1,15 @@
 use crate::actions::builders::{create_compression_line, create_plot_actions_section_annotation};
 use crate::actions::compression_line::CompressionLine;
-use crate::actions::missed_action_coordinates_calculator::{seconds_to_date_time_string, MissedActionsCoordinatesIterator, Rectangle};
+use crate::actions::icon_sizing::{IconSize, IconSizing};
+use crate::actions::missed_action_coordinates_calculator::{layout_missed_actions, seconds_to_date_time_string};
 use crate::actions::plot_data::ActionsPlotDataItem::{Lines, Points};
+use crate::config::layout_settings::{LayoutSettings, Theme};
 use crate::config::plotly_mappings::PlotlyConfig;
 use crate::font::Font;
 use crate::image::Image;
 use crate::layout::Layout;
 use crate::shape::Shape;
+use crate::value::PlotValue;
 use mteam_dashboard_action_processor::plot_structures::PlotLocation;
 use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
 use serde::{Deserialize, Serialize};
@@ -21,8 +24,8 @@ pub struct Marker {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ActionsPlotSeries {
-    pub x: Vec<String>,
-    pub y: Vec<String>,
+    pub x: Vec<PlotValue>,
+    pub y: Vec<PlotValue>,
     pub text: Vec<String>,
     pub mode: String,
     #[serde(rename = "type")]
@@ -36,19 +39,17 @@ pub struct ActionsPlotSeries {
     #[serde(skip)]
     pub images: Vec<Image>,
     #[serde(skip)]
-    pub stages: Vec<(u32, String)>,
-    #[serde(skip)]
-    pub stage_action_counts: HashMap<String, u16>
+    pub stages: Vec<(u32, String)>
 }
 
 impl ActionsPlotSeries {
-    pub fn new() -> Self {
+    pub fn new(settings: &LayoutSettings) -> Self {
         ActionsPlotSeries {
             customdata: Vec::new(),
             hoverinfo: "text".to_owned(),
             hovertext: Vec::new(),
             marker: Marker{
-                size: 24,
+                size: settings.marker_size,
                 symbol: "square".to_owned(),
                 color: Vec::new()
             },
@@ -58,12 +59,11 @@ impl ActionsPlotSeries {
             series_type: "text".to_owned(),
             text: Vec::new(),
             textfont: Font {
-                size: 10, color: None, family: None, weight: None
+                size: settings.action_text_font_size, color: None, family: None, weight: None
             },
             textposition: "bottom center".to_owned(),
             images: Vec::new(),
-            stages: Vec::new(),
-            stage_action_counts: HashMap::new()
+            stages: Vec::new()
         }
     }
 }
@@ -81,6 +81,10 @@ pub struct ActionsPlotData {
     pub layout: Layout,
     #[serde(rename = "actionGroupIcons")]
     pub action_group_icons: BTreeMap<String, String>,
+    /// Icon sizes at the configured zoom levels of the time axis, the frontend swaps `sizex` and `sizey` of the
+    /// action images when zooming.
+    #[serde(rename = "iconSizes", default, skip_serializing_if = "Vec::is_empty")]
+    pub icon_sizes: Vec<IconSize>,
 }
 
 #[derive(Clone)]
@@ -98,37 +102,41 @@ pub struct ActionsPlotDataCollector <'a> {
     pub performed_action_groups: BTreeMap<String, ActionGroup>,
     pub x_max_seconds: usize,
     pub y_max: f32,
-    pub plotly_config: &'a PlotlyConfig
+    pub plotly_config: &'a PlotlyConfig,
+    pub theme: &'a Theme,
+    pub icon_sizes: Vec<IconSize>
 }
 
 impl<'a> ActionsPlotDataCollector<'a> {
-    pub fn new(plotly_config: &'a PlotlyConfig) -> Self {
+    pub fn new(plotly_config: &'a PlotlyConfig, theme: &'a Theme) -> Self {
+        let layout_settings = &plotly_config.layout_settings;
         Self {
-            actions_series: ActionsPlotSeries::new(),
-            missed_actions_series: ActionsPlotSeries::new(),
+            actions_series: ActionsPlotSeries::new(layout_settings),
+            missed_actions_series: ActionsPlotSeries::new(layout_settings),
             scatter_data: Vec::new(),
-            layout: Layout::new(),
+            layout: Layout::new(layout_settings, theme),
             performed_action_groups: BTreeMap::new(),
             x_max_seconds: 0, // will be calculated while processing
             y_max: plotly_config.action_plot_settings.y_increment*2.0, //this is y_max value of actions, will be used to assign the starting y value for the first action group points, immutable
-            plotly_config
+            plotly_config,
+            theme,
+            icon_sizes: Vec::new()
         }
     }
     pub fn increment_y_max(&mut self) {
         self.y_max += self.plotly_config.action_plot_settings.y_increment;
     }
 
-    pub fn select_stage_color(&self, index: usize) -> String {
-        let colors = &self.plotly_config.stages.colors;
-        colors.get(index % colors.len()).cloned().unwrap_or_else(|| "#ffffff".to_owned())
+    pub fn select_stage_color(&self, stage_name: &str) -> String {
+        self.plotly_config.stages.stage_color(stage_name, self.theme.stage_colors.as_deref())
     }
 
     pub fn map_stage_name(&self, stage_name: &str) -> String {
-        self.plotly_config.stages.names.get(stage_name).cloned().unwrap_or_else(|| stage_name.to_owned())
+        self.plotly_config.stages.display_name(stage_name)
     }
     
     pub fn add_compression_line(&mut self, start: PlotLocation, end: PlotLocation){
-        let compression_line = create_compression_line(start, end, self.plotly_config.action_plot_settings.y_increment.to_string());
+        let compression_line = create_compression_line(start, end, self.plotly_config.action_plot_settings.y_increment.into(), self.theme);
         self.scatter_data.push(Lines(compression_line));   
     }
     
@@ -139,8 +147,7 @@ impl<'a> ActionsPlotDataCollector<'a> {
 
     pub fn add_missed_action_stage(&mut self, stage: &(u32, String)) {
         let mapped_stage_name = self.map_stage_name(&stage.1);
-        self.missed_actions_series.stages.push((stage.0, mapped_stage_name.clone()));
-        *self.missed_actions_series.stage_action_counts.entry(mapped_stage_name).or_insert(0) += 1;
+        self.missed_actions_series.stages.push((stage.0, mapped_stage_name));
     }
 
     pub fn get_y_for_action_group(&mut self, group_name: &String) -> f32{
@@ -165,49 +172,63 @@ impl<'a> ActionsPlotDataCollector<'a> {
     }
 
     pub fn update_y_coordinates(&mut self) {
-        let max_missed_actions_count_per_stage = self.missed_actions_series.stage_action_counts.values().max().unwrap_or(&0);
-        let missed_actions_y_max = self.plotly_config.action_plot_settings.missed_actions.calculate_y_max(*max_missed_actions_count_per_stage); // TODO: need to work on keeping in sync with y-axis range values
+        let settings = &self.plotly_config.action_plot_settings;
+        // keyed by the stage number too, a stage that repeats has a span for each occurrence
+        let stage_spans: HashMap<(u32, &str), (f32, f32)> = self.layout.shapes.iter()
+            .map(|shape| ((shape.location.0.stage.0, shape.name.as_str()), (shape.location.0.timestamp.total_seconds as f32, shape.location.1.timestamp.total_seconds as f32)))
+            .collect();
+        let x_end_secs = (self.x_max_seconds + settings.x_axis_padding_secs) as f32;
+        let icon_settings = &self.plotly_config.layout_settings.icon;
+        let actions_y_span = (self.y_max + 2.0*settings.y_increment - settings.y_min) as f64;
+        let icon_sizing = IconSizing::new(x_end_secs as f64 * 1000.0, actions_y_span, settings.y_increment as f64, &self.actions_series.x, &self.actions_series.y);
+        let icon_size = icon_sizing.size(icon_settings, 1.0);
+        self.icon_sizes = icon_sizing.zoom_level_sizes(icon_settings);
+        // the stage icons are the only layout images so far
+        for image in self.actions_series.images.iter_mut().chain(self.missed_actions_series.images.iter_mut()).chain(self.layout.images.iter_mut()) {
+            image.sizex = icon_size.size_x;
+            image.sizey = icon_size.size_y;
+        }
+        let icon_width_secs = (icon_size.size_x / 1000.0) as f32;
+        // missed actions of stages without a stage shape are placed after the last stage
+        let overflow_span = (self.x_max_seconds as f32, x_end_secs.max(self.x_max_seconds as f32 + 2.0 * icon_width_secs));
+        let missed_stages: Vec<(u32, &str)> = self.missed_actions_series.stages.iter().map(|(number, name)| (*number, name.as_str())).collect();
+        let missed_actions_layout = layout_missed_actions(&missed_stages, &stage_spans, overflow_span, icon_width_secs, &settings.missed_actions);
+        let missed_actions_y_max = missed_actions_layout.y_max;
 
-        self.layout.yaxis.range.push(missed_actions_y_max.to_string());
-        self.layout.yaxis.range.push((self.y_max + 2.0*self.plotly_config.action_plot_settings.y_increment).to_string());
+        self.layout.yaxis.range.push(missed_actions_y_max.into());
+        self.layout.yaxis.range.push((self.y_max + 2.0*settings.y_increment).into());
         
         let mut missed_actions_stages_shapes: Vec<Shape> = Vec::new();
-        let mut missed_actions_rectangles: HashMap<String, Rectangle> = HashMap::new();
         
-        self.layout.shapes.iter_mut().enumerate().for_each(|(action_shape_index, action_shape)| {
-            action_shape.y0 = self.plotly_config.action_plot_settings.y_min.to_string();
-            action_shape.y1 = (self.y_max + self.plotly_config.action_plot_settings.y_increment).to_string();
+        for action_shape in self.layout.shapes.iter_mut() {
+            action_shape.y0 = settings.y_min.into();
+            action_shape.y1 = (self.y_max + settings.y_increment).into();
             
             let mut missed_actions_shape = action_shape.clone();
-            missed_actions_shape.y0 = self.plotly_config.action_plot_settings.missed_actions.y_min.to_string();
-            missed_actions_shape.y1 = (missed_actions_y_max+2.5*self.plotly_config.action_plot_settings.missed_actions.y_increment).to_string();
-            missed_actions_stages_shapes.push(missed_actions_shape.clone());
-
-            let missed_actions_rectangle = Rectangle{
-                name: missed_actions_shape.name,
-                x0: missed_actions_shape.location.0.timestamp.total_seconds as f32,
-                x1: missed_actions_shape.location.1.timestamp.total_seconds as f32,
-                y0: self.plotly_config.action_plot_settings.missed_actions.y_min,
-                y1: missed_actions_y_max
-            };
-            missed_actions_rectangles.insert(missed_actions_rectangle.name.clone(), missed_actions_rectangle);
-            
-            self.layout.annotations[action_shape_index].y = self.plotly_config.action_plot_settings.y_annotation;
-        });
-        
-        let missed_action_calculation_iterator = MissedActionsCoordinatesIterator::new(&self.missed_actions_series.hovertext, &self.missed_actions_series.stages ,&missed_actions_rectangles, &self.missed_actions_series.stage_action_counts, self.plotly_config.action_plot_settings.missed_actions.max_count_per_row as usize);
-        missed_action_calculation_iterator.enumerate().for_each(|(action_index, missed_action)| {
-            self.missed_actions_series.x.push(missed_action.1.clone());
-            self.missed_actions_series.y.push(missed_action.2.to_string());
-            self.missed_actions_series.images[action_index].x = missed_action.1.clone();
-            self.missed_actions_series.images[action_index].y = missed_action.2.to_string();
-        });
+            missed_actions_shape.y0 = settings.missed_actions.y_min.into();
+            missed_actions_shape.y1 = (missed_actions_y_max+2.5*settings.missed_actions.y_increment).into();
+            missed_actions_stages_shapes.push(missed_actions_shape);
+        }
+        // the first annotations are the stage names, one for every stage shape
+        self.layout.annotations.iter_mut().take(missed_actions_stages_shapes.len()).for_each(|annotation| annotation.y = settings.y_annotation);
+
+        for (placement, image) in missed_actions_layout.placements.into_iter().zip(self.missed_actions_series.images.iter_mut()) {
+            let x = PlotValue::date_time(&placement.x);
+            let y = PlotValue::from(placement.y);
+            self.missed_actions_series.x.push(x.clone());
+            self.missed_actions_series.y.push(y.clone());
+            image.x = x;
+            image.y = y;
+            image.sizex *= placement.icon_scale as f64;
+            image.sizey *= placement.icon_scale as f64;
+        }
         self.layout.images.extend(self.actions_series.images.clone());
         self.layout.images.extend(self.missed_actions_series.images.clone());
         self.layout.shapes.extend(missed_actions_stages_shapes);
         
-        self.layout.xaxis.range.push(seconds_to_date_time_string(0f32));
-        self.layout.xaxis.range.push(seconds_to_date_time_string((self.x_max_seconds + self.plotly_config.action_plot_settings.x_axis_padding_secs) as f32));
+        let x_end_secs = if missed_actions_layout.uses_overflow { overflow_span.1 } else { x_end_secs };
+        self.layout.xaxis.range.push(PlotValue::date_time(&seconds_to_date_time_string(0f32)));
+        self.layout.xaxis.range.push(PlotValue::date_time(&seconds_to_date_time_string(x_end_secs)));
     }
     
     pub fn to_plot_data(mut self) -> ActionsPlotData {
@@ -218,9 +239,9 @@ impl<'a> ActionsPlotDataCollector<'a> {
             .map(|(key, value)| (key, value.icon))
             .collect();
 
-        let annotation_x = seconds_to_csv_row_time(0).date_string;
+        let annotation_x = PlotValue::date_time(&seconds_to_csv_row_time(0).date_string);
 
-        let performed_actions_annotation = create_plot_actions_section_annotation("Performed Actions".to_owned(), annotation_x.clone(), 0.970, "paper".to_owned());
+        let performed_actions_annotation = create_plot_actions_section_annotation("Performed Actions".to_owned(), annotation_x.clone(), 0.970, "paper".to_owned(), &self.plotly_config.layout_settings);
 
         //     create_stage_annotation("Performed Actions".to_owned());
         // performed_actions_annotation.x=annotation_x.clone();
@@ -230,7 +251,7 @@ impl<'a> ActionsPlotDataCollector<'a> {
         // performed_actions_annotation.font.size=14;
         // performed_actions_annotation.font.weight=Some(550);
 
-        let missed_actions_annotation = create_plot_actions_section_annotation("Missed Actions".to_owned(), annotation_x, self.plotly_config.action_plot_settings.missed_actions.y_min+0.3, "y".to_owned());
+        let missed_actions_annotation = create_plot_actions_section_annotation("Missed Actions".to_owned(), annotation_x, self.plotly_config.action_plot_settings.missed_actions.y_min+0.3, "y".to_owned(), &self.plotly_config.layout_settings);
 
         //     create_stage_annotation("Missed Actions".to_owned());
         // missed_actions_annotation.x=annotation_x;
@@ -246,7 +267,8 @@ impl<'a> ActionsPlotDataCollector<'a> {
         ActionsPlotData {
             data: self.scatter_data,
             layout: self.layout,
-            action_group_icons: action_groups
+            action_group_icons: action_groups,
+            icon_sizes: self.icon_sizes
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,510 @@
+use crate::actions::compression_line::CompressionLine;
+use crate::actions::plot_data::{ActionsPlotData, ActionsPlotDataItem, ActionsPlotSeries};
+use crate::annotation::Annotation;
+use crate::image::Image;
+use crate::layout::Layout;
+use crate::shape::Shape;
+use crate::value::PlotValue;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{Duration, NaiveDateTime};
+use once_cell::sync::Lazy;
+use resvg::{tiny_skia, usvg};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use usvg::{TreeParsing, TreePostProc};
+
+const FONT_FAMILY: &str = "Arial, sans-serif";
+const TEXT_COLOR: &str = "#444444";
+const TITLE_FONT_SIZE: f64 = 17.0;
+const TICK_FONT_SIZE: f64 = 12.0;
+/// Approximate width of a character relative to the font size, used to size the annotation backgrounds.
+const CHAR_WIDTH_RATIO: f64 = 0.6;
+const TICK_STEPS_SECS: [i64; 10] = [10, 15, 30, 60, 120, 300, 600, 900, 1800, 3600];
+
+/// Used for the generic sans-serif family when Arial (the fontdb default) is not installed, e.g. on Linux servers.
+const FALLBACK_SANS_SERIF_FAMILIES: [&str; 3] = ["Helvetica", "Liberation Sans", "DejaVu Sans"];
+
+/// Largest PNG the renderer produces, `width * height * scale²` pixels (about 64 MB of RGBA).
+pub const MAX_PNG_PIXELS: u64 = 16_000_000;
+
+static FONT_DATABASE: Lazy<usvg::fontdb::Database> = Lazy::new(|| {
+    let mut database = usvg::fontdb::Database::new();
+    database.load_system_fonts();
+    let families: Vec<String> = database.faces()
+        .filter_map(|face| face.families.first().map(|(family, _)| family.clone()))
+        .collect();
+    if let Some(family) = sans_serif_fallback(&families) {
+        database.set_sans_serif_family(family);
+    }
+    database
+});
+
+/// System fonts with a sans-serif fallback, shared with the other SVG conversions (e.g. the PDF report).
+pub fn font_database() -> &'static usvg::fontdb::Database {
+    &FONT_DATABASE
+}
+
+/// Family to use for the generic sans-serif family among the installed font `families`, `None` when Arial is installed.
+fn sans_serif_fallback(families: &[String]) -> Option<String> {
+    if families.iter().any(|family| family == "Arial") {
+        return None;
+    }
+    FALLBACK_SANS_SERIF_FAMILIES.iter()
+        .find(|fallback| families.iter().any(|family| family == *fallback))
+        .map(|fallback| fallback.to_string())
+        .or_else(|| families.first().cloned())
+}
+
+/// Size of the rendered timeline and where to find the action group icons.
+pub struct RenderOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Pixel density of the PNG output, 2.0 renders twice the width and height.
+    pub scale: f32,
+    /// Root of the icon paths in the plot configuration (e.g. `/icons/cpr.png`), the frontend static files folder.
+    /// Icons are left out when `None` or when the file can not be read.
+    pub icon_dir: Option<PathBuf>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self { width: 1600, height: 800, scale: 1.0, icon_dir: None }
+    }
+}
+
+impl RenderOptions {
+    /// Number of pixels of the PNG output.
+    pub fn png_pixels(&self) -> u64 {
+        (self.width as f64 * self.height as f64 * (self.scale as f64).powi(2)).round() as u64
+    }
+}
+
+/// Maps the data coordinates of the timeline to SVG pixels.
+struct Axes {
+    x_start: NaiveDateTime,
+    x_span_secs: f64,
+    y_min: f64,
+    y_max: f64,
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Axes {
+    fn new(layout: &Layout, options: &RenderOptions) -> Self {
+        let x_range: Vec<NaiveDateTime> = layout.xaxis.range.iter().filter_map(PlotValue::as_date_time).collect();
+        let x_start = x_range.first().copied().unwrap_or_default();
+        let x_span_secs = x_range.get(1)
+            .map(|end| (*end - x_start).num_milliseconds() as f64 / 1000.0)
+            .filter(|span| *span > 0.0)
+            .unwrap_or(60.0);
+        let y_range: Vec<f64> = layout.yaxis.range.iter().filter_map(PlotValue::as_number).collect();
+        let (y_min, y_max) = match y_range.as_slice() {
+            [first, second, ..] if first != second => (first.min(*second), first.max(*second)),
+            _ => (0.0, 1.0),
+        };
+        let left = layout.margin.l;
+        let top = layout.margin.t;
+        Self {
+            x_start,
+            x_span_secs,
+            y_min,
+            y_max,
+            left,
+            top,
+            width: (options.width as f64 - left - layout.margin.r).max(1.0),
+            height: (options.height as f64 - top - layout.margin.b).max(1.0),
+        }
+    }
+
+    fn x_secs(&self, value: &PlotValue) -> Option<f64> {
+        match value {
+            PlotValue::DateTime(date_time) => Some((*date_time - self.x_start).num_milliseconds() as f64 / 1000.0),
+            // numbers on a date axis are milliseconds
+            PlotValue::Number(milliseconds) => Some(milliseconds / 1000.0),
+            PlotValue::Category(_) => None,
+        }
+    }
+
+    fn x(&self, value: &PlotValue) -> Option<f64> {
+        self.x_secs(value).map(|secs| self.left + secs / self.x_span_secs * self.width)
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        self.top + (self.y_max - value) / (self.y_max - self.y_min) * self.height
+    }
+
+    fn y_ref(&self, value: f64, yref: &str) -> f64 {
+        if yref == "paper" {
+            self.top + (1.0 - value) * self.height
+        } else {
+            self.y(value)
+        }
+    }
+
+    /// Pixel length of `size` x axis units (milliseconds).
+    fn x_size(&self, size: f64) -> f64 {
+        size / 1000.0 / self.x_span_secs * self.width
+    }
+
+    fn y_size(&self, size: f64) -> f64 {
+        size / (self.y_max - self.y_min) * self.height
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// SVG paint attributes of a Plotly color, `#rrggbbaa` colors are split into a color and an opacity since not every
+/// SVG viewer supports them.
+fn paint(attribute: &str, color: &str) -> String {
+    if color.is_empty() || color == "#" {
+        return format!("{}=\"none\"", attribute);
+    }
+    if color.len() == 9 && color.starts_with('#') {
+        if let Ok(alpha) = u8::from_str_radix(&color[7..], 16) {
+            return format!("{}=\"{}\" {}-opacity=\"{:.3}\"", attribute, &color[..7], attribute, alpha as f64 / 255.0);
+        }
+    }
+    format!("{}=\"{}\"", attribute, escape(color))
+}
+
+fn non_empty(value: Option<&String>) -> Option<&str> {
+    value.map(String::as_str).filter(|value| !value.is_empty())
+}
+
+/// Reads an icon of the plot configuration as a data URI so that the SVG is self-contained.
+pub fn icon_data_uri(source: &str, icon_dir: &Path) -> Option<String> {
+    let relative_path = Path::new(source.trim_start_matches('/'));
+    if relative_path.components().any(|component| !matches!(component, Component::Normal(_))) {
+        return None;
+    }
+    let mime_type = match relative_path.extension()?.to_str()?.to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    };
+    let content = fs::read(icon_dir.join(relative_path)).ok()?;
+    Some(format!("data:{};base64,{}", mime_type, STANDARD.encode(content)))
+}
+
+fn write_shape(svg: &mut String, shape: &Shape, axes: &Axes) {
+    let (Some(x0), Some(x1), Some(y0), Some(y1)) = (axes.x(&shape.x0), axes.x(&shape.x1), shape.y0.as_number(), shape.y1.as_number()) else {
+        return;
+    };
+    let (y0, y1) = (axes.y(y0), axes.y(y1));
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" {}/>"#,
+        x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs(), paint("fill", &shape.fillcolor)
+    );
+}
+
+fn write_compression_line(svg: &mut String, line: &CompressionLine, axes: &Axes, text_color: &str) {
+    let points: Vec<String> = line.x.iter().zip(&line.y)
+        .filter_map(|(x, y)| Some(format!("{:.1},{:.1}", axes.x(x)?, axes.y(y.as_number()?))))
+        .collect();
+    let color = non_empty(line.line.color.as_ref()).unwrap_or(text_color);
+    let _ = writeln!(
+        svg,
+        r#"<polyline points="{}" fill="none" {} stroke-width="{}"/>"#,
+        points.join(" "), paint("stroke", color), line.line.width.unwrap_or(2)
+    );
+}
+
+fn write_points(svg: &mut String, series: &ActionsPlotSeries, axes: &Axes, text_color: &str) {
+    let size = series.marker.size as f64;
+    let font_size = series.textfont.size as f64;
+    for (index, (x, y)) in series.x.iter().zip(&series.y).enumerate() {
+        let (Some(x), Some(y)) = (axes.x(x), y.as_number().map(|y| axes.y(y))) else {
+            continue;
+        };
+        let color = series.marker.color.get(index).map(String::as_str).unwrap_or(text_color);
+        if series.marker.symbol == "square" {
+            let _ = writeln!(svg, r#"<rect x="{:.1}" y="{:.1}" width="{size}" height="{size}" {}/>"#, x - size / 2.0, y - size / 2.0, paint("fill", color));
+        } else {
+            let _ = writeln!(svg, r#"<circle cx="{:.1}" cy="{:.1}" r="{}" {}/>"#, x, y, size / 2.0, paint("fill", color));
+        }
+        if let Some(text) = series.text.get(index).filter(|text| !text.is_empty()) {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" font-size="{font_size}" text-anchor="middle" fill="{text_color}">{}</text>"#,
+                x, y + size / 2.0 + font_size, escape(text)
+            );
+        }
+    }
+}
+
+fn write_image(svg: &mut String, image: &Image, axes: &Axes, icon_dir: &Path) {
+    let (Some(x), Some(y)) = (axes.x(&image.x), image.y.as_number().map(|y| axes.y(y))) else {
+        return;
+    };
+    let Some(data_uri) = icon_data_uri(&image.source, icon_dir) else {
+        return;
+    };
+    let width = axes.x_size(image.sizex);
+    let height = axes.y_size(image.sizey);
+    let x = match image.xanchor.as_str() {
+        "left" => x,
+        "right" => x - width,
+        _ => x - width / 2.0,
+    };
+    let y = match image.yanchor.as_str() {
+        "top" => y,
+        "bottom" => y - height,
+        _ => y - height / 2.0,
+    };
+    let _ = writeln!(
+        svg,
+        r#"<image x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" preserveAspectRatio="xMidYMid meet" href="{}"/>"#,
+        x, y, width, height, data_uri
+    );
+}
+
+fn write_annotation(svg: &mut String, annotation: &Annotation, axes: &Axes, text_color: &str) {
+    let Some(x) = axes.x(&annotation.x) else {
+        return;
+    };
+    let y = axes.y_ref(annotation.y as f64, &annotation.yref);
+    let font_size = annotation.font.size as f64;
+    let padding = annotation.borderpad as f64;
+    let box_width = annotation.text.chars().count() as f64 * font_size * CHAR_WIDTH_RATIO + 2.0 * padding;
+    let box_height = font_size * 1.2 + 2.0 * padding;
+    let box_x = match annotation.xanchor.as_str() {
+        "right" => x - box_width,
+        "center" => x - box_width / 2.0,
+        _ => x,
+    };
+    let box_y = match annotation.yanchor.as_str() {
+        "top" => y,
+        "bottom" => y - box_height,
+        _ => y - box_height / 2.0,
+    };
+    if !annotation.bgcolor.is_empty() || (!annotation.bordercolor.is_empty() && annotation.borderwidth > 0) {
+        let border = if annotation.bordercolor.is_empty() || annotation.borderwidth == 0 {
+            String::new()
+        } else {
+            format!(" {} stroke-width=\"{}\"", paint("stroke", &annotation.bordercolor), annotation.borderwidth)
+        };
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" {}{}/>"#,
+            box_x, box_y, box_width, box_height, paint("fill", &annotation.bgcolor), border
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" font-family="{}" font-size="{}" font-weight="{}" dominant-baseline="central" {}>{}</text>"#,
+        box_x + padding,
+        box_y + box_height / 2.0,
+        escape(non_empty(annotation.font.family.as_ref()).unwrap_or(FONT_FAMILY)),
+        font_size,
+        annotation.font.weight.unwrap_or(400),
+        paint("fill", non_empty(annotation.font.color.as_ref()).unwrap_or(text_color)),
+        escape(&annotation.text)
+    );
+}
+
+fn write_x_axis(svg: &mut String, layout: &Layout, axes: &Axes, text_color: &str) {
+    let axis_y = axes.top + axes.height;
+    let _ = writeln!(
+        svg,
+        r#"<line x1="{:.1}" y1="{axis_y:.1}" x2="{:.1}" y2="{axis_y:.1}" stroke="{text_color}"/>"#,
+        axes.left, axes.left + axes.width
+    );
+    let max_tick_count = (axes.width / 100.0).max(1.0);
+    let step_secs = TICK_STEPS_SECS.iter()
+        .copied()
+        .find(|step| axes.x_span_secs / *step as f64 <= max_tick_count)
+        .unwrap_or(*TICK_STEPS_SECS.last().unwrap());
+    let mut secs = 0;
+    while secs as f64 <= axes.x_span_secs {
+        let tick = axes.x_start + Duration::seconds(secs);
+        let x = axes.left + secs as f64 / axes.x_span_secs * axes.width;
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{x:.1}" y1="{axis_y:.1}" x2="{x:.1}" y2="{:.1}" stroke="{text_color}"/><text x="{x:.1}" y="{:.1}" font-size="{TICK_FONT_SIZE}" text-anchor="middle" fill="{text_color}">{}</text>"#,
+            axis_y + 5.0, axis_y + 5.0 + TICK_FONT_SIZE, tick.format(&layout.xaxis.tickformat)
+        );
+        secs += step_secs;
+    }
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" font-size="{TICK_FONT_SIZE}" text-anchor="middle" fill="{text_color}">{}</text>"#,
+        axes.left + axes.width / 2.0, axis_y + 10.0 + 2.0 * TICK_FONT_SIZE, escape(&layout.xaxis.title)
+    );
+}
+
+/// Draws the clinical review timeline the way the frontend plots it with Plotly.js: stage rectangles, CPR lines,
+/// action markers, icons, stage annotations and the time axis.
+pub fn render_actions_svg(plot_data: &ActionsPlotData, options: &RenderOptions) -> String {
+    let layout = &plot_data.layout;
+    let axes = Axes::new(layout, options);
+    let font = layout.font.as_ref();
+    let text_color = non_empty(font.and_then(|font| font.color.as_ref())).unwrap_or(TEXT_COLOR);
+    let font_family = non_empty(font.and_then(|font| font.family.as_ref())).unwrap_or(FONT_FAMILY);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="{2}">"#,
+        options.width, options.height, escape(font_family)
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" {}/>"#, paint("fill", non_empty(layout.paper_bgcolor.as_ref()).unwrap_or("white")));
+    if let Some(plot_background_color) = non_empty(layout.plot_bgcolor.as_ref()) {
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" {}/>"#,
+            axes.left, axes.top, axes.width, axes.height, paint("fill", plot_background_color)
+        );
+    }
+
+    layout.shapes.iter().filter(|shape| shape.layer == "below").for_each(|shape| write_shape(&mut svg, shape, &axes));
+    for item in &plot_data.data {
+        match item {
+            ActionsPlotDataItem::Lines(line) => write_compression_line(&mut svg, line, &axes, text_color),
+            ActionsPlotDataItem::Points(series) => write_points(&mut svg, series, &axes, text_color),
+        }
+    }
+    layout.shapes.iter().filter(|shape| shape.layer != "below").for_each(|shape| write_shape(&mut svg, shape, &axes));
+    if let Some(icon_dir) = &options.icon_dir {
+        layout.images.iter().filter(|image| image.visible).for_each(|image| write_image(&mut svg, image, &axes, icon_dir));
+    }
+    layout.annotations.iter().for_each(|annotation| write_annotation(&mut svg, annotation, &axes, text_color));
+    write_x_axis(&mut svg, layout, &axes, text_color);
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" font-size="{TITLE_FONT_SIZE}" text-anchor="middle" fill="{text_color}">{}</text>"#,
+        options.width as f64 / 2.0, (1.0 - layout.title.y) * options.height as f64 + TITLE_FONT_SIZE, escape(&layout.title.text)
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Rasterises an SVG (e.g. `render_actions_svg` output) to PNG with the system fonts, at most `MAX_PNG_PIXELS`.
+pub fn svg_to_png(svg: &str, scale: f32) -> Result<Vec<u8>, String> {
+    let mut tree = usvg::Tree::from_str(svg, &usvg::Options::default()).map_err(|e| format!("Error parsing svg: {}", e))?;
+    tree.postprocess(usvg::PostProcessingSteps::default(), &FONT_DATABASE);
+    let size = tree.size.to_int_size().scale_by(scale).ok_or_else(|| format!("Invalid png scale: {}", scale))?;
+    let pixels = size.width() as u64 * size.height() as u64;
+    if pixels > MAX_PNG_PIXELS {
+        return Err(format!("Png of {} pixels exceeds the limit of {} pixels", pixels, MAX_PNG_PIXELS));
+    }
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height()).ok_or_else(|| format!("Invalid png size: {:?}", size))?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+    pixmap.encode_png().map_err(|e| format!("Error encoding png: {}", e))
+}
+
+pub fn render_actions_png(plot_data: &ActionsPlotData, options: &RenderOptions) -> Result<Vec<u8>, String> {
+    svg_to_png(&render_actions_svg(plot_data, options), options.scale)
+}
+
+#[cfg(test)]
+mod tests_actions_renderer {
+    use super::*;
+    use crate::actions::builders::{create_compression_line, create_image, create_shape};
+    use crate::config::layout_settings::{LayoutSettings, Theme};
+    use crate::font::Font;
+    use mteam_dashboard_action_processor::plot_structures::PlotLocation;
+    use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+    use std::collections::BTreeMap;
+
+    fn location(seconds: u32) -> PlotLocation {
+        PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (1, "Stage 1".to_owned()) }
+    }
+
+    fn plot_data() -> ActionsPlotData {
+        let settings = LayoutSettings::default();
+        let mut layout = Layout::new(&settings, &Theme::default());
+        layout.xaxis.range = vec![PlotValue::date_time(&seconds_to_csv_row_time(0).date_string), PlotValue::date_time(&seconds_to_csv_row_time(100).date_string)];
+        layout.yaxis.range = vec![PlotValue::Number(-5.0), PlotValue::Number(5.0)];
+        let mut shape = create_shape(&location(0), &location(50));
+        shape.fillcolor = "#1f77b433".to_owned();
+        shape.y0 = PlotValue::Number(0.0);
+        shape.y1 = PlotValue::Number(5.0);
+        layout.shapes.push(shape);
+        layout.images.push(create_image(PlotValue::date_time(&location(10).timestamp.date_string), PlotValue::Number(2.0), "/icons/cpr.png".to_owned(), &settings.icon));
+        let mut actions = ActionsPlotSeries::new(&settings);
+        actions.x.push(PlotValue::date_time(&location(10).timestamp.date_string));
+        actions.y.push(PlotValue::Number(2.0));
+        actions.text.push("200J & <shock>".to_owned());
+        actions.marker.color.push("green".to_owned());
+        ActionsPlotData {
+            data: vec![
+                ActionsPlotDataItem::Lines(create_compression_line(location(20), location(40), PlotValue::Number(1.0), &Theme::default())),
+                ActionsPlotDataItem::Points(actions),
+            ],
+            layout,
+            action_group_icons: BTreeMap::new(),
+            icon_sizes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn svg_contains_plot_elements() {
+        let options = RenderOptions { width: 1100, height: 600, ..Default::default() };
+        let svg = render_actions_svg(&plot_data(), &options);
+        // plot area is 1000 x 550 px after the 50 px margins, the stage spans the first half of it
+        assert!(svg.contains(r##"<rect x="50.0" y="0.0" width="500.0" height="275.0" fill="#1f77b4" fill-opacity="0.200"/>"##));
+        assert!(svg.contains(r#"<polyline points="250.0,220.0 450.0,220.0""#));
+        assert!(svg.contains("200J &amp; &lt;shock&gt;"));
+        assert!(svg.contains(">00:01:40</text>"));
+        assert!(!svg.contains("<image"));
+    }
+
+    #[test]
+    fn svg_uses_theme_colors() {
+        let mut plot_data = plot_data();
+        plot_data.layout.paper_bgcolor = Some("#1e1e1e".to_owned());
+        plot_data.layout.font = Some(Font { size: 12, color: Some("#eeeeee".to_owned()), family: None, weight: None });
+        let svg = render_actions_svg(&plot_data, &RenderOptions::default());
+        assert!(svg.contains(r##"<rect width="100%" height="100%" fill="#1e1e1e"/>"##));
+        assert!(svg.contains(r##"fill="#eeeeee">Clinical Review Timeline</text>"##));
+        assert!(!svg.contains(TEXT_COLOR));
+    }
+
+    #[test]
+    fn icons_are_embedded() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let icon_dir = temp_dir.path().to_path_buf();
+        fs::create_dir_all(icon_dir.join("icons")).unwrap();
+        let icon = svg_to_png(r#"<svg xmlns="http://www.w3.org/2000/svg" width="2" height="2"><rect width="2" height="2" fill="red"/></svg>"#, 1.0).unwrap();
+        fs::write(icon_dir.join("icons/cpr.png"), icon).unwrap();
+        let options = RenderOptions { icon_dir: Some(icon_dir), ..Default::default() };
+        assert!(render_actions_svg(&plot_data(), &options).contains(r#"href="data:image/png;base64,"#));
+        assert_eq!(icon_data_uri("/../secrets.png", &options.icon_dir.unwrap()), None);
+    }
+
+    #[test]
+    fn png() {
+        let options = RenderOptions { width: 200, height: 100, scale: 2.0, icon_dir: None };
+        let png = render_actions_png(&plot_data(), &options).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        // width and height of the IHDR chunk
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 400);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 200);
+    }
+
+    #[test]
+    fn png_size_is_limited() {
+        let options = RenderOptions { width: 8000, height: 4000, scale: 4.0, icon_dir: None };
+        assert_eq!(options.png_pixels(), 512_000_000);
+        assert!(render_actions_png(&plot_data(), &options).unwrap_err().contains("exceeds the limit"));
+    }
+
+    #[test]
+    fn png_text_uses_an_installed_font() {
+        if font_database().is_empty() {
+            eprintln!("Skipped: no system fonts installed");
+            return;
+        }
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="60" height="20"><text x="0" y="15" font-family="Arial, sans-serif" font-size="14">CPR</text></svg>"#;
+        let pixmap = tiny_skia::Pixmap::decode_png(&svg_to_png(svg, 1.0).unwrap()).unwrap();
+        assert!(pixmap.pixels().iter().any(|pixel| pixel.alpha() > 0));
+    }
+}
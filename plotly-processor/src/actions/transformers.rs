@@ -1,14 +1,16 @@
+use crate::config::layout_settings::Theme;
 use crate::config::plotly_mappings::PlotlyConfig;
 use crate::actions::builders::{
     create_stage_annotation, create_image
     , create_shape,
 };
 use crate::actions::plot_data::{ActionGroup, ActionsPlotData, ActionsPlotDataCollector};
+use crate::value::PlotValue;
 use mteam_dashboard_action_processor::plot_structures::{
     Action, ActionPlotPoint, ErroneousAction, MissedAction, PeriodType, PlotLocation,
 };
 
-fn append_to_plotly_data(data_point: Result<ActionPlotPoint, String>, data_collector: &mut ActionsPlotDataCollector, stage_index: &mut usize) {
+fn append_to_plotly_data(data_point: Result<ActionPlotPoint, String>, data_collector: &mut ActionsPlotDataCollector) {
     match data_point {
         Ok(ActionPlotPoint::Action(action)) => {
             process_action(&action, data_collector);
@@ -23,25 +25,24 @@ fn append_to_plotly_data(data_point: Result<ActionPlotPoint, String>, data_colle
             process_cpr_period(start, end, data_collector);
         }
         Ok(ActionPlotPoint::Period(PeriodType::Stage, start, end)) => {
-            process_stage_period((start, end), data_collector, *stage_index);
-            *stage_index+=1;
+            process_stage_period((start, end), data_collector);
         },
         Err(_) => {}
     }
 }
 
-fn add_action(data_collector: &mut ActionsPlotDataCollector, group: ActionGroup, x: String, hover_text: String, text: String, color: String, stage: &(u32, String)){
+fn add_action(data_collector: &mut ActionsPlotDataCollector, group: ActionGroup, x: PlotValue, hover_text: String, text: String, color: String, stage: &(u32, String)){
     data_collector.actions_series.customdata.push(group.group_name);
 
     data_collector.actions_series.x.push(x.clone());
-    data_collector.actions_series.y.push(group.y_value.to_string());
+    data_collector.actions_series.y.push(group.y_value.into());
     data_collector.actions_series.hovertext.push(hover_text);
     data_collector.actions_series.text.push(text);
     data_collector.actions_series.marker.color.push(color);
 
     data_collector.add_action_stage(stage);
 
-    let image = create_image(x.clone(), group.y_value.to_string(), group.icon);
+    let image = create_image(x, group.y_value.into(), group.icon, &data_collector.plotly_config.layout_settings.icon);
     data_collector.actions_series.images.push(image);
 }
 
@@ -50,7 +51,7 @@ fn process_action(action: &Action, data_collector: &mut ActionsPlotDataCollector
     group.y_value = data_collector.get_y_for_action_group(&group.group_name);
     data_collector.performed_action_groups.insert(group.group_name.clone(), group.clone());
     let x = action.clone().location.timestamp.date_string;
-    add_action(data_collector, group, x.clone(), format!("{}, {}", action.location.timestamp.timestamp, action.name), action.clone().shock_value, "green".to_owned(), &action.location.stage);
+    add_action(data_collector, group, PlotValue::date_time(&x), format!("{}, {}", action.location.timestamp.timestamp, action.name), action.clone().shock_value, data_collector.theme.performed_action_color.clone(), &action.location.stage);
 }
 
 fn process_error(action: ErroneousAction, data_collector: &mut ActionsPlotDataCollector) {
@@ -68,7 +69,7 @@ fn process_error(action: ErroneousAction, data_collector: &mut ActionsPlotDataCo
             action.location.timestamp.timestamp, action.name, action.error_info.advice
         )
     };
-    add_action(data_collector, group, x.clone(), hover_text, action.shock_value, "red".to_owned(), &action.location.stage);
+    add_action(data_collector, group, PlotValue::date_time(&x), hover_text, action.shock_value, data_collector.theme.erroneous_action_color.clone(), &action.location.stage);
 }
 
 fn process_missed_action(action: MissedAction, data_collector: &mut ActionsPlotDataCollector) {
@@ -80,11 +81,11 @@ fn process_missed_action(action: MissedAction, data_collector: &mut ActionsPlotD
     };
 
     data_collector.missed_actions_series.hovertext.push(hover_text);
-    data_collector.missed_actions_series.marker.color.push("rgba(249, 105, 14, 0.8)".to_owned());
+    data_collector.missed_actions_series.marker.color.push(data_collector.theme.missed_action_color.clone());
 
     data_collector.add_missed_action_stage(&action.location.stage);
     
-    let image = create_image("".to_owned(), "".to_owned(), group.icon);
+    let image = create_image(PlotValue::default(), PlotValue::default(), group.icon, &data_collector.plotly_config.layout_settings.icon);
     data_collector.missed_actions_series.images.push(image);
 
 }
@@ -93,13 +94,13 @@ fn process_cpr_period(start: PlotLocation, end: PlotLocation, data_collector: &m
     data_collector.add_compression_line(start, end);
 }
 
-fn process_stage_period(period: (PlotLocation, PlotLocation), data_collector: &mut ActionsPlotDataCollector, stage_idx: usize) {
+fn process_stage_period(period: (PlotLocation, PlotLocation), data_collector: &mut ActionsPlotDataCollector) {
     let (start, end) = period;
     if end.timestamp.total_seconds > data_collector.x_max_seconds as u32 {
         data_collector.x_max_seconds = end.timestamp.total_seconds as usize;
     }
 
-    let color = data_collector.select_stage_color(stage_idx);
+    let color = data_collector.select_stage_color(&start.stage.1);
     let stage_color = color.clone()+"33";
     let annotation_color = color+"70";
     let mapped_stage_name = data_collector.map_stage_name(&start.stage.1);
@@ -108,26 +109,72 @@ fn process_stage_period(period: (PlotLocation, PlotLocation), data_collector: &m
     shape_normal.fillcolor=stage_color;
     shape_normal.name=mapped_stage_name.clone();
 
-    let mut annotation = create_stage_annotation(mapped_stage_name.clone());
+    let mut annotation = create_stage_annotation(mapped_stage_name.clone(), &data_collector.plotly_config.layout_settings, data_collector.theme);
     annotation.font.color = Some(annotation_color.clone());
     annotation.bordercolor=annotation_color;
-    annotation.x = start.timestamp.date_string;
+    annotation.x = PlotValue::date_time(&start.timestamp.date_string);
     data_collector.layout.annotations.push(annotation);
     data_collector.layout.shapes.push(shape_normal);
-}
 
-pub fn to_plotly_data(plotly_config: &PlotlyConfig, data_points: impl Iterator<Item = Result<ActionPlotPoint, String>>) -> ActionsPlotData {
-    let mut data_collector = ActionsPlotDataCollector::new(&plotly_config);
+    if let Some(icon) = data_collector.plotly_config.stages.stage_icon(&start.stage.1) {
+        let y = data_collector.plotly_config.action_plot_settings.y_min.into();
+        let mut image = create_image(PlotValue::date_time(&start.timestamp.date_string), y, icon.clone(), &data_collector.plotly_config.layout_settings.icon);
+        image.xanchor = "left".to_owned();
+        image.yanchor = "bottom".to_owned();
+        data_collector.layout.images.push(image);
+    }
+}
 
-    let mut stage_index = 0;
+pub fn to_plotly_data(plotly_config: &PlotlyConfig, theme: &Theme, data_points: impl Iterator<Item = Result<ActionPlotPoint, String>>) -> ActionsPlotData {
+    let mut data_collector = ActionsPlotDataCollector::new(&plotly_config, theme);
 
     for data_point in data_points {
             append_to_plotly_data(
                 data_point,
-                &mut data_collector,
-                &mut stage_index
+                &mut data_collector
             );
     }
 
     data_collector.to_plot_data()
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests_actions_plot_data {
+    use super::*;
+    use crate::actions::plot_data::ActionsPlotDataItem;
+    use mteam_dashboard_action_processor::plot_structures::ErrorInfo;
+    use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+    use std::path::Path;
+
+    fn location(seconds: u32, stage: (u32, &str)) -> PlotLocation {
+        PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (stage.0, stage.1.to_owned()) }
+    }
+
+    fn stage(start: u32, end: u32, stage: (u32, &str)) -> Result<ActionPlotPoint, String> {
+        Ok(ActionPlotPoint::Period(PeriodType::Stage, location(start, stage), location(end, stage)))
+    }
+
+    #[test]
+    fn missed_actions_stay_in_their_stage_occurrence() {
+        let plotly_config = PlotlyConfig::load(Path::new("../plot-config")).unwrap();
+        let error_info = ErrorInfo { action_rule: String::new(), violation: String::new(), advice: String::new() };
+        let points = vec![
+            stage(0, 100, (1, "Asystole 1D No.1")),
+            stage(100, 200, (2, "V-Tach 2D")),
+            stage(200, 300, (3, "Asystole 1D No.1")),
+            Ok(ActionPlotPoint::MissedAction(MissedAction { location: location(50, (1, "Asystole 1D No.1")), name: "Epinephrine".to_owned(), error_info })),
+        ];
+        let plot_data = to_plotly_data(&plotly_config, plotly_config.layout_settings.default_theme(), points.into_iter());
+
+        let missed_actions = plot_data.data.iter()
+            .filter_map(|item| match item {
+                ActionsPlotDataItem::Points(series) => Some(series),
+                _ => None,
+            })
+            .nth(1)
+            .unwrap();
+        let x = missed_actions.x[0].as_date_time().unwrap();
+        let from = PlotValue::date_time(&seconds_to_csv_row_time(0).date_string).as_date_time().unwrap();
+        let to = PlotValue::date_time(&seconds_to_csv_row_time(100).date_string).as_date_time().unwrap();
+        assert!(from < x && x < to, "{} not in the first Asystole", x);
+    }
+}
@@ -1,11 +1,12 @@
 use serde::{Deserialize, Serialize};
 use crate::font::Font;
+use crate::value::PlotValue;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Annotation {
     pub xref: String,
     pub yref: String,
-    pub x: String,
+    pub x: PlotValue,
     pub y: f32,
     pub xanchor: String,
     pub yanchor: String,
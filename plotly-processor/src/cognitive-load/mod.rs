@@ -0,0 +1,2 @@
+pub mod plot_data;
+pub mod transformers;
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// Plotly scatter trace of the event-related average cognitive load, x values are seconds relative to the event.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EventLockedSeries {
+    pub x: Vec<i32>,
+    pub y: Vec<Option<f64>>,
+    pub name: String,
+    pub mode: String,
+    #[serde(rename = "type")]
+    pub plot_type: String,
+    pub customdata: Vec<usize>, //number of events averaged at each offset
+    pub hovertemplate: String,
+    #[serde(rename = "eventType")]
+    pub event_type: String,
+    #[serde(rename = "actionGroup", skip_serializing_if = "Option::is_none")]
+    pub action_group: Option<String>,
+    #[serde(rename = "eventCount")]
+    pub event_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EventLockedPlotData {
+    pub data: Vec<EventLockedSeries>,
+    #[serde(rename = "preSeconds")]
+    pub pre_secs: u32,
+    #[serde(rename = "postSeconds")]
+    pub post_secs: u32,
+}
+
+/// Plotly scatter trace of a team member's cognitive load over the session.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CognitiveLoadSeries {
+    pub x: Vec<String>,
+    pub y: Vec<Option<f64>>,
+    pub name: String,
+    pub mode: String,
+    #[serde(rename = "type")]
+    pub plot_type: String,
+    /// Only set when the source file has any confidence values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<Vec<Option<f64>>>,
+}
@@ -0,0 +1,136 @@
+use crate::cognitive_load::plot_data::{CognitiveLoadSeries, EventLockedPlotData, EventLockedSeries};
+use crate::config::plotly_mappings::PlotlyConfig;
+use mteam_dashboard_action_processor::plot_structures::ActionPlotPoint;
+use mteam_dashboard_cognitive_load_processor::event_locked::{event_locked_average, EventLockedWindow};
+use mteam_dashboard_cognitive_load_processor::file_processor::{normalize_cognitive_load_data, CognitiveLoadSample};
+use mteam_dashboard_utils::alignment::StreamOffset;
+use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Read;
+
+const ERRONEOUS_ACTIONS: &str = "Erroneous Actions";
+const MISSED_ACTIONS: &str = "Missed Actions";
+
+/// Groups erroneous and missed action times by event type, once for all events of the type (`None`) and once per action group.
+fn collect_event_times(plotly_config: &PlotlyConfig, data_points: impl Iterator<Item = Result<ActionPlotPoint, String>>) -> BTreeMap<(&'static str, Option<String>), Vec<f64>> {
+    let mut event_times: BTreeMap<(&'static str, Option<String>), Vec<f64>> = BTreeMap::new();
+
+    for data_point in data_points {
+        let (event_type, action_name, seconds) = match data_point {
+            Ok(ActionPlotPoint::Error(action)) => (ERRONEOUS_ACTIONS, action.name, action.location.timestamp.total_seconds),
+            Ok(ActionPlotPoint::MissedAction(action)) => (MISSED_ACTIONS, action.name, action.location.timestamp.total_seconds),
+            _ => continue,
+        };
+        let group_name = plotly_config.get_action_group_name(&action_name);
+        event_times.entry((event_type, None)).or_default().push(seconds as f64);
+        event_times.entry((event_type, Some(group_name))).or_default().push(seconds as f64);
+    }
+    event_times
+}
+
+fn create_series_name(event_type: &str, action_group: &Option<String>) -> String {
+    match action_group {
+        Some(group_name) => format!("{}: {}", event_type, group_name),
+        None => event_type.to_owned(),
+    }
+}
+
+/// Extracts the cognitive load around each erroneous and missed action and averages it across the events,
+/// for all events of a type and per action group. `data_points` are expected to be aligned already, `alignment`
+/// is applied to the cognitive load samples.
+pub fn to_event_locked_plotly_data(reader: &mut dyn Read, data_points: impl Iterator<Item = Result<ActionPlotPoint, String>>, window: &EventLockedWindow, alignment: &StreamOffset, plotly_config: &PlotlyConfig) -> Result<EventLockedPlotData, io::Error> {
+    let mut samples: Vec<(f64, Option<f64>)> = normalize_cognitive_load_data(reader, alignment)
+        .map_err(io::Error::other)?
+        .collect();
+    samples.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    let data = collect_event_times(plotly_config, data_points)
+        .into_iter()
+        .map(|((event_type, action_group), times)| {
+            let average = event_locked_average(&samples, &times, window);
+            EventLockedSeries {
+                x: average.offsets,
+                y: average.mean,
+                name: create_series_name(event_type, &action_group),
+                mode: "lines".to_owned(),
+                plot_type: "scatter".to_owned(),
+                customdata: average.contributing_events,
+                hovertemplate: "%{x}s: %{y:.2f} (%{customdata} events)".to_owned(),
+                event_type: event_type.to_owned(),
+                action_group,
+                event_count: average.event_count,
+            }
+        })
+        .collect();
+
+    Ok(EventLockedPlotData {
+        data,
+        pre_secs: window.pre_secs,
+        post_secs: window.post_secs,
+    })
+}
+
+/// Scatter trace of the aligned cognitive load samples of a team member, on the time axis of the actions plot.
+pub fn to_time_series(member_name: &str, samples: &[CognitiveLoadSample]) -> CognitiveLoadSeries {
+    let has_confidence = samples.iter().any(|sample| sample.confidence.is_some());
+    CognitiveLoadSeries {
+        x: samples.iter().map(|sample| seconds_to_csv_row_time(sample.seconds as u32).date_string).collect(),
+        y: samples.iter().map(|sample| sample.load).collect(),
+        name: member_name.to_owned(),
+        mode: "lines".to_owned(),
+        plot_type: "scatter".to_owned(),
+        confidence: has_confidence.then(|| samples.iter().map(|sample| sample.confidence).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests_event_locked_plotly_data {
+    use super::*;
+    use mteam_dashboard_action_processor::plot_structures::{ErrorInfo, ErroneousAction, MissedAction, PlotLocation};
+    use std::path::Path;
+
+    fn location(seconds: u32) -> PlotLocation {
+        PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (1, "Asystole".to_owned()) }
+    }
+
+    fn error_info() -> ErrorInfo {
+        ErrorInfo { action_rule: String::new(), violation: String::new(), advice: String::new() }
+    }
+
+    fn error(seconds: u32, name: &str) -> Result<ActionPlotPoint, String> {
+        Ok(ActionPlotPoint::Error(ErroneousAction { location: location(seconds), name: name.to_owned(), action_category: String::new(), shock_value: String::new(), error_info: error_info() }))
+    }
+
+    #[test]
+    fn events_are_averaged_per_type_and_action_group() {
+        let plotly_config = PlotlyConfig::load(Path::new("../plot-config")).unwrap();
+        let content: String = (0..=20).map(|second| format!("{},{}\n", second, second)).collect();
+        let data_points = vec![
+            error(5, "Defib"),
+            error(11, "Defib"),
+            Ok(ActionPlotPoint::MissedAction(MissedAction { location: location(10), name: "Epinephrine".to_owned(), error_info: error_info() })),
+            Err("unreadable row".to_owned()),
+        ];
+        let window = EventLockedWindow { pre_secs: 2, post_secs: 2 };
+        let plot_data = to_event_locked_plotly_data(&mut content.as_bytes(), data_points.into_iter(), &window, &StreamOffset::default(), &plotly_config).unwrap();
+
+        let defib_group = plotly_config.get_action_group_name("Defib");
+        let epinephrine_group = plotly_config.get_action_group_name("Epinephrine");
+        let names: Vec<&str> = plot_data.data.iter().map(|series| series.name.as_str()).collect();
+        assert_eq!(names, vec![
+            ERRONEOUS_ACTIONS.to_owned(),
+            format!("{}: {}", ERRONEOUS_ACTIONS, defib_group),
+            MISSED_ACTIONS.to_owned(),
+            format!("{}: {}", MISSED_ACTIONS, epinephrine_group),
+        ]);
+
+        let erroneous = &plot_data.data[0];
+        assert_eq!(erroneous.x, vec![-2, -1, 0, 1, 2]);
+        assert_eq!(erroneous.y, vec![Some(6.0), Some(7.0), Some(8.0), Some(9.0), Some(10.0)]);
+        assert_eq!((erroneous.event_count, erroneous.action_group.as_deref()), (2, None));
+        assert_eq!(plot_data.data[1].action_group.as_deref(), Some(defib_group.as_str()));
+        assert_eq!(plot_data.data[2].y, vec![Some(8.0), Some(9.0), Some(10.0), Some(11.0), Some(12.0)]);
+        assert_eq!((plot_data.pre_secs, plot_data.post_secs), (2, 2));
+    }
+}
@@ -0,0 +1,34 @@
+use crate::layout::Layout;
+use crate::value::PlotValue;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ComparisonMarker {
+    pub symbol: String,
+    pub size: i8,
+    pub color: String,
+}
+
+/// Plotly scatter trace of the performed, erroneous or missed actions of a session, drawn in the row (`y`) of the
+/// session. `x` is on the stage aligned timeline, `customdata` holds the seconds in the session itself.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ComparisonSeries {
+    pub x: Vec<PlotValue>,
+    pub y: Vec<String>,
+    pub name: String,
+    pub mode: String,
+    #[serde(rename = "type")]
+    pub plot_type: String,
+    pub marker: ComparisonMarker,
+    pub hovertext: Vec<String>,
+    pub hoverinfo: String,
+    pub customdata: Vec<u32>,
+    pub legendgroup: String,
+    pub showlegend: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ComparisonPlotData {
+    pub data: Vec<ComparisonSeries>,
+    pub layout: Layout,
+}
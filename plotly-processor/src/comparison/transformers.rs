@@ -0,0 +1,193 @@
+use crate::actions::builders::{create_shape, create_stage_annotation};
+use crate::comparison::plot_data::{ComparisonMarker, ComparisonPlotData, ComparisonSeries};
+use crate::config::layout_settings::Theme;
+use crate::config::plotly_mappings::PlotlyConfig;
+use crate::layout::Layout;
+use crate::value::PlotValue;
+use mteam_dashboard_action_processor::plot_structures::{ActionPlotPoint, PlotLocation};
+use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+use mteam_dashboard_utils::stage_alignment::{StageAlignment, StageSpan};
+
+const MARKER_SIZE: i8 = 10;
+
+/// Session of the comparison with its stages mapped onto the stages of the first (reference) session.
+pub struct ComparedSession<'a> {
+    pub name: &'a str,
+    pub action_points: &'a [ActionPlotPoint],
+    pub alignment: &'a StageAlignment,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ActionKind {
+    Performed,
+    Erroneous,
+    Missed,
+}
+
+impl ActionKind {
+    const ALL: [ActionKind; 3] = [ActionKind::Performed, ActionKind::Erroneous, ActionKind::Missed];
+
+    fn of(point: &ActionPlotPoint) -> Option<(ActionKind, &str, u32)> {
+        match point {
+            ActionPlotPoint::Action(action) => Some((ActionKind::Performed, &action.name, action.location.timestamp.total_seconds)),
+            ActionPlotPoint::Error(action) => Some((ActionKind::Erroneous, &action.name, action.location.timestamp.total_seconds)),
+            ActionPlotPoint::MissedAction(action) => Some((ActionKind::Missed, &action.name, action.location.timestamp.total_seconds)),
+            ActionPlotPoint::Period(..) => None,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            ActionKind::Performed => "Performed Actions",
+            ActionKind::Erroneous => "Erroneous Actions",
+            ActionKind::Missed => "Missed Actions",
+        }
+    }
+
+    fn marker(&self, theme: &Theme) -> ComparisonMarker {
+        let (symbol, color) = match self {
+            ActionKind::Performed => ("circle", &theme.performed_action_color),
+            ActionKind::Erroneous => ("x", &theme.erroneous_action_color),
+            ActionKind::Missed => ("triangle-down", &theme.missed_action_color),
+        };
+        ComparisonMarker { symbol: symbol.to_owned(), size: MARKER_SIZE, color: color.clone() }
+    }
+}
+
+fn location(seconds: u32, stage_name: &str) -> PlotLocation {
+    PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (0, stage_name.to_owned()) }
+}
+
+fn create_series(session: &ComparedSession, kind: ActionKind, showlegend: bool, theme: &Theme) -> ComparisonSeries {
+    let mut series = ComparisonSeries {
+        x: Vec::new(),
+        y: Vec::new(),
+        name: kind.label().to_owned(),
+        mode: "markers".to_owned(),
+        plot_type: "scatter".to_owned(),
+        marker: kind.marker(theme),
+        hovertext: Vec::new(),
+        hoverinfo: "text".to_owned(),
+        customdata: Vec::new(),
+        legendgroup: kind.label().to_owned(),
+        showlegend,
+    };
+    let actions = session.action_points.iter().filter_map(ActionKind::of).filter(|(action_kind, _, _)| *action_kind == kind);
+    for (_, name, seconds) in actions {
+        // actions in stages the reference session doesn't have are left out
+        let Some(aligned_secs) = session.alignment.align(seconds as f64) else { continue };
+        let time = seconds_to_csv_row_time(seconds);
+        series.x.push(PlotValue::date_time(&seconds_to_csv_row_time(aligned_secs.round() as u32).date_string));
+        series.y.push(session.name.to_owned());
+        series.hovertext.push(format!("{} at {} in {}", name, time.timestamp, session.name));
+        series.customdata.push(seconds);
+    }
+    series
+}
+
+/// One row per session with its performed, erroneous and missed actions on a shared timeline: the times of every
+/// session are mapped onto the stages of the first one (`reference_stages`), whose stages are drawn across all rows.
+/// The first session is the top row.
+pub fn to_comparison_plotly_data(sessions: &[ComparedSession], reference_stages: &[StageSpan], plotly_config: &PlotlyConfig, theme: &Theme) -> ComparisonPlotData {
+    let settings = &plotly_config.layout_settings;
+    let mut layout = Layout::new(settings, theme);
+    layout.title.text = "Session Comparison".to_owned();
+    layout.showlegend = true;
+    layout.yaxis.visible = true;
+    if let Some(reference) = sessions.first() {
+        layout.xaxis.title = format!("Time (stages aligned to {})", reference.name);
+    }
+    let end_secs = reference_stages.iter().map(|stage| stage.end_secs).max().unwrap_or_default();
+    layout.xaxis.range = vec![
+        PlotValue::date_time(&seconds_to_csv_row_time(0).date_string),
+        PlotValue::date_time(&seconds_to_csv_row_time(end_secs).date_string),
+    ];
+
+    for stage in reference_stages {
+        let color = plotly_config.stages.stage_color(&stage.name, theme.stage_colors.as_deref());
+        let display_name = plotly_config.stages.display_name(&stage.name);
+        let mut shape = create_shape(&location(stage.start_secs, &stage.name), &location(stage.end_secs, &stage.name));
+        shape.fillcolor = color.clone() + "33";
+        shape.name = display_name.clone();
+        shape.yref = "paper".to_owned();
+        shape.y0 = 0.0.into();
+        shape.y1 = 1.0.into();
+        layout.shapes.push(shape);
+
+        let annotation_color = color + "70";
+        let mut annotation = create_stage_annotation(display_name, settings, theme);
+        annotation.font.color = Some(annotation_color.clone());
+        annotation.bordercolor = annotation_color;
+        annotation.x = PlotValue::date_time(&seconds_to_csv_row_time(stage.start_secs).date_string);
+        layout.annotations.push(annotation);
+    }
+
+    // plotly puts the first category at the bottom of the axis
+    let data = sessions.iter().rev().enumerate()
+        .flat_map(|(row, session)| ActionKind::ALL.map(|kind| create_series(session, kind, row == 0, theme)))
+        .collect();
+    ComparisonPlotData { data, layout }
+}
+
+#[cfg(test)]
+mod tests_comparison {
+    use super::*;
+    use mteam_dashboard_action_processor::plot_structures::{Action, ErroneousAction, ErrorInfo, MissedAction, PeriodType};
+    use mteam_dashboard_utils::stage_alignment::stage_spans;
+    use std::path::Path;
+
+    fn action(seconds: u32, name: &str) -> ActionPlotPoint {
+        ActionPlotPoint::Action(Action { location: location(seconds, ""), name: name.to_owned(), action_category: String::new(), shock_value: String::new() })
+    }
+
+    fn error_info() -> ErrorInfo {
+        ErrorInfo { action_rule: String::new(), violation: String::new(), advice: String::new() }
+    }
+
+    fn session(stage_end: u32, points: Vec<ActionPlotPoint>) -> Vec<ActionPlotPoint> {
+        let mut session = vec![
+            ActionPlotPoint::Period(PeriodType::Stage, location(0, "Asystole"), location(stage_end, "Asystole")),
+            ActionPlotPoint::Period(PeriodType::Stage, location(stage_end, "ROSC"), location(stage_end + 60, "ROSC")),
+        ];
+        session.extend(points);
+        session
+    }
+
+    #[test]
+    fn sessions_are_drawn_on_the_reference_stages() {
+        let plotly_config = PlotlyConfig::load(Path::new("../plot-config")).unwrap();
+        let theme = plotly_config.layout_settings.default_theme();
+        let reference = session(100, vec![action(50, "Epinephrine")]);
+        let other = session(200, vec![
+            action(100, "Epinephrine"),
+            ActionPlotPoint::Error(ErroneousAction { location: location(230, ""), name: "Defib".to_owned(), action_category: String::new(), shock_value: String::new(), error_info: error_info() }),
+            ActionPlotPoint::MissedAction(MissedAction { location: location(300, ""), name: "Amiodarone".to_owned(), error_info: error_info() }),
+        ]);
+        let reference_stages = stage_spans(&reference);
+        let reference_alignment = StageAlignment::new(&reference_stages, &reference_stages);
+        let other_alignment = StageAlignment::new(&stage_spans(&other), &reference_stages);
+        let sessions = [
+            ComparedSession { name: "06102024", action_points: &reference, alignment: &reference_alignment },
+            ComparedSession { name: "09182024", action_points: &other, alignment: &other_alignment },
+        ];
+        let plot_data = to_comparison_plotly_data(&sessions, &reference_stages, &plotly_config, theme);
+
+        assert_eq!(plot_data.data.len(), 6);
+        // the last session is the bottom row, the legend lists each kind of action once
+        assert_eq!(plot_data.data[0].y, vec!["09182024"]);
+        assert!(plot_data.data[0].showlegend && !plot_data.data[3].showlegend);
+        // 100 seconds into 200 seconds of asystole is 50 seconds into the reference asystole
+        let epinephrine = &plot_data.data[0];
+        assert_eq!(epinephrine.x, vec![PlotValue::date_time(&seconds_to_csv_row_time(50).date_string)]);
+        assert_eq!(epinephrine.customdata, vec![100]);
+        assert_eq!(plot_data.data[1].x, vec![PlotValue::date_time(&seconds_to_csv_row_time(130).date_string)]);
+        // the missed action is after the last stage
+        assert!(plot_data.data[2].x.is_empty());
+        assert_eq!(plot_data.data[3].y, vec!["06102024"]);
+
+        assert_eq!(plot_data.layout.shapes.len(), 2);
+        assert!(plot_data.layout.shapes.iter().all(|shape| shape.yref == "paper"));
+        assert_eq!(plot_data.layout.xaxis.range[1], PlotValue::date_time(&seconds_to_csv_row_time(160).date_string));
+        assert_eq!(plot_data.layout.xaxis.title, "Time (stages aligned to 06102024)");
+    }
+}
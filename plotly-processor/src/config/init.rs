@@ -1,30 +1,153 @@
-use crate::config::plotly_mappings::{ConfigError, PlotlyConfig};
-use once_cell::sync::OnceCell;
-use std::path::Path;
-use std::io;
+use crate::config::plotly_mappings::PlotlyConfig;
+use chrono::Local;
+use serde::Serialize;
+use mteam_dashboard_utils::hash::StableHasher;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::{fs, io};
 
-static CONFIG: OnceCell<PlotlyConfig> = OnceCell::new();
+/// Identifies the active plot configuration, `version` is incremented by every successful reload.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ConfigVersion {
+    pub version: u64,
+    /// Hash of the configuration files the config was loaded from.
+    pub fingerprint: String,
+    #[serde(rename = "loadedAt")]
+    pub loaded_at: String,
+}
 
-fn read_config(config_dir: &Path) -> Result<(), ConfigError> {
-    let config = PlotlyConfig::load(config_dir)?;
-    Ok(CONFIG.set(config).unwrap())
+struct LoadedConfig {
+    config: Arc<PlotlyConfig>,
+    version: ConfigVersion,
 }
 
-pub fn get_config() -> Option<&'static PlotlyConfig> {
-    CONFIG.get()
+/// Swappable plot configuration. Readers take a snapshot with `current()` which stays valid while a reload
+/// replaces the configuration, an invalid configuration never replaces the active one.
+pub struct PlotConfigHandle {
+    config_dir: PathBuf,
+    loaded: RwLock<LoadedConfig>,
 }
 
-pub fn init_plot_config(path_string: String) -> Result<Option<&'static PlotlyConfig>, io::Error> {
-        let path = Path::new(&path_string);
-        if path.exists() {
-            read_config(&path)
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Error initializing plot-config at {:?}: {}", path, e)))?;
-            return Ok(get_config().map(|c| c));
-        } 
+impl PlotConfigHandle {
+    pub fn load(config_dir: &Path) -> Result<Self, String> {
+        let (config, fingerprint) = load_validated(config_dir)?;
+        Ok(Self {
+            config_dir: config_dir.to_path_buf(),
+            loaded: RwLock::new(LoadedConfig {
+                config: Arc::new(config),
+                version: ConfigVersion { version: 1, fingerprint, loaded_at: now() },
+            }),
+        })
+    }
+
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    pub fn current(&self) -> Arc<PlotlyConfig> {
+        self.loaded.read().unwrap_or_else(PoisonError::into_inner).config.clone()
+    }
+
+    pub fn version(&self) -> ConfigVersion {
+        self.loaded.read().unwrap_or_else(PoisonError::into_inner).version.clone()
+    }
+
+    /// The snapshot of `current()` with its version, taken together so that a reload in between can't pair a
+    /// configuration with the version of another one.
+    pub fn current_with_version(&self) -> (Arc<PlotlyConfig>, ConfigVersion) {
+        let loaded = self.loaded.read().unwrap_or_else(PoisonError::into_inner);
+        (loaded.config.clone(), loaded.version.clone())
+    }
+
+    /// Reads the configuration files again and swaps them in, keeps the active configuration when they are invalid.
+    pub fn reload(&self) -> Result<ConfigVersion, String> {
+        let (config, fingerprint) = load_validated(&self.config_dir)?;
+        let mut loaded = self.loaded.write().unwrap_or_else(PoisonError::into_inner);
+        loaded.config = Arc::new(config);
+        loaded.version = ConfigVersion { version: loaded.version.version + 1, fingerprint, loaded_at: now() };
+        Ok(loaded.version.clone())
+    }
+}
+
+fn now() -> String {
+    Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn load_validated(config_dir: &Path) -> Result<(PlotlyConfig, String), String> {
+    let fingerprint = fingerprint(config_dir).map_err(|e| format!("Error reading plot-config at {:?}: {}", config_dir, e))?;
+    let config = PlotlyConfig::load(config_dir).map_err(|e| format!("Error loading plot-config at {:?}: {}", config_dir, e))?;
+    config.validate().map_err(|e| format!("Invalid plot-config at {:?}: {}", config_dir, e))?;
+    Ok((config, fingerprint))
+}
 
-    // If all attempts fail, return a combined error
-    Err(io::Error::new(
-        io::ErrorKind::Other,
-        "Reading the plot configuration files failed".to_string(),
-    ))
-}
\ No newline at end of file
+/// Hash of the names and contents of the files in the plot-config folder, changes whenever one of them is edited,
+/// added or removed.
+pub fn fingerprint(config_dir: &Path) -> Result<String, io::Error> {
+    let mut files: Vec<PathBuf> = fs::read_dir(config_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    let mut hasher = StableHasher::default();
+    for file in files {
+        hasher.write_field(file.file_name().unwrap_or_default().as_encoded_bytes());
+        hasher.write_field(&fs::read(&file)?);
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+pub fn init_plot_config(path_string: String) -> Result<PlotConfigHandle, io::Error> {
+    let path = Path::new(&path_string);
+    if !path.exists() {
+        return Err(io::Error::other(format!("Plot configuration folder {:?} does not exist", path)));
+    }
+    PlotConfigHandle::load(path).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests_plot_config_handle {
+    use super::*;
+
+    fn config_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for entry in fs::read_dir("../plot-config").unwrap() {
+            let path = entry.unwrap().path();
+            fs::copy(&path, dir.path().join(path.file_name().unwrap())).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn reload_swaps_the_config() {
+        let temp_dir = config_dir();
+        let dir = temp_dir.path();
+        let handle = PlotConfigHandle::load(dir).unwrap();
+        let (before, initial_version) = handle.current_with_version();
+        assert_eq!(initial_version.version, 1);
+
+        fs::write(dir.join("action-groups.json"), r#"{"cpr": "Compressions"}"#).unwrap();
+        let version = handle.reload().unwrap();
+        assert_eq!(version.version, 2);
+        assert_eq!(version.fingerprint, fingerprint(dir).unwrap());
+        assert_ne!(version.fingerprint, initial_version.fingerprint);
+        assert_eq!(handle.current().get_action_group_name("CPR"), "Compressions");
+        assert_eq!(handle.current_with_version().1, version);
+        // snapshots taken before the reload are unaffected
+        assert_ne!(before.get_action_group_name("CPR"), "Compressions");
+    }
+
+    #[test]
+    fn invalid_config_keeps_the_active_one() {
+        let temp_dir = config_dir();
+        let dir = temp_dir.path();
+        let handle = PlotConfigHandle::load(dir).unwrap();
+        let version = handle.version();
+
+        fs::write(dir.join("action-groups.json"), "{").unwrap();
+        assert!(handle.reload().unwrap_err().starts_with("Error loading plot-config"));
+        fs::write(dir.join("action-plot-stages.json"), r#"{"names": {}, "colors": []}"#).unwrap();
+        fs::write(dir.join("action-groups.json"), "{}").unwrap();
+        assert!(handle.reload().unwrap_err().contains("colors"));
+        assert_eq!(handle.version(), version);
+    }
+}
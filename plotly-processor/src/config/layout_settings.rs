@@ -0,0 +1,304 @@
+use crate::config::schema::JsonSchema;
+use crate::font::Font;
+use crate::layout::Margin;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+const DEFAULT_THEME_NAME: &str = "light";
+const ANNOTATION_FONT_FAMILY: &str = "Arial, sans-serif";
+
+static DEFAULT_THEME: Lazy<Theme> = Lazy::new(Theme::default);
+
+/// Size of the action icons, either fixed or computed from the session by `IconSizing`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct IconSettings {
+    /// Icon width in x axis units (milliseconds on the time axis) when `autoSize` is off.
+    #[serde(rename = "sizeX")]
+    pub size_x: f64,
+    /// Icon height in y axis units when `autoSize` is off.
+    #[serde(rename = "sizeY")]
+    pub size_y: f64,
+    /// Computes the size from the session duration, the distance between the actions and the y axis range.
+    #[serde(rename = "autoSize")]
+    pub auto_size: bool,
+    #[serde(rename = "minSizeX")]
+    pub min_size_x: f64,
+    #[serde(rename = "maxSizeX")]
+    pub max_size_x: f64,
+    #[serde(rename = "minSizeY")]
+    pub min_size_y: f64,
+    #[serde(rename = "maxSizeY")]
+    pub max_size_y: f64,
+    /// Number of icons that fit side by side on the whole time axis, the starting point of the computed width.
+    #[serde(rename = "iconsAcross")]
+    pub icons_across: f64,
+    /// Zoom factors of the time axis the icon sizes are also computed for, e.g. `[2, 4]`, the frontend swaps the
+    /// sizes when zooming.
+    #[serde(rename = "zoomLevels", skip_serializing_if = "Vec::is_empty")]
+    pub zoom_levels: Vec<f64>,
+}
+
+impl Default for IconSettings {
+    fn default() -> Self {
+        Self {
+            size_x: 10500.0,
+            size_y: 1.5,
+            auto_size: false,
+            min_size_x: 2000.0,
+            max_size_x: 30000.0,
+            min_size_y: 0.5,
+            max_size_y: 2.0,
+            icons_across: 60.0,
+            zoom_levels: Vec::new(),
+        }
+    }
+}
+
+/// Colors of the actions plot, every field missing from a theme keeps the light (default) value.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Theme {
+    /// Plotly's default (white) is used when not set.
+    #[serde(rename = "paperBackgroundColor", skip_serializing_if = "Option::is_none")]
+    pub paper_background_color: Option<String>,
+    #[serde(rename = "plotBackgroundColor", skip_serializing_if = "Option::is_none")]
+    pub plot_background_color: Option<String>,
+    #[serde(rename = "fontColor", skip_serializing_if = "Option::is_none")]
+    pub font_color: Option<String>,
+    /// `#rrggbb` colors replacing the ones in action-plot-stages.json.
+    #[serde(rename = "stageColors", skip_serializing_if = "Option::is_none")]
+    pub stage_colors: Option<Vec<String>>,
+    #[serde(rename = "stageAnnotationBackgroundColor")]
+    pub stage_annotation_background_color: String,
+    #[serde(rename = "performedActionColor")]
+    pub performed_action_color: String,
+    #[serde(rename = "erroneousActionColor")]
+    pub erroneous_action_color: String,
+    #[serde(rename = "missedActionColor")]
+    pub missed_action_color: String,
+    #[serde(rename = "cprLineColor")]
+    pub cpr_line_color: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            paper_background_color: None,
+            plot_background_color: None,
+            font_color: None,
+            stage_colors: None,
+            stage_annotation_background_color: "rgba(255, 255, 255, 0.8)".to_owned(),
+            performed_action_color: "green".to_owned(),
+            erroneous_action_color: "red".to_owned(),
+            missed_action_color: "rgba(249, 105, 14, 0.8)".to_owned(),
+            cpr_line_color: "rgb(0, 150, 0)".to_owned(),
+        }
+    }
+}
+
+/// Title, margins, fonts and icon sizes of the actions plot (layout-settings.json) and the named color themes
+/// selectable per request.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LayoutSettings {
+    pub title: String,
+    #[serde(rename = "titleY")]
+    pub title_y: f64,
+    pub margin: Margin,
+    /// Font family of the whole plot, Plotly's default is used when not set.
+    #[serde(rename = "fontFamily", skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+    #[serde(rename = "stageAnnotationFont")]
+    pub stage_annotation_font: Font,
+    #[serde(rename = "sectionAnnotationFont")]
+    pub section_annotation_font: Font,
+    #[serde(rename = "actionTextFontSize")]
+    pub action_text_font_size: i8,
+    #[serde(rename = "markerSize")]
+    pub marker_size: i8,
+    pub icon: IconSettings,
+    #[serde(rename = "defaultTheme")]
+    pub default_theme: String,
+    pub themes: BTreeMap<String, Theme>,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            title: "Clinical Review Timeline".to_owned(),
+            title_y: 0.99,
+            margin: Margin { t: 0.0, l: 50.0, r: 50.0, b: 50.0 },
+            font_family: None,
+            stage_annotation_font: Font {
+                size: 16,
+                color: None,
+                family: Some(ANNOTATION_FONT_FAMILY.to_owned()),
+                weight: Some(700),
+            },
+            section_annotation_font: Font {
+                size: 14,
+                color: Some("".to_owned()),
+                family: Some(ANNOTATION_FONT_FAMILY.to_owned()),
+                weight: Some(550),
+            },
+            action_text_font_size: 10,
+            marker_size: 24,
+            icon: IconSettings::default(),
+            default_theme: DEFAULT_THEME_NAME.to_owned(),
+            themes: BTreeMap::from([(DEFAULT_THEME_NAME.to_owned(), Theme::default())]),
+        }
+    }
+}
+
+impl LayoutSettings {
+    /// The theme requested by name, the default theme when no name is given.
+    pub fn theme(&self, name: Option<&str>) -> Result<&Theme, String> {
+        match name {
+            Some(name) => self.themes.get(name).ok_or_else(|| format!(
+                "Unknown theme: {}, expected one of: {}",
+                name,
+                self.themes.keys().cloned().collect::<Vec<_>>().join(", ")
+            )),
+            None => Ok(self.default_theme()),
+        }
+    }
+
+    /// Falls back to the built-in light theme when `defaultTheme` names a theme that is not configured.
+    pub fn default_theme(&self) -> &Theme {
+        self.themes.get(&self.default_theme).unwrap_or(&DEFAULT_THEME)
+    }
+}
+
+impl JsonSchema for IconSettings {
+    fn json_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sizeX": {"description": "Icon width in x axis units (milliseconds on the time axis) when autoSize is off.", "type": "number", "minimum": 0},
+                "sizeY": {"description": "Icon height in y axis units when autoSize is off.", "type": "number", "minimum": 0},
+                "autoSize": {"description": "Computes the size from the session duration, the distance between the actions and the y axis range.", "type": "boolean"},
+                "minSizeX": {"type": "number", "minimum": 0},
+                "maxSizeX": {"type": "number", "minimum": 0},
+                "minSizeY": {"type": "number", "minimum": 0},
+                "maxSizeY": {"type": "number", "minimum": 0},
+                "iconsAcross": {"description": "Number of icons that fit side by side on the whole time axis.", "type": "number", "minimum": 1},
+                "zoomLevels": {
+                    "description": "Zoom factors of the time axis the icon sizes are also computed for.",
+                    "type": "array",
+                    "items": {"type": "number", "minimum": 1}
+                }
+            }
+        })
+    }
+}
+
+impl JsonSchema for Font {
+    fn json_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "size": {"type": "integer", "minimum": 1, "maximum": 127},
+                "color": {"type": "string"},
+                "family": {"type": "string"},
+                "weight": {"type": "integer", "minimum": 1, "maximum": 1000}
+            },
+            "required": ["size"]
+        })
+    }
+}
+
+impl JsonSchema for Margin {
+    fn json_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "t": {"type": "number"},
+                "l": {"type": "number"},
+                "r": {"type": "number"},
+                "b": {"type": "number"}
+            },
+            "required": ["t", "l", "r", "b"]
+        })
+    }
+}
+
+impl JsonSchema for Theme {
+    fn json_schema() -> Value {
+        let color = json!({"type": "string"});
+        json!({
+            "description": "Every property missing from a theme keeps the light (default) value.",
+            "type": "object",
+            "properties": {
+                "paperBackgroundColor": color,
+                "plotBackgroundColor": color,
+                "fontColor": color,
+                "stageColors": {
+                    "description": "#rrggbb colors replacing the ones in action-plot-stages.json.",
+                    "type": "array",
+                    "items": {"type": "string", "pattern": "^#[0-9a-fA-F]{6}$"},
+                    "minItems": 1
+                },
+                "stageAnnotationBackgroundColor": color,
+                "performedActionColor": color,
+                "erroneousActionColor": color,
+                "missedActionColor": color,
+                "cprLineColor": color
+            }
+        })
+    }
+}
+
+impl JsonSchema for LayoutSettings {
+    fn json_schema() -> Value {
+        json!({
+            "description": "Every property missing from the file keeps the default value.",
+            "type": "object",
+            "properties": {
+                "title": {"type": "string"},
+                "titleY": {"type": "number", "minimum": 0, "maximum": 1},
+                "margin": Margin::json_schema(),
+                "fontFamily": {"description": "Font family of the whole plot.", "type": "string"},
+                "stageAnnotationFont": Font::json_schema(),
+                "sectionAnnotationFont": Font::json_schema(),
+                "actionTextFontSize": {"type": "integer", "minimum": 1, "maximum": 127},
+                "markerSize": {"type": "integer", "minimum": 1, "maximum": 127},
+                "icon": IconSettings::json_schema(),
+                "defaultTheme": {"description": "Theme used when a request doesn't select one.", "type": "string"},
+                "themes": {"type": "object", "additionalProperties": Theme::json_schema()}
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests_layout_settings {
+    use super::*;
+
+    #[test]
+    fn missing_fields_keep_the_defaults() {
+        let settings: LayoutSettings = serde_json::from_str(r##"{
+            "title": "Team Debrief",
+            "themes": {"light": {}, "dark": {"paperBackgroundColor": "#1e1e1e", "fontColor": "#eeeeee"}}
+        }"##).unwrap();
+        assert_eq!(settings.title, "Team Debrief");
+        assert_eq!(settings.icon.size_x, 10500.0);
+        assert_eq!(settings.stage_annotation_font.weight, Some(700));
+
+        let dark = settings.theme(Some("dark")).unwrap();
+        assert_eq!(dark.paper_background_color.as_deref(), Some("#1e1e1e"));
+        assert_eq!(dark.cpr_line_color, "rgb(0, 150, 0)");
+        assert_eq!(settings.theme(None).unwrap().font_color, None);
+    }
+
+    #[test]
+    fn unknown_theme() {
+        let settings = LayoutSettings::default();
+        assert_eq!(settings.theme(Some("neon")).unwrap_err(), "Unknown theme: neon, expected one of: light");
+
+        let settings = LayoutSettings { default_theme: "neon".to_owned(), ..Default::default() };
+        assert_eq!(settings.default_theme().performed_action_color, "green");
+    }
+}
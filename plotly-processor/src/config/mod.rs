@@ -1,2 +1,5 @@
 pub mod plotly_mappings;
 pub mod init;
+pub mod layout_settings;
+pub mod schema;
+pub mod validator;
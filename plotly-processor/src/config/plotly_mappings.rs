@@ -1,6 +1,12 @@
+use crate::config::layout_settings::LayoutSettings;
+use crate::config::schema::{schema_document, unknown_properties, validate_json, JsonSchema};
+use crate::config::validator::validate_config;
 use serde::de::{Error, SeqAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
-use serde_json::Error as SerdeError;
+use mteam_dashboard_utils::hash::stable_hash;
+use mteam_dashboard_visual_attention_processor::category::{CategoryLevel, CategoryMapping};
+use mteam_dashboard_visual_attention_processor::window::{SampleWeighting, WindowSettings};
+use serde_json::{json, Error as SerdeError, Value};
 use std::borrow::ToOwned;
 use std::collections::HashMap;
 use std::{fmt, fs, io, path::Path};
@@ -9,6 +15,9 @@ use std::{fmt, fs, io, path::Path};
 pub enum ConfigError {
     Io(io::Error),
     Serde(SerdeError),
+    /// A configuration file that can't be read, isn't JSON or doesn't match its schema, the errors start with the
+    /// JSON path of the offending value.
+    Invalid { file: String, errors: Vec<String> },
 }
 
 impl From<io::Error> for ConfigError {
@@ -25,7 +34,88 @@ impl From<serde_json::Error> for ConfigError {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct StagesConfig {
     pub names: HashMap<String, String>,
-    pub colors: Vec<String>
+    /// Palette of the stages without a color in `styles`.
+    pub colors: Vec<String>,
+    /// Fixed color and icon by stage name, either the name in the CSV or the display name.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub styles: HashMap<String, StageStyle>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StageStyle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Icon drawn in the bottom left corner of the stage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>
+}
+
+impl StagesConfig {
+    pub fn display_name(&self, stage_name: &str) -> String {
+        self.names.get(stage_name).cloned().unwrap_or_else(|| stage_name.to_owned())
+    }
+
+    /// The style of the stage name in the CSV, looked up by its display name when the CSV name has none.
+    pub fn style(&self, stage_name: &str) -> Option<&StageStyle> {
+        self.styles.get(stage_name).or_else(|| self.names.get(stage_name).and_then(|display_name| self.styles.get(display_name)))
+    }
+
+    /// The `stageColors` of a theme replace both the fixed colors of `styles` and `colors`, so that e.g. a greyscale
+    /// theme stays greyscale. Without them it is the color of `styles`, otherwise a palette color picked by the
+    /// display name, so that a stage gets the same color in every session no matter where or how often it occurs.
+    pub fn stage_color(&self, stage_name: &str, theme_colors: Option<&[String]>) -> String {
+        let palette = match theme_colors {
+            Some(theme_colors) => theme_colors,
+            None => {
+                if let Some(color) = self.style(stage_name).and_then(|style| style.color.clone()) {
+                    return color;
+                }
+                &self.colors
+            }
+        };
+        if palette.is_empty() {
+            return "#ffffff".to_owned();
+        }
+        let index = stable_hash(self.display_name(stage_name).as_bytes()) % palette.len() as u64;
+        palette[index as usize].clone()
+    }
+
+    pub fn stage_icon(&self, stage_name: &str) -> Option<&String> {
+        self.style(stage_name).and_then(|style| style.icon.as_ref())
+    }
+}
+
+impl JsonSchema for StagesConfig {
+    fn json_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "names": {
+                    "description": "Stage name in the CSV to the name shown in the plot.",
+                    "type": "object",
+                    "additionalProperties": {"type": "string"}
+                },
+                "colors": {
+                    "description": "#rrggbb palette of the stages without a color in styles, a stage always gets the same color picked by its display name.",
+                    "type": "array",
+                    "items": {"type": "string", "pattern": "^#[0-9a-fA-F]{6}$"},
+                    "minItems": 1
+                },
+                "styles": {
+                    "description": "Fixed color and icon by stage name, either the name in the CSV or the display name.",
+                    "type": "object",
+                    "additionalProperties": {
+                        "type": "object",
+                        "properties": {
+                            "color": {"type": "string", "pattern": "^#[0-9a-fA-F]{6}$"},
+                            "icon": {"description": "Icon drawn in the bottom left corner of the stage.", "type": "string"}
+                        }
+                    }
+                }
+            },
+            "required": ["names", "colors"]
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -40,7 +130,21 @@ pub struct MissedActionsPlotSettings {
 
 impl MissedActionsPlotSettings {
     pub fn calculate_y_max(&self, max_missed_action_count_per_stage: u16) -> f32 {
-        self.y_min + (max_missed_action_count_per_stage as f32 / (self.max_count_per_row + 1) as f32) * self.y_increment
+        self.y_min + (max_missed_action_count_per_stage as f32 / (self.max_count_per_row as f32 + 1.0)) * self.y_increment
+    }
+}
+
+impl JsonSchema for MissedActionsPlotSettings {
+    fn json_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "maxCountPerRow": {"description": "Missed action icons per row within a stage.", "type": "integer", "minimum": 1, "maximum": 65535},
+                "yIncrement": {"description": "Distance between the rows, negative to grow downwards.", "type": "number"},
+                "yMin": {"description": "y of the first row.", "type": "number"}
+            },
+            "required": ["maxCountPerRow", "yIncrement", "yMin"]
+        })
     }
 }
 
@@ -58,12 +162,117 @@ pub struct ActionsPlotSettings {
     pub missed_actions: MissedActionsPlotSettings
 }
 
+impl JsonSchema for ActionsPlotSettings {
+    fn json_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "xAxisPaddingSecs": {"description": "Seconds added to the end of the time axis.", "type": "integer", "minimum": 0},
+                "yAnnotation": {"description": "Height of the stage names as a fraction of the plot height.", "type": "number"},
+                "yMin": {"description": "Bottom of the stage backgrounds.", "type": "number"},
+                "yIncrement": {"description": "Distance between the action group rows.", "type": "number"},
+                "missedActions": MissedActionsPlotSettings::json_schema()
+            },
+            "required": ["xAxisPaddingSecs", "yAnnotation", "yMin", "yIncrement", "missedActions"]
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct VisualAttentionPlotSettings {
     #[serde(rename = "windowSizeSeconds")]
     pub window_size_secs: u32,
+    /// Seconds between window starts, defaults to the window size (no overlap).
+    #[serde(rename = "stepSeconds", default, skip_serializing_if = "Option::is_none")]
+    pub step_secs: Option<u32>,
+    /// Windows with fewer categorized samples are flagged as unreliable, 0 flags none.
+    #[serde(rename = "minSampleCount", default)]
+    pub min_sample_count: usize,
+    #[serde(default)]
+    pub weighting: SampleWeighting,
+    #[serde(rename = "maxSampleDurationSeconds", default = "default_max_sample_duration_secs")]
+    pub max_sample_duration_secs: f64,
     #[serde(rename = "orderedColorMap", deserialize_with = "ordered_color_map")]
-    pub ordered_category_color_tuples: Vec<(String, String)>
+    pub ordered_category_color_tuples: Vec<(String, String)>,
+    /// Colors assigned in order to the categories missing from `orderedColorMap`.
+    #[serde(rename = "fallbackColors", default = "default_fallback_colors")]
+    pub fallback_colors: Vec<String>,
+    /// Eye tracker label to category, a category can be hierarchical, e.g. "Patient/Airway".
+    #[serde(rename = "categoryAliases", default)]
+    pub category_aliases: HashMap<String, String>
+}
+
+impl VisualAttentionPlotSettings {
+    pub fn window_settings(&self) -> WindowSettings {
+        WindowSettings {
+            window_size_secs: self.window_size_secs,
+            step_secs: self.step_secs.unwrap_or(self.window_size_secs),
+            min_sample_count: self.min_sample_count,
+            weighting: self.weighting,
+            max_sample_duration_secs: self.max_sample_duration_secs,
+        }
+    }
+
+    pub fn category_mapping(&self, level: CategoryLevel) -> CategoryMapping {
+        CategoryMapping {
+            aliases: self.category_aliases.iter().map(|(label, category)| (label.clone(), category.clone())).collect(),
+            level,
+        }
+    }
+
+    /// Endless sequence of the fallback colors not used by the configured categories, starting over when exhausted.
+    /// The built-in palette is used when `fallbackColors` is empty.
+    pub fn fallback_colors(&self) -> impl Iterator<Item = String> {
+        let configured = if self.fallback_colors.is_empty() { default_fallback_colors() } else { self.fallback_colors.clone() };
+        let unused: Vec<String> = configured.iter()
+            .filter(|color| !self.ordered_category_color_tuples.iter().any(|(_, used)| used.eq_ignore_ascii_case(color)))
+            .cloned()
+            .collect();
+        let colors = if unused.is_empty() { configured } else { unused };
+        colors.into_iter().cycle()
+    }
+}
+
+impl JsonSchema for VisualAttentionPlotSettings {
+    fn json_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "windowSizeSeconds": {"description": "Length of the time windows the samples are grouped into.", "type": "integer", "minimum": 1},
+                "stepSeconds": {"description": "Seconds between window starts, defaults to the window size (no overlap).", "type": "integer", "minimum": 1},
+                "minSampleCount": {"description": "Windows with fewer categorized samples are flagged as unreliable, 0 flags none.", "type": "integer", "minimum": 0},
+                "weighting": {"description": "How much a sample counts in its windows.", "enum": ["samples", "duration"]},
+                "maxSampleDurationSeconds": {"description": "Cap of the seconds a sample counts for with duration weighting.", "type": "number", "minimum": 0},
+                "orderedColorMap": {
+                    "description": "[category, color] pairs in plotting order.",
+                    "type": "array",
+                    "items": {"type": "array", "items": {"type": "string"}, "minItems": 2, "maxItems": 2}
+                },
+                "fallbackColors": {
+                    "description": "Colors assigned in order to the categories missing from orderedColorMap.",
+                    "type": "array",
+                    "items": {"type": "string"}
+                },
+                "categoryAliases": {
+                    "description": "Eye tracker label to category, a category can be hierarchical, e.g. \"Patient/Airway\".",
+                    "type": "object",
+                    "additionalProperties": {"type": "string"}
+                }
+            },
+            "required": ["windowSizeSeconds", "orderedColorMap"]
+        })
+    }
+}
+
+fn default_max_sample_duration_secs() -> f64 {
+    1.0
+}
+
+fn default_fallback_colors() -> Vec<String> {
+    ["#ff7f0e", "#8c564b", "#e377c2", "#7f7f7f", "#bcbd22", "#17becf", "#aec7e8", "#ffbb78", "#98df8a", "#ff9896"]
+        .iter()
+        .map(|color| color.to_string())
+        .collect()
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -72,6 +281,23 @@ pub struct TeamMemberFilterSettings {
     pub filter_selection_order: Vec<String>,
 }
 
+impl JsonSchema for TeamMemberFilterSettings {
+    fn json_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "filterSelectionOrder": {
+                    "description": "Team member names (derived from the snake_case data file names) listed first, in this order.",
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "minItems": 1
+                }
+            },
+            "required": ["filterSelectionOrder"]
+        })
+    }
+}
+
 fn ordered_color_map<'de, D>(deserializer: D) -> Result<Vec<(String, String)>, D::Error>
 where
     D: Deserializer<'de>,
@@ -107,8 +333,29 @@ where
     deserializer.deserialize_seq(OrderedColorMapVisitor)
 }
 
-const DEFAULT_ACTION_GROUP_NAME: &str = "default_group_name";
-const DEFAULT_ACTION_GROUP_ICON_ATTR: &str = "default"; 
+pub const ACTION_PLOT_STAGES_FILE: &str = "action-plot-stages.json";
+pub const ACTION_GROUPS_FILE: &str = "action-groups.json";
+pub const ACTION_GROUP_ICONS_FILE: &str = "action-group-icons.json";
+pub const ACTION_PLOT_SETTINGS_FILE: &str = "action-plot-settings.json";
+pub const VISUAL_ATTENTION_PLOT_SETTINGS_FILE: &str = "visual-attention-plot-settings.json";
+pub const TEAM_MEMBER_FILTER_SETTINGS_FILE: &str = "team-member-filter-settings.json";
+pub const LAYOUT_SETTINGS_FILE: &str = "layout-settings.json";
+
+/// Schema documents of the plot-config files by file name.
+pub fn plot_config_schemas() -> Vec<(&'static str, Value)> {
+    vec![
+        (ACTION_PLOT_STAGES_FILE, schema_document::<StagesConfig>(ACTION_PLOT_STAGES_FILE)),
+        (ACTION_GROUPS_FILE, schema_document::<HashMap<String, String>>(ACTION_GROUPS_FILE)),
+        (ACTION_GROUP_ICONS_FILE, schema_document::<HashMap<String, String>>(ACTION_GROUP_ICONS_FILE)),
+        (ACTION_PLOT_SETTINGS_FILE, schema_document::<ActionsPlotSettings>(ACTION_PLOT_SETTINGS_FILE)),
+        (VISUAL_ATTENTION_PLOT_SETTINGS_FILE, schema_document::<VisualAttentionPlotSettings>(VISUAL_ATTENTION_PLOT_SETTINGS_FILE)),
+        (TEAM_MEMBER_FILTER_SETTINGS_FILE, schema_document::<TeamMemberFilterSettings>(TEAM_MEMBER_FILTER_SETTINGS_FILE)),
+        (LAYOUT_SETTINGS_FILE, schema_document::<LayoutSettings>(LAYOUT_SETTINGS_FILE)),
+    ]
+}
+
+pub const DEFAULT_ACTION_GROUP_NAME: &str = "default_group_name";
+pub const DEFAULT_ACTION_GROUP_ICON_ATTR: &str = "default";
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PlotlyConfig {
     pub stages: StagesConfig,
@@ -116,7 +363,11 @@ pub struct PlotlyConfig {
     pub action_group_icons: HashMap<String, String>,
     pub action_plot_settings: ActionsPlotSettings,
     pub visual_attention_plot_settings: VisualAttentionPlotSettings,
-    pub team_member_filter_settings: TeamMemberFilterSettings
+    pub team_member_filter_settings: TeamMemberFilterSettings,
+    pub layout_settings: LayoutSettings,
+    /// (file name, JSON path) of the keys in the files that no setting reads, `validate_config` warns about them.
+    #[serde(skip)]
+    pub unknown_properties: Vec<(String, String)>
 }
 impl PlotlyConfig {
     pub fn get_action_group_name(&self, action_name: &str) -> String{
@@ -129,19 +380,24 @@ impl PlotlyConfig {
 }
 impl fmt::Display for ConfigError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            ConfigError::Invalid { file, errors } => write!(f, "{}: {}", file, errors.join("; ")),
+            other => write!(f, "{:?}", other),
+        }
     }
 }
 
 impl std::error::Error for ConfigError {}
 impl PlotlyConfig {
     pub fn load(config_dir: &Path) -> Result<Self, ConfigError> {
-        let stage_names: StagesConfig = load_json(config_dir.join("action-plot-stages.json"))?;
-        let action_groups: HashMap<String, String> = load_json(config_dir.join("action-groups.json"))?;
-        let action_group_icons: HashMap<String, String> = load_json(config_dir.join("action-group-icons.json"))?;
-        let action_plot_settings: ActionsPlotSettings = load_json(config_dir.join("action-plot-settings.json"))?;
-        let visual_attention_plot_settings: VisualAttentionPlotSettings = load_json(config_dir.join("visual-attention-plot-settings.json"))?;
-        let team_member_filter_settings: TeamMemberFilterSettings = load_json(config_dir.join("team-member-filter-settings.json"))?;
+        let mut unknown_properties = Vec::new();
+        let stage_names: StagesConfig = load_json(config_dir.join(ACTION_PLOT_STAGES_FILE), &mut unknown_properties)?;
+        let action_groups: HashMap<String, String> = load_json(config_dir.join(ACTION_GROUPS_FILE), &mut unknown_properties)?;
+        let action_group_icons: HashMap<String, String> = load_json(config_dir.join(ACTION_GROUP_ICONS_FILE), &mut unknown_properties)?;
+        let action_plot_settings: ActionsPlotSettings = load_json(config_dir.join(ACTION_PLOT_SETTINGS_FILE), &mut unknown_properties)?;
+        let visual_attention_plot_settings: VisualAttentionPlotSettings = load_json(config_dir.join(VISUAL_ATTENTION_PLOT_SETTINGS_FILE), &mut unknown_properties)?;
+        let team_member_filter_settings: TeamMemberFilterSettings = load_json(config_dir.join(TEAM_MEMBER_FILTER_SETTINGS_FILE), &mut unknown_properties)?;
+        let layout_settings: LayoutSettings = load_json_or_default(config_dir.join(LAYOUT_SETTINGS_FILE), &mut unknown_properties)?;
 
         Ok(PlotlyConfig {
             stages: stage_names,
@@ -149,17 +405,167 @@ impl PlotlyConfig {
             action_group_icons,
             action_plot_settings,
             visual_attention_plot_settings,
-            team_member_filter_settings
+            team_member_filter_settings,
+            layout_settings,
+            unknown_properties
         })
     }
 }
 
+impl PlotlyConfig {
+    /// Fails with the errors of `validate_config`, warnings don't prevent the configuration from being used.
+    pub fn validate(&self) -> Result<(), String> {
+        let report = validate_config(self);
+        if report.has_errors() {
+            Err(report.errors().map(ToString::to_string).collect::<Vec<_>>().join("; "))
+        } else {
+            Ok(())
+        }
+    }
+}
+
 // for<'de> Deserialize<'de> is essential for writing generic deserialization functions in Rust
 // that can handle data with arbitrary lifetimes. It's a key part of how serde achieves its
 // flexibility and safety. If you are ever writing a function that deserializes data using serde,
 // it is almost always what you want.
-fn load_json<T: for<'de> Deserialize<'de>>(path: impl AsRef<Path>) -> Result<T, ConfigError> {
-    let content = fs::read_to_string(path.as_ref())?;
-    let data = serde_json::from_str(&content)?;
-    Ok(data)
-}
\ No newline at end of file
+fn load_json<T: for<'de> Deserialize<'de> + JsonSchema>(path: impl AsRef<Path>, unknown: &mut Vec<(String, String)>) -> Result<T, ConfigError> {
+    let path = path.as_ref();
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let invalid = |errors: Vec<String>| ConfigError::Invalid { file: path.display().to_string(), errors };
+    let content = fs::read_to_string(path).map_err(|e| invalid(vec![e.to_string()]))?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| invalid(vec![e.to_string()]))?;
+    // the schema errors point at the offending value, serde stops at the first error with a line and column only
+    let schema = schema_document::<T>(&file_name);
+    let errors = validate_json(&value, &schema);
+    if !errors.is_empty() {
+        return Err(invalid(errors));
+    }
+    unknown.extend(unknown_properties(&value, &schema).into_iter().map(|path| (file_name.clone(), path)));
+    serde_json::from_value(value).map_err(|e| invalid(vec![e.to_string()]))
+}
+
+/// Optional configuration files fall back to the defaults when they do not exist.
+fn load_json_or_default<T: for<'de> Deserialize<'de> + JsonSchema + Default>(path: impl AsRef<Path>, unknown: &mut Vec<(String, String)>) -> Result<T, ConfigError> {
+    if path.as_ref().exists() {
+        load_json(path, unknown)
+    } else {
+        Ok(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests_config_schemas {
+    use super::*;
+    use crate::config::layout_settings::{IconSettings, Theme};
+    use crate::config::schema::schema_field_differences;
+    use crate::font::Font;
+    use crate::layout::Margin;
+
+    #[test]
+    fn bundled_config_matches_the_schemas() {
+        for (file_name, schema) in plot_config_schemas() {
+            let content = fs::read_to_string(Path::new("../plot-config").join(file_name)).unwrap();
+            let value: Value = serde_json::from_str(&content).unwrap();
+            assert_eq!(validate_json(&value, &schema), Vec::<String>::new(), "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn load_errors_point_at_the_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(ACTION_PLOT_SETTINGS_FILE);
+        fs::write(&path, r#"{
+            "xAxisPaddingSecs": 30, "yAnnotation": 0.95, "yMin": 0, "yIncrement": 1.5,
+            "missedActions": {"maxCountPerRow": "3", "yIncrement": -1, "yMin": -1}
+        }"#).unwrap();
+        let error = load_json::<ActionsPlotSettings>(&path, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.to_string(), format!("{}: $.missedActions.maxCountPerRow: expected integer, found string \"3\"", path.display()));
+    }
+
+    #[test]
+    fn unknown_properties_still_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(ACTION_PLOT_SETTINGS_FILE);
+        fs::write(&path, r#"{
+            "xAxisPaddingSecs": 30, "yAnnotation": 0.95, "yMin": 0, "yIncrement": 1.5,
+            "missedActions": {"maxCountPerRow": 3, "yIncrement": -1, "yMin": -1, "yMax": 2}
+        }"#).unwrap();
+        let mut unknown = Vec::new();
+        let settings = load_json::<ActionsPlotSettings>(&path, &mut unknown).unwrap();
+        assert_eq!(settings.missed_actions.max_count_per_row, 3);
+        assert_eq!(unknown, vec![(ACTION_PLOT_SETTINGS_FILE.to_owned(), "$.missedActions.yMax".to_owned())]);
+    }
+
+    #[test]
+    fn schemas_list_every_field() {
+        let styles = &StagesConfig::json_schema()["properties"]["styles"]["additionalProperties"];
+        let differences = [
+            ("StagesConfig", schema_field_differences::<StagesConfig>(&StagesConfig::json_schema())),
+            ("StageStyle", schema_field_differences::<StageStyle>(styles)),
+            ("ActionsPlotSettings", schema_field_differences::<ActionsPlotSettings>(&ActionsPlotSettings::json_schema())),
+            ("MissedActionsPlotSettings", schema_field_differences::<MissedActionsPlotSettings>(&MissedActionsPlotSettings::json_schema())),
+            ("VisualAttentionPlotSettings", schema_field_differences::<VisualAttentionPlotSettings>(&VisualAttentionPlotSettings::json_schema())),
+            ("TeamMemberFilterSettings", schema_field_differences::<TeamMemberFilterSettings>(&TeamMemberFilterSettings::json_schema())),
+            ("LayoutSettings", schema_field_differences::<LayoutSettings>(&LayoutSettings::json_schema())),
+            ("Theme", schema_field_differences::<Theme>(&Theme::json_schema())),
+            ("IconSettings", schema_field_differences::<IconSettings>(&IconSettings::json_schema())),
+            ("Font", schema_field_differences::<Font>(&Font::json_schema())),
+            ("Margin", schema_field_differences::<Margin>(&Margin::json_schema())),
+        ];
+        for (type_name, differences) in differences {
+            assert_eq!(differences, Vec::<String>::new(), "{}", type_name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_stage_styles {
+    use super::*;
+
+    fn stages() -> StagesConfig {
+        serde_json::from_str(r##"{
+            "names": {"V-Fib 4C.1 - AMIO": "VF", "V-Fib 4C.2": "VF", "ROSC 5B - Stemi": "ROSC"},
+            "colors": ["#1f77b4", "#d62728", "#2ca02c", "#8c564b", "#9467bd"],
+            "styles": {"ROSC": {"color": "#00ff00", "icon": "/icons/rosc.png"}, "Asystole 1D No.1": {"color": "#7f7f7f"}}
+        }"##).unwrap()
+    }
+
+    #[test]
+    fn configured_styles_win() {
+        let stages = stages();
+        assert_eq!(stages.stage_color("ROSC 5B - Stemi", None), "#00ff00");
+        assert_eq!(stages.stage_color("Asystole 1D No.1", None), "#7f7f7f");
+        assert_eq!(stages.stage_icon("ROSC 5B - Stemi").map(String::as_str), Some("/icons/rosc.png"));
+        assert_eq!(stages.stage_icon("V-Fib 4C.2"), None);
+    }
+
+    #[test]
+    fn theme_colors_replace_the_styles() {
+        let stages = stages();
+        let greys = vec!["#4d4d4d".to_owned(), "#969696".to_owned()];
+        assert!(greys.contains(&stages.stage_color("ROSC 5B - Stemi", Some(&greys))));
+        assert!(greys.contains(&stages.stage_color("Asystole 1D No.1", Some(&greys))));
+        assert_eq!(stages.stage_color("VF", Some(&greys)), stages.stage_color("V-Fib 4C.2", Some(&greys)));
+    }
+
+    #[test]
+    fn bundled_print_theme_is_greyscale() {
+        let plotly_config = PlotlyConfig::load(Path::new("../plot-config")).unwrap();
+        let greys = plotly_config.layout_settings.theme(Some("print")).unwrap().stage_colors.clone().unwrap();
+        for stage_name in plotly_config.stages.names.keys().chain(plotly_config.stages.styles.keys()) {
+            assert!(greys.contains(&plotly_config.stages.stage_color(stage_name, Some(&greys))), "{}", stage_name);
+        }
+    }
+
+    #[test]
+    fn palette_colors_follow_the_display_name() {
+        let stages = stages();
+        let color = stages.stage_color("V-Fib 4C.1 - AMIO", None);
+        assert!(stages.colors.contains(&color));
+        assert_eq!(stages.stage_color("V-Fib 4C.2", None), color);
+        assert_eq!(stages.stage_color("VF", None), color);
+        // pinned so that a change of the hash, which would recolor every session, doesn't go unnoticed
+        assert_eq!(stable_hash(b"VF"), 0x093b_6207_b5cc_43dd);
+        assert_eq!(stages.stage_color("Unknown", Some(&[])), "#ffffff");
+    }
+}
@@ -0,0 +1,510 @@
+use regex::Regex;
+use serde::de::{value, DeserializeSeed, Deserialize, Deserializer, Error, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON Schema of a configuration type, kept next to the type so that it changes along with the serde attributes,
+/// `schema_field_differences` checks that the properties match the serde fields and their types. Objects with fixed properties don't
+/// set `additionalProperties: false`, a file with a key the type doesn't know still loads and `unknown_properties`
+/// lists the key instead.
+pub trait JsonSchema {
+    fn json_schema() -> Value;
+}
+
+/// Action name to group and group to icon maps.
+impl JsonSchema for HashMap<String, String> {
+    fn json_schema() -> Value {
+        json!({"type": "object", "additionalProperties": {"type": "string"}})
+    }
+}
+
+/// Standalone schema document of a configuration file. Objects with fixed properties accept a `$schema` property so
+/// that editors can be pointed at the schema from the file itself.
+pub fn schema_document<T: JsonSchema>(title: &str) -> Value {
+    let mut schema = T::json_schema();
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        properties.insert("$schema".to_owned(), json!({"type": "string"}));
+    }
+    if let Some(schema) = schema.as_object_mut() {
+        let mut document = Map::new();
+        document.insert("$schema".to_owned(), json!(SCHEMA_DIALECT));
+        document.insert("title".to_owned(), json!(title));
+        document.append(schema);
+        return Value::Object(document);
+    }
+    schema
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|number| number.fract() == 0.0),
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Array(_) | Value::Object(_) => format!("an {}", type_name(value)),
+        other => format!("{} {}", type_name(other), other),
+    }
+}
+
+fn property_path(path: &str, name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        format!("{}.{}", path, name)
+    } else {
+        format!("{}[{}]", path, Value::from(name))
+    }
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            other => other.as_str().into_iter().collect(),
+        };
+        if !types.iter().any(|expected| matches_type(value, expected)) {
+            errors.push(format!("{}: expected {}, found {}", path, types.join(" or "), describe(value)));
+            return;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            errors.push(format!("{}: expected one of {}, found {}", path, allowed.join(", "), value));
+        }
+    }
+    if let (Some(pattern), Some(text)) = (schema.get("pattern").and_then(Value::as_str), value.as_str()) {
+        match Regex::new(pattern) {
+            Ok(regex) if !regex.is_match(text) => errors.push(format!("{}: expected a string matching {}, found {}", path, pattern, value)),
+            Ok(_) => {}
+            Err(e) => errors.push(format!("{}: invalid pattern {} in the schema: {}", path, pattern, e)),
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64).filter(|minimum| number < *minimum) {
+            errors.push(format!("{}: must be at least {}, found {}", path, minimum, value));
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64).filter(|maximum| number > *maximum) {
+            errors.push(format!("{}: must be at most {}, found {}", path, maximum, value));
+        }
+    }
+    if let Some(items) = value.as_array() {
+        if let Some(min_items) = schema.get("minItems").and_then(Value::as_u64).filter(|min_items| (items.len() as u64) < *min_items) {
+            errors.push(format!("{}: expected at least {} items, found {}", path, min_items, items.len()));
+        }
+        if let Some(max_items) = schema.get("maxItems").and_then(Value::as_u64).filter(|max_items| items.len() as u64 > *max_items) {
+            errors.push(format!("{}: expected at most {} items, found {}", path, max_items, items.len()));
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (index, item) in items.iter().enumerate() {
+                validate_at(item, item_schema, &format!("{}[{}]", path, index), errors);
+            }
+        }
+    }
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for required in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+            if !object.contains_key(required) {
+                errors.push(format!("{}: missing required property {}", path, required));
+            }
+        }
+        for (name, property) in object {
+            let property_path = property_path(path, name);
+            match (properties.and_then(|properties| properties.get(name)), schema.get("additionalProperties")) {
+                (Some(property_schema), _) => validate_at(property, property_schema, &property_path, errors),
+                (None, Some(Value::Bool(false))) => {
+                    let known: Vec<&str> = properties.into_iter().flat_map(|properties| properties.keys()).map(String::as_str).filter(|name| *name != "$schema").collect();
+                    errors.push(format!("{}: unknown property, expected one of {}", property_path, known.join(", ")));
+                }
+                (None, Some(additional_schema)) if additional_schema.is_object() => validate_at(property, additional_schema, &property_path, errors),
+                (None, _) => {}
+            }
+        }
+    }
+}
+
+/// Checks a JSON document against a schema, supports the keywords the configuration schemas use: `type`, `enum`,
+/// `pattern`, `minimum`, `maximum`, `items`, `minItems`, `maxItems`, `properties`, `required` and
+/// `additionalProperties`.
+/// Every error starts with the JSON path of the offending value, e.g. `$.missedActions.maxCountPerRow`.
+pub fn validate_json(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(value, schema, "$", &mut errors);
+    errors
+}
+
+fn unknown_properties_at(value: &Value, schema: &Value, path: &str, unknown: &mut Vec<String>) {
+    if let (Some(items), Some(item_schema)) = (value.as_array(), schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            unknown_properties_at(item, item_schema, &format!("{}[{}]", path, index), unknown);
+        }
+    }
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (name, property) in object {
+            let property_path = property_path(path, name);
+            match (properties.and_then(|properties| properties.get(name)), schema.get("additionalProperties")) {
+                (Some(property_schema), _) => unknown_properties_at(property, property_schema, &property_path, unknown),
+                (None, Some(additional_schema)) => unknown_properties_at(property, additional_schema, &property_path, unknown),
+                (None, None) if properties.is_some() => unknown.push(property_path),
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+/// JSON paths of the properties of a document that the schema doesn't declare, in objects with fixed properties and
+/// no `additionalProperties`. Nothing reads them, usually they are misspelled keys.
+pub fn unknown_properties(value: &Value, schema: &Value) -> Vec<String> {
+    let mut unknown = Vec::new();
+    unknown_properties_at(value, schema, "$", &mut unknown);
+    unknown
+}
+
+/// JSON type a field is read from, as told by the deserialize method the `Deserialize` impl of the field calls.
+#[derive(Debug, Clone, PartialEq)]
+enum FieldType {
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array(Option<Box<FieldType>>),
+    Object,
+    Enum(&'static [&'static str]),
+}
+
+impl FieldType {
+    fn json_type(&self) -> &'static str {
+        match self {
+            FieldType::Boolean => "boolean",
+            FieldType::Integer => "integer",
+            FieldType::Number => "number",
+            FieldType::String | FieldType::Enum(_) => "string",
+            FieldType::Array(_) => "array",
+            FieldType::Object => "object",
+        }
+    }
+}
+
+/// Records the type the visitor asks for, options record the type of their value and sequences the type of their
+/// items. Nothing is recorded for `deserialize_any`, i.e. for types with a hand-written `Deserialize`.
+struct TypeProbe<'a>(&'a mut Option<FieldType>);
+
+impl TypeProbe<'_> {
+    fn record<T>(self, field_type: FieldType) -> Result<T, value::Error> {
+        *self.0 = Some(field_type);
+        Err(Error::custom("only the type is read"))
+    }
+}
+
+macro_rules! record_type {
+    ($($method:ident => $field_type:expr),* $(,)?) => {
+        $(fn $method<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            self.record($field_type)
+        })*
+    };
+}
+
+impl<'de> Deserializer<'de> for TypeProbe<'_> {
+    type Error = value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(Error::custom("the type is not known"))
+    }
+
+    record_type! {
+        deserialize_bool => FieldType::Boolean,
+        deserialize_i8 => FieldType::Integer, deserialize_i16 => FieldType::Integer, deserialize_i32 => FieldType::Integer,
+        deserialize_i64 => FieldType::Integer, deserialize_i128 => FieldType::Integer, deserialize_u8 => FieldType::Integer,
+        deserialize_u16 => FieldType::Integer, deserialize_u32 => FieldType::Integer, deserialize_u64 => FieldType::Integer,
+        deserialize_u128 => FieldType::Integer, deserialize_f32 => FieldType::Number, deserialize_f64 => FieldType::Number,
+        deserialize_char => FieldType::String, deserialize_str => FieldType::String, deserialize_string => FieldType::String,
+        deserialize_map => FieldType::Object,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut item = None;
+        let _ = visitor.visit_seq(FirstItem(&mut item));
+        self.record(FieldType::Array(item.map(Box::new)))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value, Self::Error> {
+        self.record(FieldType::Object)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, variants: &'static [&'static str], _visitor: V) -> Result<V::Value, Self::Error> {
+        self.record(FieldType::Enum(variants))
+    }
+
+    forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct identifier ignored_any
+    }
+}
+
+/// Hands the visitor of a sequence a single item, the type probe records its type.
+struct FirstItem<'a>(&'a mut Option<FieldType>);
+
+impl<'de> SeqAccess<'de> for FirstItem<'_> {
+    type Error = value::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        seed.deserialize(TypeProbe(self.0)).map(Some)
+    }
+}
+
+/// Hands the visitor of a struct a map with a single field, the type probe records the type of the field.
+struct SingleField<'a> {
+    field: Option<&'static str>,
+    field_type: &'a mut Option<FieldType>,
+}
+
+impl<'de> MapAccess<'de> for SingleField<'_> {
+    type Error = value::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        self.field.take().map(|field| seed.deserialize(field.into_deserializer())).transpose()
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        seed.deserialize(TypeProbe(self.field_type))
+    }
+}
+
+/// Records the fields serde derive passes to `deserialize_struct`, the names as they appear in JSON. With a field
+/// to probe, the visitor is handed that field and the type of the field is recorded.
+struct FieldNames {
+    fields: &'static [&'static str],
+    probe: Option<(&'static str, Option<FieldType>)>,
+}
+
+impl<'de> Deserializer<'de> for &mut FieldNames {
+    type Error = value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(Error::custom("expected a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        self.fields = fields;
+        match &mut self.probe {
+            Some((field, field_type)) => visitor.visit_map(SingleField { field: Some(field), field_type }),
+            None => Err(Error::custom("only the field names are read")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+        newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+fn field_type<T: for<'de> Deserialize<'de>>(field: &'static str) -> Option<FieldType> {
+    let mut probe = FieldNames { fields: &[], probe: Some((field, None)) };
+    let _ = T::deserialize(&mut probe);
+    probe.probe.and_then(|(_, field_type)| field_type)
+}
+
+/// How the schema of a property differs from the type of its field. An integer field needs a schema that allows
+/// integers only, a number field also accepts an integer schema. An enum needs the variant names as its `enum`.
+fn type_difference(field_type: &FieldType, schema: &Value, path: &str) -> Option<String> {
+    if let (FieldType::Enum(variants), Some(allowed)) = (field_type, schema.get("enum").and_then(Value::as_array)) {
+        let mut allowed: Vec<&str> = allowed.iter().filter_map(Value::as_str).collect();
+        let mut variants = variants.to_vec();
+        allowed.sort_unstable();
+        variants.sort_unstable();
+        return (allowed != variants).then(|| format!("{}: schema enum {}, field variants {}", path, allowed.join(", "), variants.join(", ")));
+    }
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        Some(other) => other.as_str().into_iter().collect(),
+        None => return None,
+    };
+    let json_type = field_type.json_type();
+    let accepted = types.contains(&json_type) || (json_type == "number" && types.contains(&"integer"));
+    if !accepted {
+        return Some(format!("{}: schema type {}, field type {}", path, types.join(" or "), json_type));
+    }
+    match (field_type, schema.get("items")) {
+        (FieldType::Array(Some(item_type)), Some(item_schema)) => type_difference(item_type, item_schema, &format!("{}[]", path)),
+        _ => None,
+    }
+}
+
+/// Differences between the `properties` of an object schema and the serde fields of the struct it describes, empty
+/// when every field is in the schema, every property is a field and the property types match the field types.
+/// Fields with a hand-written `Deserialize` have no known type, only their names are compared.
+pub fn schema_field_differences<T: for<'de> Deserialize<'de>>(schema: &Value) -> Vec<String> {
+    let mut names = FieldNames { fields: &[], probe: None };
+    let _ = T::deserialize(&mut names);
+    let fields = names.fields;
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let property_names: Vec<&str> = properties.into_iter().flat_map(|properties| properties.keys()).map(String::as_str).collect();
+    let missing = fields.iter().filter(|field| !property_names.contains(field)).map(|field| format!("{}: field missing from the schema", field));
+    let unknown = property_names.iter().filter(|property| !fields.contains(property)).map(|property| format!("{}: not a field", property));
+    let mismatched = fields.iter().filter_map(|field| {
+        let property = properties?.get(*field)?;
+        type_difference(&field_type::<T>(field)?, property, field)
+    });
+    missing.chain(unknown).chain(mismatched).collect()
+}
+
+#[cfg(test)]
+mod tests_json_schema {
+    use super::*;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "size": {"type": "integer", "minimum": 1},
+                "weighting": {"enum": ["samples", "duration"]},
+                "colors": {"type": "array", "items": {"type": "array", "items": {"type": "string"}, "minItems": 2, "maxItems": 2}},
+                "names": {"type": "object", "additionalProperties": {"type": "string"}}
+            },
+            "required": ["size"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn valid_document() {
+        let document = json!({"size": 3, "weighting": "duration", "colors": [["Team", "#1f77b4"]], "names": {"V-Tach 2D": "V Tach"}});
+        assert!(validate_json(&document, &schema()).is_empty());
+        assert!(validate_json(&json!({"size": 3.0}), &schema()).is_empty());
+    }
+
+    #[test]
+    fn errors_have_json_paths() {
+        let document = json!({"sise": 3, "weighting": "time", "colors": [["Team"], ["Patient", 2]], "names": {"V-Tach 2D": 1}});
+        assert_eq!(validate_json(&document, &schema()), vec![
+            "$: missing required property size",
+            "$.colors[0]: expected at least 2 items, found 1",
+            "$.colors[1][1]: expected string, found integer 2",
+            "$.names[\"V-Tach 2D\"]: expected string, found integer 1",
+            "$.sise: unknown property, expected one of colors, names, size, weighting",
+            "$.weighting: expected one of \"samples\", \"duration\", found \"time\"",
+        ]);
+        assert_eq!(validate_json(&json!({"size": 0}), &schema()), vec!["$.size: must be at least 1, found 0"]);
+        assert_eq!(validate_json(&json!([]), &schema()), vec!["$: expected object, found an array"]);
+    }
+
+    #[test]
+    fn documents_accept_a_schema_reference() {
+        let document = schema_document::<HashMap<String, String>>("action-groups.json");
+        assert_eq!(document["$schema"], SCHEMA_DIALECT);
+        assert_eq!(document["title"], "action-groups.json");
+        assert!(validate_json(&json!({"cpr": "CPR"}), &document).is_empty());
+    }
+
+    #[test]
+    fn strings_match_the_pattern() {
+        let schema = json!({"type": "array", "items": {"type": "string", "pattern": "^#[0-9a-fA-F]{6}$"}});
+        assert_eq!(validate_json(&json!(["#1f77b4", "red", "#1f77b4ff"]), &schema), vec![
+            "$[1]: expected a string matching ^#[0-9a-fA-F]{6}$, found \"red\"",
+            "$[2]: expected a string matching ^#[0-9a-fA-F]{6}$, found \"#1f77b4ff\"",
+        ]);
+    }
+
+    #[test]
+    fn unknown_properties_are_listed() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"size": {"type": "integer"}, "font": {"type": "object", "properties": {"size": {"type": "integer"}}}},
+            "additionalProperties": false
+        });
+        let document = json!({"size": 3, "font": {"size": 12, "colour": "red"}});
+        assert_eq!(unknown_properties(&document, &schema), vec!["$.font.colour"]);
+        // fields of objects declaring additionalProperties: false are errors rather than unknown properties
+        assert_eq!(unknown_properties(&json!({"sise": 3}), &schema), Vec::<String>::new());
+    }
+
+    #[test]
+    fn field_differences_use_the_serde_names() {
+        #[derive(serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Settings {
+            #[serde(rename = "windowSize")]
+            window_size: u32,
+            #[serde(default)]
+            step: Option<u32>,
+        }
+        let schema = json!({"type": "object", "properties": {"windowSize": {"type": "integer"}, "weighting": {"type": "string"}}});
+        assert_eq!(schema_field_differences::<Settings>(&schema), vec!["step: field missing from the schema", "weighting: not a field"]);
+    }
+
+    #[test]
+    fn field_differences_compare_the_types() {
+        #[derive(serde::Deserialize)]
+        #[allow(dead_code)]
+        enum Weighting {
+            #[serde(rename = "samples")]
+            Samples,
+            #[serde(rename = "duration")]
+            Duration,
+        }
+        #[derive(serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Settings {
+            size: u32,
+            step: Option<f64>,
+            colors: Vec<(String, String)>,
+            weighting: Weighting,
+            visible: bool,
+        }
+        let schema = json!({"type": "object", "properties": {
+            "size": {"type": "integer"},
+            "step": {"type": "integer"},
+            "colors": {"type": "array", "items": {"type": "array", "items": {"type": "string"}}},
+            "weighting": {"enum": ["samples", "duration"]},
+            "visible": {"type": "boolean"}
+        }});
+        assert_eq!(schema_field_differences::<Settings>(&schema), Vec::<String>::new());
+
+        let schema = json!({"type": "object", "properties": {
+            "size": {"type": "number"},
+            "step": {"type": "string"},
+            "colors": {"type": "array", "items": {"type": "string"}},
+            "weighting": {"enum": ["samples", "time"]},
+            "visible": {"type": ["boolean", "null"]}
+        }});
+        assert_eq!(schema_field_differences::<Settings>(&schema), vec![
+            "size: schema type number, field type integer",
+            "step: schema type string, field type number",
+            "colors[]: schema type string, field type array",
+            "weighting: schema enum samples, time, field variants duration, samples",
+        ]);
+    }
+}
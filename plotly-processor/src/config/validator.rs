@@ -0,0 +1,424 @@
+use crate::config::layout_settings::LayoutSettings;
+use crate::config::plotly_mappings::{
+    PlotlyConfig, StageStyle, ACTION_GROUPS_FILE, ACTION_GROUP_ICONS_FILE, ACTION_PLOT_SETTINGS_FILE, ACTION_PLOT_STAGES_FILE,
+    DEFAULT_ACTION_GROUP_ICON_ATTR, DEFAULT_ACTION_GROUP_NAME, LAYOUT_SETTINGS_FILE, TEAM_MEMBER_FILTER_SETTINGS_FILE,
+    VISUAL_ATTENTION_PLOT_SETTINGS_FILE,
+};
+use mteam_dashboard_action_processor::plot_structures::{ActionPlotPoint, PeriodType};
+use mteam_dashboard_action_processor::process_csv;
+use mteam_dashboard_cognitive_load_processor::format::SUPPORTED_FILE_EXTENSIONS;
+use mteam_dashboard_utils::alignment::StreamOffset;
+use mteam_dashboard_utils::strings::snake_case_file_to_title_case;
+use mteam_dashboard_visual_attention_processor::category::{CategoryLevel, HIERARCHY_SEPARATOR};
+use mteam_dashboard_visual_attention_processor::file_processor::normalize_visual_attention_load_data;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::{fmt, io};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The plots can not be built, or are built wrong, with the configuration.
+    Error,
+    /// The plots are built but probably not the way it was intended, e.g. an action group without an icon.
+    Warning,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub file: String,
+    /// JSON key or entry the issue is about, e.g. `missedActions.maxCountPerRow` or an action group name.
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {} [{}]: {}", severity, self.file, self.key, self.message)
+    }
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn add(&mut self, severity: Severity, file: &str, key: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(ValidationIssue { severity, file: file.to_owned(), key: key.into(), message: message.into() });
+    }
+
+    fn error(&mut self, file: &str, key: impl Into<String>, message: impl Into<String>) {
+        self.add(Severity::Error, file, key, message);
+    }
+
+    fn warning(&mut self, file: &str, key: impl Into<String>, message: impl Into<String>) {
+        self.add(Severity::Warning, file, key, message);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    pub fn extend(&mut self, other: ValidationReport) {
+        self.issues.extend(other.issues);
+    }
+}
+
+/// Data of a session the configuration is checked against, e.g. a recent recording of the study.
+#[derive(Default, Debug)]
+pub struct SampleSession {
+    pub action_points: Vec<ActionPlotPoint>,
+    /// Team member names derived from the data file names, e.g. "Team Lead" for team_lead.json.
+    pub team_members: Vec<String>,
+    /// Visual attention categories of the gaze samples after the category aliases are applied.
+    pub visual_attention_categories: Vec<String>,
+}
+
+fn files_with_extensions(dir: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>, io::Error> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.iter().any(|extension| ext.eq_ignore_ascii_case(extension))))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+impl SampleSession {
+    /// Reads a session folder laid out like the local file data source: the actions csv (or txt) file and the
+    /// cognitive-load and visual-attention folders with a file per team member.
+    pub fn load(session_dir: &Path, config: &PlotlyConfig) -> Result<Self, String> {
+        let read_error = |e: io::Error| format!("Error reading session folder {:?}: {}", session_dir, e);
+        let csv_file = files_with_extensions(session_dir, &["csv", "txt"]).map_err(read_error)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("No CSV file found in folder {:?}", session_dir))?;
+        let reader = File::open(&csv_file).map_err(|e| format!("Error opening {:?}: {}", csv_file, e))?;
+        let action_points = process_csv(reader, 10).filter_map(Result::ok).collect();
+
+        let mut team_members = BTreeSet::new();
+        for (folder, extensions) in [("cognitive-load", SUPPORTED_FILE_EXTENSIONS), ("visual-attention", &["json"][..])] {
+            for file in files_with_extensions(&session_dir.join(folder), extensions).map_err(read_error)? {
+                let file_name = file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+                team_members.insert(snake_case_file_to_title_case(file_name));
+            }
+        }
+
+        let category_mapping = config.visual_attention_plot_settings.category_mapping(CategoryLevel::Detail);
+        let mut visual_attention_categories = BTreeSet::new();
+        for file in files_with_extensions(&session_dir.join("visual-attention"), &["json"]).map_err(read_error)? {
+            let mut reader = File::open(&file).map_err(|e| format!("Error opening {:?}: {}", file, e))?;
+            let samples = normalize_visual_attention_load_data(&mut reader, &category_mapping, &StreamOffset::default())
+                .map_err(|e| format!("Error reading {:?}: {}", file, e))?;
+            visual_attention_categories.extend(samples.filter_map(|(_, category)| category));
+        }
+
+        Ok(Self {
+            action_points,
+            team_members: team_members.into_iter().collect(),
+            visual_attention_categories: visual_attention_categories.into_iter().collect(),
+        })
+    }
+}
+
+/// Stage backgrounds append an alpha channel to the stage colors, so they must be `#rrggbb`.
+fn is_rgb_hex(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn top_level_category(category: &str) -> &str {
+    category.split(HIERARCHY_SEPARATOR).next().unwrap_or(category).trim()
+}
+
+fn validate_stage_colors(report: &mut ValidationReport, file: &str, key: &str, colors: &[String]) {
+    if colors.is_empty() {
+        report.error(file, key, "colors must not be empty");
+    }
+    for color in colors.iter().filter(|color| !is_rgb_hex(color)) {
+        report.error(file, key, format!("{} is not a #rrggbb color, an alpha channel is appended to the stage colors", color));
+    }
+}
+
+fn validate_action_groups(config: &PlotlyConfig, report: &mut ValidationReport) {
+    for action_name in config.action_groups.keys().filter(|name| **name != name.to_lowercase()) {
+        report.error(ACTION_GROUPS_FILE, action_name, "never matches, action names are looked up in lower case");
+    }
+    let groups: BTreeSet<&String> = config.action_groups.values().collect();
+    for group in groups.iter().filter(|group| !config.action_group_icons.contains_key(group.as_str())) {
+        report.warning(ACTION_GROUP_ICONS_FILE, *group, format!(
+            "action group of {} has no icon, its actions get the `{}` icon", ACTION_GROUPS_FILE, DEFAULT_ACTION_GROUP_ICON_ATTR
+        ));
+    }
+    if !config.action_group_icons.contains_key(DEFAULT_ACTION_GROUP_NAME) {
+        report.warning(ACTION_GROUP_ICONS_FILE, DEFAULT_ACTION_GROUP_NAME, format!(
+            "no icon for the actions missing from {}, they get the `{}` icon", ACTION_GROUPS_FILE, DEFAULT_ACTION_GROUP_ICON_ATTR
+        ));
+    }
+    let mut icons: Vec<(&String, &String)> = config.action_group_icons.iter().collect();
+    icons.sort();
+    for (group, icon) in icons {
+        if icon.trim().is_empty() {
+            report.error(ACTION_GROUP_ICONS_FILE, group, "icon path must not be empty");
+        }
+        if group != DEFAULT_ACTION_GROUP_NAME && !groups.contains(group) {
+            report.warning(ACTION_GROUP_ICONS_FILE, group, format!("not an action group of {}, the icon is never used", ACTION_GROUPS_FILE));
+        }
+    }
+}
+
+fn validate_stages(config: &PlotlyConfig, report: &mut ValidationReport) {
+    validate_stage_colors(report, ACTION_PLOT_STAGES_FILE, "colors", &config.stages.colors);
+    let mut names: Vec<(&String, &String)> = config.stages.names.iter().collect();
+    names.sort();
+    for (stage, _) in names.into_iter().filter(|(_, display_name)| display_name.trim().is_empty()) {
+        report.warning(ACTION_PLOT_STAGES_FILE, format!("names.{}", stage), format!("empty display name, stage {} is shown without a name", stage));
+    }
+    let mut styles: Vec<(&String, &StageStyle)> = config.stages.styles.iter().collect();
+    styles.sort_by_key(|(stage, _)| *stage);
+    for (stage, style) in styles {
+        if let Some(color) = style.color.as_ref().filter(|color| !is_rgb_hex(color)) {
+            report.error(ACTION_PLOT_STAGES_FILE, format!("styles.{}.color", stage), format!("{} is not a #rrggbb color, an alpha channel is appended to the stage colors", color));
+        }
+        if style.icon.as_ref().is_some_and(|icon| icon.trim().is_empty()) {
+            report.error(ACTION_PLOT_STAGES_FILE, format!("styles.{}.icon", stage), "icon path must not be empty");
+        }
+    }
+}
+
+fn validate_action_plot_settings(config: &PlotlyConfig, report: &mut ValidationReport) {
+    let settings = &config.action_plot_settings;
+    if settings.y_increment == 0.0 {
+        report.error(ACTION_PLOT_SETTINGS_FILE, "yIncrement", "must not be 0, the action groups would overlap");
+    }
+    if !(0.0..=1.0).contains(&settings.y_annotation) {
+        report.warning(ACTION_PLOT_SETTINGS_FILE, "yAnnotation", "is a fraction of the plot height, values outside of 0..1 place the stage names outside of the plot");
+    }
+    if settings.missed_actions.max_count_per_row == 0 {
+        report.error(ACTION_PLOT_SETTINGS_FILE, "missedActions.maxCountPerRow", "must be greater than 0");
+    }
+    if settings.missed_actions.y_increment == 0.0 {
+        report.error(ACTION_PLOT_SETTINGS_FILE, "missedActions.yIncrement", "must not be 0, the missed action rows would overlap");
+    }
+}
+
+fn validate_visual_attention_settings(config: &PlotlyConfig, report: &mut ValidationReport) {
+    let file = VISUAL_ATTENTION_PLOT_SETTINGS_FILE;
+    let settings = &config.visual_attention_plot_settings;
+    if settings.window_size_secs == 0 {
+        report.error(file, "windowSizeSeconds", "must be greater than 0");
+    }
+    match settings.step_secs {
+        Some(0) => report.error(file, "stepSeconds", "must be greater than 0"),
+        Some(step_secs) if step_secs > settings.window_size_secs => {
+            report.warning(file, "stepSeconds", "is longer than the window, the samples between the windows are left out");
+        }
+        _ => {}
+    }
+    if settings.max_sample_duration_secs <= 0.0 {
+        report.error(file, "maxSampleDurationSeconds", "must be greater than 0");
+    }
+    let mut categories = HashSet::new();
+    for (category, _) in &settings.ordered_category_color_tuples {
+        if !categories.insert(category.as_str()) {
+            report.warning(file, format!("orderedColorMap.{}", category), "listed more than once, the first color is used");
+        }
+    }
+    if settings.fallback_colors.is_empty() {
+        report.warning(file, "fallbackColors", "empty, categories missing from orderedColorMap get the built-in fallback colors");
+    }
+    let mut aliases: Vec<(&String, &String)> = settings.category_aliases.iter().collect();
+    aliases.sort();
+    for (label, category) in aliases.into_iter().filter(|(_, category)| !categories.contains(top_level_category(category))) {
+        report.warning(file, format!("categoryAliases.{}", label), format!("{} is not in orderedColorMap, it gets a fallback color", category));
+    }
+}
+
+fn validate_team_member_filter_settings(config: &PlotlyConfig, report: &mut ValidationReport) {
+    let order = &config.team_member_filter_settings.filter_selection_order;
+    if order.is_empty() {
+        report.error(TEAM_MEMBER_FILTER_SETTINGS_FILE, "filterSelectionOrder", "must not be empty, listing the team member files fails without it");
+    }
+    let mut members = HashSet::new();
+    for member in order.iter().filter(|member| !members.insert(member.as_str())) {
+        report.warning(TEAM_MEMBER_FILTER_SETTINGS_FILE, format!("filterSelectionOrder.{}", member), "listed more than once");
+    }
+}
+
+fn validate_layout_settings(settings: &LayoutSettings, report: &mut ValidationReport) {
+    let file = LAYOUT_SETTINGS_FILE;
+    if !settings.themes.contains_key(&settings.default_theme) {
+        report.error(file, "defaultTheme", format!("{} is not one of the themes", settings.default_theme));
+    }
+    for (name, theme) in &settings.themes {
+        if let Some(stage_colors) = &theme.stage_colors {
+            validate_stage_colors(report, file, &format!("themes.{}.stageColors", name), stage_colors);
+        }
+    }
+    if settings.icon.size_x <= 0.0 || settings.icon.size_y <= 0.0 {
+        report.error(file, "icon", "sizeX and sizeY must be greater than 0");
+    }
+    let icon = &settings.icon;
+    if icon.min_size_x <= 0.0 || icon.min_size_y <= 0.0 || icon.min_size_x > icon.max_size_x || icon.min_size_y > icon.max_size_y {
+        report.error(file, "icon", "minSizeX and minSizeY must be greater than 0 and not greater than maxSizeX and maxSizeY");
+    }
+    if icon.icons_across < 1.0 {
+        report.error(file, "icon.iconsAcross", "must be at least 1");
+    }
+    for zoom in icon.zoom_levels.iter().filter(|zoom| **zoom < 1.0) {
+        report.error(file, "icon.zoomLevels", format!("{} is not a zoom factor, zoom levels must be at least 1", zoom));
+    }
+    let sizes = [
+        ("stageAnnotationFont.size", settings.stage_annotation_font.size),
+        ("sectionAnnotationFont.size", settings.section_annotation_font.size),
+        ("actionTextFontSize", settings.action_text_font_size),
+        ("markerSize", settings.marker_size),
+    ];
+    for (key, _) in sizes.into_iter().filter(|(_, size)| *size <= 0) {
+        report.error(file, key, "must be greater than 0");
+    }
+}
+
+fn validate_unknown_properties(config: &PlotlyConfig, report: &mut ValidationReport) {
+    for (file, path) in &config.unknown_properties {
+        report.warning(file, path.trim_start_matches("$.").to_owned(), "unknown property, it is ignored");
+    }
+}
+
+/// Checks the plot-config files on their own and against each other.
+pub fn validate_config(config: &PlotlyConfig) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    validate_unknown_properties(config, &mut report);
+    validate_action_groups(config, &mut report);
+    validate_stages(config, &mut report);
+    validate_action_plot_settings(config, &mut report);
+    validate_visual_attention_settings(config, &mut report);
+    validate_team_member_filter_settings(config, &mut report);
+    validate_layout_settings(&config.layout_settings, &mut report);
+    report
+}
+
+/// Checks that the names found in a session's data are configured, every issue is a warning since the plots are
+/// still built, just with default icons, raw stage names or fallback colors.
+pub fn validate_session(config: &PlotlyConfig, session: &SampleSession) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let mut action_names = BTreeSet::new();
+    let mut stage_names = BTreeSet::new();
+    for point in &session.action_points {
+        match point {
+            ActionPlotPoint::Action(action) => { action_names.insert(&action.name); }
+            ActionPlotPoint::Error(action) => { action_names.insert(&action.name); }
+            ActionPlotPoint::MissedAction(action) => { action_names.insert(&action.name); }
+            ActionPlotPoint::Period(PeriodType::Stage, start, _) => { stage_names.insert(&start.stage.1); }
+            ActionPlotPoint::Period(_, _, _) => {}
+        }
+    }
+    for action_name in action_names.into_iter().filter(|name| !config.action_groups.contains_key(&name.to_lowercase())) {
+        report.warning(ACTION_GROUPS_FILE, action_name, "action of the session has no action group, it gets the default group and icon");
+    }
+    for stage_name in stage_names.into_iter().filter(|name| !config.stages.names.contains_key(*name)) {
+        report.warning(ACTION_PLOT_STAGES_FILE, format!("names.{}", stage_name), "stage of the session has no display name, the raw name is shown");
+    }
+    let order = &config.team_member_filter_settings.filter_selection_order;
+    let members: BTreeSet<&String> = session.team_members.iter().collect();
+    for member in members.into_iter().filter(|member| !order.contains(member)) {
+        report.warning(TEAM_MEMBER_FILTER_SETTINGS_FILE, format!("filterSelectionOrder.{}", member), "team member of the session is not listed, it is sorted after the listed ones");
+    }
+    let colored: HashSet<&str> = config.visual_attention_plot_settings.ordered_category_color_tuples.iter()
+        .map(|(category, _)| category.as_str())
+        .collect();
+    let categories: BTreeSet<&str> = session.visual_attention_categories.iter().map(|category| top_level_category(category)).collect();
+    for category in categories.into_iter().filter(|category| !colored.contains(category)) {
+        report.warning(VISUAL_ATTENTION_PLOT_SETTINGS_FILE, format!("orderedColorMap.{}", category), "category of the session is not in orderedColorMap, it gets a fallback color (add a categoryAliases entry if it is another name of a configured category)");
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests_config_validator {
+    use super::*;
+    use mteam_dashboard_action_processor::plot_structures::{Action, PlotLocation};
+    use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+    use std::path::Path;
+
+    fn config() -> PlotlyConfig {
+        PlotlyConfig::load(Path::new("../plot-config")).unwrap()
+    }
+
+    fn keys(report: &ValidationReport, severity: Severity) -> Vec<String> {
+        report.issues.iter().filter(|issue| issue.severity == severity).map(|issue| format!("{} {}", issue.file, issue.key)).collect()
+    }
+
+    #[test]
+    fn bundled_config_has_no_errors() {
+        let report = validate_config(&config());
+        assert!(!report.has_errors(), "{:?}", keys(&report, Severity::Error));
+    }
+
+    #[test]
+    fn cross_checks_the_files() {
+        let mut config = config();
+        config.action_groups.insert("Select Epi".to_owned(), "Epinephrine".to_owned());
+        config.action_group_icons.insert("Unused".to_owned(), "/icons/unused.png".to_owned());
+        config.stages.colors = vec!["#1f77b4".to_owned(), "rgb(0, 0, 0)".to_owned()];
+        config.stages.styles.insert("ROSC".to_owned(), StageStyle { color: Some("green".to_owned()), icon: Some(String::new()) });
+        config.team_member_filter_settings.filter_selection_order.clear();
+        config.layout_settings.default_theme = "neon".to_owned();
+
+        let report = validate_config(&config);
+        let errors = keys(&report, Severity::Error);
+        assert!(errors.contains(&"action-groups.json Select Epi".to_owned()));
+        assert!(errors.contains(&"action-plot-stages.json colors".to_owned()));
+        assert!(errors.contains(&"action-plot-stages.json styles.ROSC.color".to_owned()));
+        assert!(errors.contains(&"action-plot-stages.json styles.ROSC.icon".to_owned()));
+        assert!(errors.contains(&"team-member-filter-settings.json filterSelectionOrder".to_owned()));
+        assert!(errors.contains(&"layout-settings.json defaultTheme".to_owned()));
+        let warnings = keys(&report, Severity::Warning);
+        assert!(warnings.contains(&"action-group-icons.json Epinephrine".to_owned()));
+        assert!(warnings.contains(&"action-group-icons.json Unused".to_owned()));
+        assert!(config.validate().unwrap_err().contains("error: action-plot-stages.json [colors]: rgb(0, 0, 0) is not a #rrggbb color"));
+    }
+
+    #[test]
+    fn checks_a_sample_session() {
+        let config = config();
+        let location = |stage: &str| PlotLocation { timestamp: seconds_to_csv_row_time(10), stage: (1, stage.to_owned()) };
+        let action = |name: &str| ActionPlotPoint::Action(Action { location: location("V-Tach 2D"), name: name.to_owned(), action_category: String::new(), shock_value: String::new() });
+        let session = SampleSession {
+            action_points: vec![
+                ActionPlotPoint::Period(PeriodType::Stage, location("Unknown Stage"), location("Unknown Stage")),
+                action("Mystery Drug"),
+                action(config.action_groups.keys().next().unwrap()),
+            ],
+            team_members: vec!["Team Lead".to_owned(), "Observer".to_owned()],
+            visual_attention_categories: vec!["Patient/Airway".to_owned(), "Ceiling".to_owned()],
+        };
+        let report = validate_session(&config, &session);
+        assert!(!report.has_errors());
+        assert_eq!(keys(&report, Severity::Warning), vec![
+            "action-groups.json Mystery Drug",
+            "action-plot-stages.json names.Unknown Stage",
+            "team-member-filter-settings.json filterSelectionOrder.Observer",
+            "visual-attention-plot-settings.json orderedColorMap.Ceiling",
+        ]);
+    }
+}
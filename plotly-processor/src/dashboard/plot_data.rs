@@ -0,0 +1,72 @@
+use crate::actions::icon_sizing::IconSize;
+use crate::actions::plot_data::ActionsPlotDataItem;
+use crate::cognitive_load::plot_data::CognitiveLoadSeries;
+use crate::layout::{Layout, YAxis};
+use crate::visual_attention::plot_data::VisualAttentionCategory;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum DashboardTraceData {
+    Actions(ActionsPlotDataItem),
+    CognitiveLoad(CognitiveLoadSeries),
+    VisualAttention(VisualAttentionCategory),
+}
+
+/// Trace of the dashboard figure, `yaxis` names the subplot it is drawn in ("y", "y2", ...), all traces share the
+/// time axis "x".
+#[derive(Serialize, Debug)]
+pub struct DashboardTrace {
+    #[serde(flatten)]
+    pub data: DashboardTraceData,
+    pub xaxis: String,
+    pub yaxis: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub legendgroup: Option<String>,
+    pub showlegend: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DashboardPanelKind {
+    Actions,
+    CognitiveLoad,
+    VisualAttention,
+}
+
+/// Subplot of the dashboard figure, from top to bottom.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DashboardPanel {
+    pub kind: DashboardPanelKind,
+    /// Team member of a visual attention panel, the cognitive load panel holds all selected members.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member: Option<String>,
+    /// Y axis of the traces in the panel.
+    pub yaxis: String,
+    pub domain: [f64; 2],
+}
+
+/// The layout of the actions plot with the y axes of the panels below it, serialized as "yaxis2", "yaxis3", ...
+#[derive(Serialize, Debug)]
+pub struct DashboardLayout {
+    #[serde(flatten)]
+    pub layout: Layout,
+    pub height: u32,
+    pub barmode: String,
+    #[serde(flatten)]
+    pub panel_axes: BTreeMap<String, YAxis>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DashboardPlotData {
+    pub data: Vec<DashboardTrace>,
+    pub layout: DashboardLayout,
+    pub panels: Vec<DashboardPanel>,
+    #[serde(rename = "actionGroupIcons")]
+    pub action_group_icons: BTreeMap<String, String>,
+    #[serde(rename = "iconSizes", skip_serializing_if = "Vec::is_empty")]
+    pub icon_sizes: Vec<IconSize>,
+    /// Visual attention warnings of the members, prefixed with the member name.
+    pub warnings: Vec<String>,
+}
@@ -0,0 +1,288 @@
+use crate::actions::plot_data::ActionsPlotData;
+use crate::cognitive_load::transformers::to_time_series;
+use crate::dashboard::plot_data::{DashboardLayout, DashboardPanel, DashboardPanelKind, DashboardPlotData, DashboardTrace, DashboardTraceData};
+use crate::layout::YAxis;
+use crate::shape::Shape;
+use crate::visual_attention::plot_data::VisualAttentionPlotData;
+use mteam_dashboard_cognitive_load_processor::file_processor::CognitiveLoadSample;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The actions panel is this many times as tall as a team member panel.
+const ACTIONS_PANEL_WEIGHT: f64 = 3.0;
+/// Vertical space between two panels, as a fraction of the figure.
+const PANEL_GAP: f64 = 0.03;
+const ACTIONS_PANEL_HEIGHT_PX: u32 = 600;
+const MEMBER_PANEL_HEIGHT_PX: u32 = 180;
+
+/// Data of a selected team member, either can be missing when the session has no such file for the member.
+pub struct DashboardMember {
+    pub name: String,
+    pub cognitive_load: Option<Vec<CognitiveLoadSample>>,
+    pub visual_attention: Option<VisualAttentionPlotData>,
+}
+
+/// Domains of panels stacked from top to bottom, the heights are proportional to the weights.
+fn stack_domains(weights: &[f64]) -> Vec<[f64; 2]> {
+    let gaps = PANEL_GAP * weights.len().saturating_sub(1) as f64;
+    let total_weight: f64 = weights.iter().sum();
+    let mut top = 1.0;
+    weights.iter()
+        .map(|weight| {
+            let bottom = (top - (1.0 - gaps) * weight / total_weight).max(0.0);
+            let domain = [bottom, top];
+            top = bottom - PANEL_GAP;
+            domain
+        })
+        .collect()
+}
+
+/// Trace reference of the nth y axis ("y", "y2", ...) and its key in the layout ("yaxis", "yaxis2", ...).
+fn axis_names(index: usize) -> (String, String) {
+    match index {
+        0 => ("y".to_owned(), "yaxis".to_owned()),
+        _ => (format!("y{}", index + 1), format!("yaxis{}", index + 1)),
+    }
+}
+
+/// One shape per stage spanning the whole figure height, instead of the performed and missed actions areas of the
+/// actions plot.
+fn full_height_stage_shapes(shapes: Vec<Shape>) -> Vec<Shape> {
+    let mut stage_shapes: Vec<Shape> = Vec::new();
+    for mut shape in shapes {
+        if stage_shapes.iter().any(|stage| stage.name == shape.name && stage.x0 == shape.x0 && stage.x1 == shape.x1) {
+            continue;
+        }
+        shape.yref = "paper".to_owned();
+        shape.y0 = 0.0.into();
+        shape.y1 = 1.0.into();
+        stage_shapes.push(shape);
+    }
+    stage_shapes
+}
+
+/// Combines the actions plot with the cognitive load and visual attention of the selected members into one figure:
+/// the actions on top, the cognitive load of all members in one panel below and a visual attention panel per member.
+/// The panels share the time axis, the stages span all of them.
+pub fn to_dashboard_plotly_data(actions: ActionsPlotData, members: Vec<DashboardMember>) -> DashboardPlotData {
+    let mut panels = vec![(DashboardPanelKind::Actions, None)];
+    if members.iter().any(|member| member.cognitive_load.is_some()) {
+        panels.push((DashboardPanelKind::CognitiveLoad, None));
+    }
+    panels.extend(members.iter()
+        .filter(|member| member.visual_attention.is_some())
+        .map(|member| (DashboardPanelKind::VisualAttention, Some(member.name.clone()))));
+
+    let weights: Vec<f64> = panels.iter()
+        .map(|(kind, _)| if *kind == DashboardPanelKind::Actions { ACTIONS_PANEL_WEIGHT } else { 1.0 })
+        .collect();
+    let panels: Vec<DashboardPanel> = panels.into_iter().zip(stack_domains(&weights)).enumerate()
+        .map(|(index, ((kind, member), domain))| DashboardPanel { kind, member, yaxis: axis_names(index).0, domain })
+        .collect();
+
+    let mut layout = actions.layout;
+    let actions_domain = panels[0].domain;
+    layout.yaxis.domain = Some(actions_domain);
+    layout.xaxis.anchor = panels.last().filter(|panel| panel.kind != DashboardPanelKind::Actions).map(|panel| panel.yaxis.clone());
+    layout.shapes = full_height_stage_shapes(layout.shapes);
+    for annotation in layout.annotations.iter_mut().filter(|annotation| annotation.yref == "paper") {
+        annotation.y = (actions_domain[0] + annotation.y as f64 * (actions_domain[1] - actions_domain[0])) as f32;
+    }
+    layout.showlegend = panels.len() > 1;
+    layout.legend.y = actions_domain[0];
+
+    let mut data: Vec<DashboardTrace> = actions.data.into_iter()
+        .map(|item| DashboardTrace {
+            data: DashboardTraceData::Actions(item),
+            xaxis: "x".to_owned(),
+            yaxis: "y".to_owned(),
+            legendgroup: None,
+            showlegend: false,
+        })
+        .collect();
+
+    let mut panel_axes = BTreeMap::new();
+    let mut legend_categories = BTreeSet::new();
+    let mut warnings = Vec::new();
+    for (index, panel) in panels.iter().enumerate().skip(1) {
+        let (yaxis, axis_key) = axis_names(index);
+        let title = match panel.kind {
+            DashboardPanelKind::VisualAttention => panel.member.clone(),
+            _ => Some("Cognitive Load".to_owned()),
+        };
+        let range = if panel.kind == DashboardPanelKind::VisualAttention { vec![0.0.into(), 1.0.into()] } else { Vec::new() };
+        panel_axes.insert(axis_key, YAxis { visible: true, range, domain: Some(panel.domain), title });
+
+        if panel.kind == DashboardPanelKind::CognitiveLoad {
+            for member in &members {
+                if let Some(samples) = &member.cognitive_load {
+                    data.push(DashboardTrace {
+                        data: DashboardTraceData::CognitiveLoad(to_time_series(&member.name, samples)),
+                        xaxis: "x".to_owned(),
+                        yaxis: yaxis.clone(),
+                        legendgroup: Some(member.name.clone()),
+                        showlegend: true,
+                    });
+                }
+            }
+        }
+    }
+
+    let visual_attention_panels = panels.iter().enumerate().filter(|(_, panel)| panel.kind == DashboardPanelKind::VisualAttention);
+    let visual_attention_members = members.into_iter().filter_map(|member| member.visual_attention.map(|plot_data| (member.name, plot_data)));
+    for ((index, _), (member_name, plot_data)) in visual_attention_panels.zip(visual_attention_members) {
+        let yaxis = axis_names(index).0;
+        for category in plot_data.data {
+            // the categories are the same in every panel, they are listed in the legend once
+            let showlegend = legend_categories.insert(category.name.clone());
+            data.push(DashboardTrace {
+                legendgroup: Some(category.name.clone()),
+                data: DashboardTraceData::VisualAttention(category),
+                xaxis: "x".to_owned(),
+                yaxis: yaxis.clone(),
+                showlegend,
+            });
+        }
+        warnings.extend(plot_data.warnings.into_iter().map(|warning| format!("{}: {}", member_name, warning)));
+    }
+
+    let height = ACTIONS_PANEL_HEIGHT_PX + MEMBER_PANEL_HEIGHT_PX * (panels.len() as u32 - 1);
+    DashboardPlotData {
+        data,
+        layout: DashboardLayout { layout, height, barmode: "stack".to_owned(), panel_axes },
+        panels,
+        action_group_icons: actions.action_group_icons,
+        icon_sizes: actions.icon_sizes,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests_dashboard {
+    use super::*;
+    use crate::actions::transformers::to_plotly_data;
+    use crate::config::plotly_mappings::PlotlyConfig;
+    use crate::value::PlotValue;
+    use crate::visual_attention::plot_data::VisualAttentionCategory;
+    use mteam_dashboard_action_processor::process_csv;
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::path::Path;
+
+    fn actions_plot_data(plotly_config: &PlotlyConfig) -> ActionsPlotData {
+        let file = File::open("../action-processor/timeline-multiplayer-09182024.csv").unwrap();
+        let theme = plotly_config.layout_settings.theme(None).unwrap();
+        to_plotly_data(plotly_config, theme, process_csv(file, 10))
+    }
+
+    fn visual_attention(categories: &[&str]) -> VisualAttentionPlotData {
+        let data = categories.iter()
+            .map(|category| VisualAttentionCategory {
+                x: vec![PlotValue::date_time("2024-01-01 00:00:10")],
+                y: vec![Some(0.5)],
+                name: category.to_string(),
+                plot_type: "bar".to_owned(),
+                marker: HashMap::new(),
+                no_data: vec![false],
+                unreliable: vec![false],
+                sample_count: vec![4],
+            })
+            .collect();
+        VisualAttentionPlotData { data, warnings: vec![format!("Category \"{}\" is not in orderedColorMap", categories[0])] }
+    }
+
+    fn member(name: &str, with_cognitive_load: bool, with_visual_attention: bool) -> DashboardMember {
+        DashboardMember {
+            name: name.to_owned(),
+            cognitive_load: with_cognitive_load.then(|| vec![
+                CognitiveLoadSample { seconds: 0.0, load: Some(0.2), confidence: None },
+                CognitiveLoadSample { seconds: 5.0, load: Some(0.4), confidence: None },
+            ]),
+            visual_attention: with_visual_attention.then(|| visual_attention(&["Monitor", "Patient"])),
+        }
+    }
+
+    #[test]
+    fn domains_are_stacked_from_the_top() {
+        let domains = stack_domains(&[3.0, 1.0, 1.0]);
+        let expected = [[0.436, 1.0], [0.218, 0.406], [0.0, 0.188]];
+        for (domain, expected) in domains.iter().zip(expected) {
+            assert!((domain[0] - expected[0]).abs() < 1e-9 && (domain[1] - expected[1]).abs() < 1e-9, "{:?}", domain);
+        }
+        assert_eq!(stack_domains(&[3.0]), vec![[0.0, 1.0]]);
+    }
+
+    #[test]
+    fn panels_of_the_selected_members() {
+        let plotly_config = PlotlyConfig::load(Path::new("../plot-config")).unwrap();
+        let members = vec![member("Team Lead", true, true), member("Airway", true, false), member("Compressor", false, true)];
+        let dashboard = to_dashboard_plotly_data(actions_plot_data(&plotly_config), members);
+
+        let panels: Vec<(DashboardPanelKind, Option<&str>, &str)> = dashboard.panels.iter()
+            .map(|panel| (panel.kind, panel.member.as_deref(), panel.yaxis.as_str()))
+            .collect();
+        assert_eq!(panels, vec![
+            (DashboardPanelKind::Actions, None, "y"),
+            (DashboardPanelKind::CognitiveLoad, None, "y2"),
+            (DashboardPanelKind::VisualAttention, Some("Team Lead"), "y3"),
+            (DashboardPanelKind::VisualAttention, Some("Compressor"), "y4"),
+        ]);
+        assert_eq!(dashboard.layout.layout.xaxis.anchor.as_deref(), Some("y4"));
+        assert_eq!(dashboard.layout.panel_axes.keys().collect::<Vec<_>>(), vec!["yaxis2", "yaxis3", "yaxis4"]);
+        assert_eq!(dashboard.layout.panel_axes["yaxis3"].title.as_deref(), Some("Team Lead"));
+        assert_eq!(dashboard.layout.height, 600 + 3 * 180);
+        assert_eq!(dashboard.warnings, vec![
+            "Team Lead: Category \"Monitor\" is not in orderedColorMap",
+            "Compressor: Category \"Monitor\" is not in orderedColorMap",
+        ]);
+
+        let traces: Vec<(&str, Option<&str>, bool)> = dashboard.data.iter()
+            .filter(|trace| trace.yaxis != "y")
+            .map(|trace| (trace.yaxis.as_str(), trace.legendgroup.as_deref(), trace.showlegend))
+            .collect();
+        assert_eq!(traces, vec![
+            ("y2", Some("Team Lead"), true),
+            ("y2", Some("Airway"), true),
+            ("y3", Some("Monitor"), true),
+            ("y3", Some("Patient"), true),
+            ("y4", Some("Monitor"), false),
+            ("y4", Some("Patient"), false),
+        ]);
+        assert!(dashboard.data.iter().all(|trace| trace.xaxis == "x"));
+    }
+
+    #[test]
+    fn stages_span_all_panels() {
+        let plotly_config = PlotlyConfig::load(Path::new("../plot-config")).unwrap();
+        let actions = actions_plot_data(&plotly_config);
+        let stage_count = actions.layout.shapes.len() / 2;
+        let dashboard = to_dashboard_plotly_data(actions, vec![member("Team Lead", true, true)]);
+
+        let shapes = &dashboard.layout.layout.shapes;
+        assert_eq!(shapes.len(), stage_count);
+        assert!(shapes.iter().all(|shape| shape.yref == "paper" && shape.y0 == 0.0.into() && shape.y1 == 1.0.into()));
+
+        // the stage names are kept within the actions panel
+        let actions_domain = dashboard.panels[0].domain;
+        assert!(dashboard.layout.layout.annotations.iter()
+            .filter(|annotation| annotation.yref == "paper")
+            .all(|annotation| annotation.y as f64 >= actions_domain[0] && annotation.y as f64 <= actions_domain[1]));
+
+        let json = serde_json::to_value(&dashboard).unwrap();
+        assert_eq!(json["layout"]["yaxis"]["domain"][1], 1.0);
+        assert_eq!(json["layout"]["yaxis3"]["range"], serde_json::json!([0.0, 1.0]));
+        assert_eq!(json["layout"]["barmode"], "stack");
+        assert!(json["layout"]["yaxis2"].get("range").is_none());
+        assert_eq!(json["data"][0]["yaxis"], "y");
+    }
+
+    #[test]
+    fn actions_only() {
+        let plotly_config = PlotlyConfig::load(Path::new("../plot-config")).unwrap();
+        let dashboard = to_dashboard_plotly_data(actions_plot_data(&plotly_config), vec![member("Team Lead", false, false)]);
+        assert_eq!(dashboard.panels.len(), 1);
+        assert_eq!(dashboard.panels[0].domain, [0.0, 1.0]);
+        assert!(dashboard.layout.layout.xaxis.anchor.is_none());
+        assert!(!dashboard.layout.layout.showlegend);
+        assert!(dashboard.layout.panel_axes.is_empty());
+    }
+}
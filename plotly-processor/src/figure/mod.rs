@@ -0,0 +1,4 @@
+pub mod model;
+pub mod plotly;
+pub mod transformers;
+pub mod vega_lite;
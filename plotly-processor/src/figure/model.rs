@@ -0,0 +1,89 @@
+use crate::value::PlotValue;
+
+/// Renderer-agnostic chart: bands behind series of marks on a shared x and y axis. The figures are built from the
+/// processor data (see `figure::transformers`) and serialized by the renderers, `figure::plotly` and `figure::vega_lite`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Figure {
+    pub title: String,
+    pub x_axis: Axis,
+    pub y_axis: Axis,
+    /// Drawn below the series in the order given, e.g. the stages of the session.
+    pub bands: Vec<Band>,
+    pub series: Vec<FigureSeries>,
+    pub background_color: Option<String>,
+    pub font_color: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AxisScale {
+    /// Session time, the values are `PlotValue::DateTime`.
+    Time,
+    Linear,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Axis {
+    pub scale: AxisScale,
+    pub title: Option<String>,
+    /// Fitted to the data when not set.
+    pub range: Option<(PlotValue, PlotValue)>,
+    /// Tick label format in d3 notation (e.g. `%H:%M:%S`), both Plotly and Vega-Lite use it.
+    pub format: Option<String>,
+    pub visible: bool,
+}
+
+impl Axis {
+    pub fn new(scale: AxisScale, title: Option<String>) -> Self {
+        Axis { scale, title, range: None, format: None, visible: true }
+    }
+}
+
+/// Colored x interval, spanning the whole height of the figure unless `y_range` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Band {
+    pub x0: PlotValue,
+    pub x1: PlotValue,
+    pub y_range: Option<(f64, f64)>,
+    pub color: String,
+    /// Drawn at the top left of the band.
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeriesMark {
+    /// Markers of the given shape (`square`, `circle`, ...), with the point labels below them.
+    Point(String),
+    /// Connects the points of each segment, points without y aren't drawn.
+    Line,
+    /// Bars of the series with the same x are stacked.
+    StackedBar,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FigurePoint {
+    pub x: PlotValue,
+    pub y: Option<f64>,
+    /// Overrides the color of the series.
+    pub color: Option<String>,
+    pub label: Option<String>,
+    pub tooltip: Option<String>,
+    /// Line segment the point belongs to, lines aren't drawn between segments.
+    pub segment: usize,
+}
+
+impl FigurePoint {
+    pub fn new(x: PlotValue, y: Option<f64>) -> Self {
+        FigurePoint { x, y, color: None, label: None, tooltip: None, segment: 0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FigureSeries {
+    pub name: String,
+    pub mark: SeriesMark,
+    /// The palette of the renderer when not set.
+    pub color: Option<String>,
+    /// Listed in the legend, series whose points have their own colors usually aren't.
+    pub show_legend: bool,
+    pub points: Vec<FigurePoint>,
+}
@@ -0,0 +1,241 @@
+use crate::figure::model::{Axis, AxisScale, Band, Figure, FigureSeries, SeriesMark};
+use crate::value::PlotValue;
+use serde_json::{json, Value};
+
+fn layout_axis(axis: &Axis) -> Value {
+    let mut layout_axis = json!({
+        "type": match axis.scale { AxisScale::Time => "date", AxisScale::Linear => "linear" },
+        "visible": axis.visible,
+        "showgrid": axis.scale == AxisScale::Linear,
+    });
+    if let Some(title) = &axis.title {
+        layout_axis["title"] = json!({"text": title});
+    }
+    if let Some((start, end)) = &axis.range {
+        layout_axis["range"] = json!([start, end]);
+    }
+    if let Some(format) = &axis.format {
+        layout_axis["tickformat"] = json!(format);
+    }
+    layout_axis
+}
+
+/// A band without a y range spans the plot area, its label is at the top then.
+fn band_shape(band: &Band) -> Value {
+    let (yref, (y0, y1)) = match band.y_range {
+        Some(y_range) => ("y", y_range),
+        None => ("paper", (0.0, 1.0)),
+    };
+    json!({
+        "type": "rect",
+        "xref": "x",
+        "yref": yref,
+        "x0": band.x0,
+        "x1": band.x1,
+        "y0": y0,
+        "y1": y1,
+        "fillcolor": band.color,
+        "line": {"width": 0},
+        "layer": "below",
+    })
+}
+
+fn band_annotation(band: &Band, label: &str) -> Value {
+    let (yref, y) = match band.y_range {
+        Some((_, y1)) => ("y", y1),
+        None => ("paper", 1.0),
+    };
+    json!({
+        "text": label,
+        "xref": "x",
+        "yref": yref,
+        "x": band.x0,
+        "y": y,
+        "xanchor": "left",
+        "yanchor": "top",
+        "showarrow": false,
+    })
+}
+
+/// The points with a `null` between the line segments, Plotly doesn't connect the points around it.
+fn line_points(series: &FigureSeries) -> Vec<(Option<&PlotValue>, Option<f64>, Option<&str>)> {
+    let mut points = Vec::with_capacity(series.points.len());
+    for (index, point) in series.points.iter().enumerate() {
+        if index > 0 && series.points[index - 1].segment != point.segment {
+            points.push((None, None, None));
+        }
+        points.push((Some(&point.x), point.y, point.tooltip.as_deref()));
+    }
+    points
+}
+
+fn trace(series: &FigureSeries) -> Value {
+    let points: Vec<(Option<&PlotValue>, Option<f64>, Option<&str>)> = match series.mark {
+        SeriesMark::Line => line_points(series),
+        _ => series.points.iter().map(|point| (Some(&point.x), point.y, point.tooltip.as_deref())).collect(),
+    };
+    let mut trace = json!({
+        "name": series.name,
+        "x": points.iter().map(|(x, _, _)| x).collect::<Vec<_>>(),
+        "y": points.iter().map(|(_, y, _)| y).collect::<Vec<_>>(),
+        "showlegend": series.show_legend,
+    });
+    if series.points.iter().any(|point| point.tooltip.is_some()) {
+        trace["hovertext"] = json!(points.iter().map(|(_, _, tooltip)| tooltip).collect::<Vec<_>>());
+        trace["hoverinfo"] = json!("text");
+    }
+    let point_colors = series.points.iter().any(|point| point.color.is_some())
+        .then(|| series.points.iter().map(|point| point.color.as_ref().or(series.color.as_ref())).collect::<Vec<_>>());
+
+    match &series.mark {
+        SeriesMark::Line => {
+            trace["type"] = json!("scatter");
+            trace["mode"] = json!("lines");
+            if let Some(color) = &series.color {
+                trace["line"] = json!({"color": color});
+            }
+        }
+        SeriesMark::StackedBar => {
+            trace["type"] = json!("bar");
+            if let Some(color) = point_colors.map(|colors| json!(colors)).or_else(|| series.color.as_ref().map(|color| json!(color))) {
+                trace["marker"] = json!({"color": color});
+            }
+        }
+        SeriesMark::Point(shape) => {
+            trace["type"] = json!("scatter");
+            trace["marker"] = json!({"symbol": shape});
+            if let Some(color) = point_colors.map(|colors| json!(colors)).or_else(|| series.color.as_ref().map(|color| json!(color))) {
+                trace["marker"]["color"] = color;
+            }
+            if series.points.iter().any(|point| point.label.is_some()) {
+                trace["mode"] = json!("markers+text");
+                trace["text"] = json!(series.points.iter().map(|point| point.label.as_deref().unwrap_or_default()).collect::<Vec<_>>());
+                trace["textposition"] = json!("bottom center");
+            } else {
+                trace["mode"] = json!("markers");
+            }
+        }
+    }
+    trace
+}
+
+/// Plotly figure (`data` and `layout`) of the figure: the bands as shapes below the traces with their labels as
+/// annotations, a trace per series and the stacked bars in the `stack` bar mode.
+pub fn to_plotly(figure: &Figure) -> Value {
+    let mut layout = json!({
+        "title": {"text": figure.title},
+        "xaxis": layout_axis(&figure.x_axis),
+        "yaxis": layout_axis(&figure.y_axis),
+        "shapes": figure.bands.iter().map(band_shape).collect::<Vec<_>>(),
+        "annotations": figure.bands.iter()
+            .filter_map(|band| band.label.as_deref().map(|label| band_annotation(band, label)))
+            .collect::<Vec<_>>(),
+        "showlegend": figure.series.iter().any(|series| series.show_legend),
+    });
+    if figure.series.iter().any(|series| series.mark == SeriesMark::StackedBar) {
+        layout["barmode"] = json!("stack");
+    }
+    if let Some(color) = &figure.background_color {
+        layout["paper_bgcolor"] = json!(color);
+    }
+    if let Some(color) = &figure.font_color {
+        layout["font"] = json!({"color": color});
+    }
+    json!({"data": figure.series.iter().map(trace).collect::<Vec<_>>(), "layout": layout})
+}
+
+#[cfg(test)]
+mod tests_plotly {
+    use super::*;
+    use crate::figure::model::FigurePoint;
+    use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+
+    fn date_time(seconds: u32) -> PlotValue {
+        PlotValue::date_time(&seconds_to_csv_row_time(seconds).date_string)
+    }
+
+    fn figure(series: Vec<FigureSeries>) -> Figure {
+        let mut x_axis = Axis::new(AxisScale::Time, Some("Time".to_owned()));
+        x_axis.range = Some((date_time(0), date_time(90)));
+        x_axis.format = Some("%H:%M:%S".to_owned());
+        let mut y_axis = Axis::new(AxisScale::Linear, None);
+        y_axis.visible = false;
+        Figure {
+            title: "Session".to_owned(),
+            x_axis,
+            y_axis,
+            bands: vec![
+                Band { x0: date_time(0), x1: date_time(60), y_range: Some((1.0, 5.0)), color: "#1f77b433".to_owned(), label: Some("Asystole".to_owned()) },
+                Band { x0: date_time(60), x1: date_time(90), y_range: None, color: "#d6272833".to_owned(), label: None },
+            ],
+            series,
+            background_color: Some("#ffffff".to_owned()),
+            font_color: None,
+        }
+    }
+
+    fn point(seconds: u32, y: f64, segment: usize) -> FigurePoint {
+        FigurePoint { segment, ..FigurePoint::new(date_time(seconds), Some(y)) }
+    }
+
+    #[test]
+    fn bands_are_shapes_with_annotations() {
+        let plotly = to_plotly(&figure(Vec::new()));
+
+        let layout = &plotly["layout"];
+        assert_eq!(layout["shapes"][0]["yref"], "y");
+        assert_eq!((&layout["shapes"][0]["y0"], &layout["shapes"][0]["y1"]), (&json!(1.0), &json!(5.0)));
+        assert_eq!((&layout["shapes"][1]["yref"], &layout["shapes"][1]["y1"]), (&json!("paper"), &json!(1.0)));
+        assert_eq!(layout["annotations"].as_array().unwrap().len(), 1);
+        assert_eq!((&layout["annotations"][0]["text"], &layout["annotations"][0]["y"]), (&json!("Asystole"), &json!(5.0)));
+        assert_eq!(layout["xaxis"]["type"], "date");
+        assert_eq!(layout["xaxis"]["range"][0], json!(date_time(0)));
+        assert_eq!(layout["yaxis"]["visible"], false);
+        assert_eq!(layout["paper_bgcolor"], "#ffffff");
+    }
+
+    #[test]
+    fn line_segments_are_separated_by_a_gap() {
+        let cpr = FigureSeries {
+            name: "CPR".to_owned(),
+            mark: SeriesMark::Line,
+            color: Some("rgb(0, 150, 0)".to_owned()),
+            show_legend: false,
+            points: vec![point(10, 1.0, 0), point(20, 1.0, 0), point(30, 1.0, 1), point(40, 1.0, 1)],
+        };
+        let plotly = to_plotly(&figure(vec![cpr]));
+
+        let trace = &plotly["data"][0];
+        assert_eq!((&trace["type"], &trace["mode"]), (&json!("scatter"), &json!("lines")));
+        assert_eq!(trace["y"], json!([1.0, 1.0, null, 1.0, 1.0]));
+        assert_eq!(trace["x"][2], Value::Null);
+        assert_eq!(trace["line"]["color"], "rgb(0, 150, 0)");
+        assert_eq!(plotly["layout"]["showlegend"], false);
+    }
+
+    #[test]
+    fn points_keep_their_colors_and_labels() {
+        let mut defib = point(30, 2.0, 0);
+        defib.color = Some("green".to_owned());
+        defib.label = Some("200J".to_owned());
+        defib.tooltip = Some("00:00:30, Defib".to_owned());
+        let actions = FigureSeries { name: "Performed Actions".to_owned(), mark: SeriesMark::Point("square".to_owned()), color: None, show_legend: false, points: vec![defib, point(40, 3.0, 0)] };
+        let plotly = to_plotly(&figure(vec![actions]));
+
+        let trace = &plotly["data"][0];
+        assert_eq!(trace["mode"], "markers+text");
+        assert_eq!(trace["marker"], json!({"symbol": "square", "color": ["green", null]}));
+        assert_eq!(trace["text"], json!(["200J", ""]));
+        assert_eq!(trace["hovertext"], json!(["00:00:30, Defib", null]));
+    }
+
+    #[test]
+    fn stacked_bars_use_the_stack_bar_mode() {
+        let patient = FigureSeries { name: "Patient".to_owned(), mark: SeriesMark::StackedBar, color: Some("#d62728".to_owned()), show_legend: true, points: vec![point(5, 0.25, 0)] };
+        let plotly = to_plotly(&figure(vec![patient]));
+
+        assert_eq!(plotly["layout"]["barmode"], "stack");
+        assert_eq!(plotly["layout"]["showlegend"], true);
+        assert_eq!((&plotly["data"][0]["type"], &plotly["data"][0]["marker"]["color"]), (&json!("bar"), &json!("#d62728")));
+    }
+}
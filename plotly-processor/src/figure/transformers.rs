@@ -0,0 +1,341 @@
+use crate::actions::icon_sizing::IconSizing;
+use crate::actions::missed_action_coordinates_calculator::{layout_missed_actions, seconds_to_date_time_string};
+use crate::config::layout_settings::Theme;
+use crate::config::plotly_mappings::{PlotlyConfig, VisualAttentionPlotSettings};
+use crate::figure::model::{Axis, AxisScale, Band, Figure, FigurePoint, FigureSeries, SeriesMark};
+use crate::value::PlotValue;
+use crate::visual_attention::transformers::order_categories;
+use mteam_dashboard_action_processor::plot_structures::{ActionPlotPoint, PeriodType, PlotLocation};
+use mteam_dashboard_cognitive_load_processor::file_processor::CognitiveLoadSample;
+use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+use mteam_dashboard_visual_attention_processor::window::AttentionWindow;
+use std::collections::{BTreeSet, HashMap};
+
+const TIME_FORMAT: &str = "%H:%M:%S";
+
+fn date_time(seconds: u32) -> PlotValue {
+    PlotValue::date_time(&seconds_to_csv_row_time(seconds).date_string)
+}
+
+fn time_axis(title: Option<String>) -> Axis {
+    let mut axis = Axis::new(AxisScale::Time, title);
+    axis.format = Some(TIME_FORMAT.to_owned());
+    axis
+}
+
+fn point_series(name: &str) -> FigureSeries {
+    FigureSeries { name: name.to_owned(), mark: SeriesMark::Point("square".to_owned()), color: None, show_legend: false, points: Vec::new() }
+}
+
+fn action_point(location: &PlotLocation, y: f32, color: &str, label: String, tooltip: String) -> FigurePoint {
+    FigurePoint {
+        color: Some(color.to_owned()),
+        label: Some(label).filter(|label| !label.is_empty()),
+        tooltip: Some(tooltip),
+        ..FigurePoint::new(date_time(location.timestamp.total_seconds), Some(y as f64))
+    }
+}
+
+fn with_advice(text: String, separator: &str, advice: &str) -> String {
+    if advice.is_empty() { text } else { format!("{}{}{}", text, separator, advice) }
+}
+
+/// The actions plot laid out like the Plotly plot data: the stages as bands in the performed and in the missed
+/// actions section, the CPR periods, a row per action group and the missed actions placed in their stages.
+/// The action icons are left out, their paths are relative to the frontend.
+pub fn to_actions_figure(plotly_config: &PlotlyConfig, theme: &Theme, data_points: impl Iterator<Item = Result<ActionPlotPoint, String>>) -> Figure {
+    let settings = &plotly_config.action_plot_settings;
+    let mut stages: Vec<(PlotLocation, PlotLocation)> = Vec::new();
+    let mut cpr = FigureSeries { name: "CPR".to_owned(), mark: SeriesMark::Line, color: Some(theme.cpr_line_color.clone()), show_legend: false, points: Vec::new() };
+    let mut performed = point_series("Performed Actions");
+    let mut missed = point_series("Missed Actions");
+    let mut missed_stages: Vec<(u32, String)> = Vec::new();
+    // the action groups in the order they were first performed, a row each starting two increments up
+    let mut group_rows: Vec<String> = Vec::new();
+    let mut row_y = |action_name: &str| {
+        let group_name = plotly_config.get_action_group_name(action_name);
+        let row = group_rows.iter().position(|group| *group == group_name).unwrap_or_else(|| {
+            group_rows.push(group_name);
+            group_rows.len() - 1
+        });
+        settings.y_increment * (row + 2) as f32
+    };
+
+    for data_point in data_points.flatten() {
+        match data_point {
+            ActionPlotPoint::Action(action) => {
+                let tooltip = format!("{}, {}", action.location.timestamp.timestamp, action.name);
+                performed.points.push(action_point(&action.location, row_y(&action.name), &theme.performed_action_color, action.shock_value, tooltip));
+            }
+            ActionPlotPoint::Error(action) => {
+                let tooltip = with_advice(format!("{}, {}", action.location.timestamp.timestamp, action.name), ", ", &action.error_info.advice);
+                performed.points.push(action_point(&action.location, row_y(&action.name), &theme.erroneous_action_color, action.shock_value, tooltip));
+            }
+            ActionPlotPoint::MissedAction(action) => {
+                missed.points.push(FigurePoint {
+                    color: Some(theme.missed_action_color.clone()),
+                    tooltip: Some(with_advice(action.name.clone(), " - ", &action.error_info.advice)),
+                    ..FigurePoint::new(PlotValue::default(), None)
+                });
+                missed_stages.push(action.location.stage);
+            }
+            ActionPlotPoint::Period(PeriodType::CPR, start, end) => {
+                let segment = cpr.points.len() / 2;
+                for location in [start, end] {
+                    cpr.points.push(FigurePoint {
+                        tooltip: Some(location.timestamp.timestamp.clone()),
+                        segment,
+                        ..FigurePoint::new(date_time(location.timestamp.total_seconds), Some(settings.y_increment as f64))
+                    });
+                }
+            }
+            ActionPlotPoint::Period(PeriodType::Stage, start, end) => stages.push((start, end)),
+        }
+    }
+
+    let y_max = settings.y_increment * (group_rows.len() + 2) as f32;
+    let x_max_secs = stages.iter().map(|(_, end)| end.timestamp.total_seconds).max().unwrap_or(0) as f32;
+    let x_end_secs = x_max_secs + settings.x_axis_padding_secs as f32;
+    let icon_settings = &plotly_config.layout_settings.icon;
+    let (performed_x, performed_y): (Vec<PlotValue>, Vec<PlotValue>) = performed.points.iter()
+        .map(|point| (point.x.clone(), PlotValue::from(point.y.unwrap_or_default())))
+        .unzip();
+    let icon_sizing = IconSizing::new(x_end_secs as f64 * 1000.0, (y_max + 2.0 * settings.y_increment - settings.y_min) as f64, settings.y_increment as f64, &performed_x, &performed_y);
+    let icon_width_secs = (icon_sizing.size(icon_settings, 1.0).size_x / 1000.0) as f32;
+
+    // keyed by the stage number too, a stage that repeats has a span for each occurrence
+    let stage_spans: HashMap<(u32, &str), (f32, f32)> = stages.iter()
+        .map(|(start, end)| ((start.stage.0, start.stage.1.as_str()), (start.timestamp.total_seconds as f32, end.timestamp.total_seconds as f32)))
+        .collect();
+    let overflow_span = (x_max_secs, x_end_secs.max(x_max_secs + 2.0 * icon_width_secs));
+    let missed_stage_keys: Vec<(u32, &str)> = missed_stages.iter().map(|(number, name)| (*number, name.as_str())).collect();
+    let missed_actions_layout = layout_missed_actions(&missed_stage_keys, &stage_spans, overflow_span, icon_width_secs, &settings.missed_actions);
+    for (placement, point) in missed_actions_layout.placements.iter().zip(missed.points.iter_mut()) {
+        point.x = PlotValue::date_time(&placement.x);
+        point.y = Some(placement.y as f64);
+    }
+
+    let stage_band = |(start, end): &(PlotLocation, PlotLocation), y_range: (f32, f32), label: Option<String>| Band {
+        x0: date_time(start.timestamp.total_seconds),
+        x1: date_time(end.timestamp.total_seconds),
+        y_range: Some((y_range.0 as f64, y_range.1 as f64)),
+        color: plotly_config.stages.stage_color(&start.stage.1, theme.stage_colors.as_deref()) + "33",
+        label,
+    };
+    let performed_range = (settings.y_min, y_max + settings.y_increment);
+    let missed_range = (settings.missed_actions.y_min, missed_actions_layout.y_max + 2.5 * settings.missed_actions.y_increment);
+    let bands = stages.iter().map(|stage| stage_band(stage, performed_range, Some(plotly_config.stages.display_name(&stage.0.stage.1))))
+        .chain(stages.iter().map(|stage| stage_band(stage, missed_range, None)))
+        .collect();
+
+    let x_end_secs = if missed_actions_layout.uses_overflow { overflow_span.1 } else { x_end_secs };
+    let mut x_axis = time_axis(Some("Time".to_owned()));
+    x_axis.range = Some((PlotValue::date_time(&seconds_to_date_time_string(0.0)), PlotValue::date_time(&seconds_to_date_time_string(x_end_secs))));
+    let mut y_axis = Axis::new(AxisScale::Linear, None);
+    y_axis.range = Some((missed_actions_layout.y_max.into(), (y_max + 2.0 * settings.y_increment).into()));
+    y_axis.visible = false;
+
+    let series = (!cpr.points.is_empty()).then_some(cpr).into_iter().chain([performed, missed]).collect();
+    Figure {
+        title: plotly_config.layout_settings.title.clone(),
+        x_axis,
+        y_axis,
+        bands,
+        series,
+        background_color: theme.paper_background_color.clone(),
+        font_color: theme.font_color.clone(),
+    }
+}
+
+/// A line per team member on the session time axis.
+pub fn to_cognitive_load_figure(title: &str, members: &[(String, Vec<CognitiveLoadSample>)]) -> Figure {
+    let series = members.iter()
+        .map(|(name, samples)| {
+            let points = samples.iter()
+                .map(|sample| {
+                    let timestamp = seconds_to_csv_row_time(sample.seconds as u32);
+                    let mut tooltip = format!("{}, {}", timestamp.timestamp, name);
+                    if let Some(load) = sample.load {
+                        tooltip += &format!(": {:.2}", load);
+                    }
+                    if let Some(confidence) = sample.confidence {
+                        tooltip += &format!(" (confidence {:.2})", confidence);
+                    }
+                    FigurePoint { tooltip: Some(tooltip), ..FigurePoint::new(PlotValue::date_time(&timestamp.date_string), sample.load) }
+                })
+                .collect();
+            FigureSeries { name: name.clone(), mark: SeriesMark::Line, color: None, show_legend: true, points }
+        })
+        .collect();
+
+    Figure {
+        title: title.to_owned(),
+        x_axis: time_axis(None),
+        y_axis: Axis::new(AxisScale::Linear, Some("Cognitive Load".to_owned())),
+        bands: Vec::new(),
+        series,
+        background_color: None,
+        font_color: None,
+    }
+}
+
+/// The attention ratio of each category per window as stacked bars at the window ends, the categories in the
+/// configured order and colors. Windows without data have no bars, the tooltips flag the unreliable ones.
+pub fn to_visual_attention_figure(title: &str, windows: &[AttentionWindow], settings: &VisualAttentionPlotSettings) -> Figure {
+    let found_categories: BTreeSet<&String> = windows.iter().flat_map(|window| window.category_counts.keys()).collect();
+    let (ordered_category_colors, _) = order_categories(found_categories, settings);
+    let series = ordered_category_colors.into_iter()
+        .map(|(category, color)| {
+            let points = windows.iter()
+                .map(|window| {
+                    let timestamp = seconds_to_csv_row_time(window.end_secs);
+                    let ratio = window.ratio(&category);
+                    let mut tooltip = format!("{}, {}: {:.0}% of {} samples", timestamp.timestamp, category, ratio.unwrap_or_default() * 100.0, window.sample_count);
+                    if window.has_data() && !window.reliable {
+                        tooltip += " (unreliable)";
+                    }
+                    FigurePoint { tooltip: Some(tooltip), ..FigurePoint::new(PlotValue::date_time(&timestamp.date_string), ratio) }
+                })
+                .collect();
+            FigureSeries { name: category, mark: SeriesMark::StackedBar, color: Some(color), show_legend: true, points }
+        })
+        .collect();
+
+    let mut y_axis = Axis::new(AxisScale::Linear, Some("Attention Ratio".to_owned()));
+    y_axis.range = Some((0.0.into(), 1.0.into()));
+    Figure {
+        title: title.to_owned(),
+        x_axis: time_axis(None),
+        y_axis,
+        bands: Vec::new(),
+        series,
+        background_color: None,
+        font_color: None,
+    }
+}
+
+#[cfg(test)]
+mod tests_figure {
+    use super::*;
+    use crate::actions::plot_data::ActionsPlotDataItem;
+    use crate::actions::transformers::to_plotly_data;
+    use mteam_dashboard_action_processor::plot_structures::{Action, ErrorInfo, MissedAction};
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    fn location(seconds: u32, stage: (u32, &str)) -> PlotLocation {
+        PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (stage.0, stage.1.to_owned()) }
+    }
+
+    fn action(seconds: u32, name: &str, shock_value: &str) -> Result<ActionPlotPoint, String> {
+        Ok(ActionPlotPoint::Action(Action { location: location(seconds, (1, "Asystole")), name: name.to_owned(), action_category: String::new(), shock_value: shock_value.to_owned() }))
+    }
+
+    fn missed_action(stage: (u32, &str)) -> Result<ActionPlotPoint, String> {
+        let error_info = ErrorInfo { action_rule: String::new(), violation: String::new(), advice: "Give it".to_owned() };
+        Ok(ActionPlotPoint::MissedAction(MissedAction { location: location(0, stage), name: "Epinephrine".to_owned(), error_info }))
+    }
+
+    fn points() -> Vec<Result<ActionPlotPoint, String>> {
+        vec![
+            Ok(ActionPlotPoint::Period(PeriodType::Stage, location(0, (1, "Asystole")), location(100, (1, "Asystole")))),
+            Ok(ActionPlotPoint::Period(PeriodType::Stage, location(100, (2, "V-Tach")), location(200, (2, "V-Tach")))),
+            Ok(ActionPlotPoint::Period(PeriodType::CPR, location(10, (1, "Asystole")), location(40, (1, "Asystole")))),
+            action(50, "Defib", "200J"),
+            action(60, "Epinephrine", ""),
+            action(70, "Defib", "300J"),
+            missed_action((1, "Asystole")),
+            missed_action((2, "V-Tach")),
+            missed_action((3, "Asystole")),
+        ]
+    }
+
+    #[test]
+    fn actions_figure_is_laid_out_like_the_plotly_plot_data() {
+        let plotly_config = PlotlyConfig::load(Path::new("../plot-config")).unwrap();
+        let theme = plotly_config.layout_settings.default_theme();
+        let plot_data = to_plotly_data(&plotly_config, theme, points().into_iter());
+        let figure = to_actions_figure(&plotly_config, theme, points().into_iter());
+
+        // every stage is drawn in both sections and labelled in the performed actions section
+        let layout = &plot_data.layout;
+        assert_eq!(figure.bands.len(), layout.shapes.len());
+        for (band, shape) in figure.bands.iter().zip(&layout.shapes) {
+            assert_eq!((&band.x0, &band.x1, &band.color), (&shape.x0, &shape.x1, &shape.fillcolor));
+            assert_eq!(band.y_range, Some((shape.y0.as_number().unwrap(), shape.y1.as_number().unwrap())));
+        }
+        let labels: Vec<Option<&str>> = figure.bands.iter().map(|band| band.label.as_deref()).collect();
+        assert_eq!(labels, vec![Some("Asystole"), Some("V-Tach"), None, None]);
+
+        let names: Vec<&str> = figure.series.iter().map(|series| series.name.as_str()).collect();
+        assert_eq!(names, vec!["CPR", "Performed Actions", "Missed Actions"]);
+        assert_eq!(figure.series[0].points.iter().map(|point| point.x.clone()).collect::<Vec<_>>(), vec![date_time(10), date_time(40)]);
+        assert_eq!(figure.series[0].color, Some(theme.cpr_line_color.clone()));
+
+        let plotly_series: Vec<_> = plot_data.data.iter()
+            .filter_map(|item| match item {
+                ActionsPlotDataItem::Points(series) => Some(series),
+                _ => None,
+            })
+            .collect();
+        for (series, plotly_series) in figure.series[1..].iter().zip(plotly_series) {
+            let x: Vec<PlotValue> = series.points.iter().map(|point| point.x.clone()).collect();
+            let y: Vec<Option<f64>> = series.points.iter().map(|point| point.y).collect();
+            assert_eq!(x, plotly_series.x);
+            assert_eq!(y, plotly_series.y.iter().map(PlotValue::as_number).collect::<Vec<_>>());
+            assert_eq!(series.points.iter().map(|point| point.color.clone().unwrap()).collect::<Vec<_>>(), plotly_series.marker.color);
+        }
+        let defib = &figure.series[1].points[0];
+        assert_eq!((defib.label.as_deref(), defib.tooltip.as_deref()), (Some("200J"), Some("00:00:50, Defib")));
+        assert_eq!(figure.series[1].points[1].label, None);
+        assert_eq!(figure.series[2].points[0].tooltip.as_deref(), Some("Epinephrine - Give it"));
+
+        assert_eq!(figure.x_axis.range, Some((layout.xaxis.range[0].clone(), layout.xaxis.range[1].clone())));
+        assert_eq!(figure.y_axis.range, Some((layout.yaxis.range[0].clone(), layout.yaxis.range[1].clone())));
+        assert!(!figure.y_axis.visible);
+    }
+
+    #[test]
+    fn cognitive_load_figure_has_a_line_per_member() {
+        let samples = vec![
+            CognitiveLoadSample { seconds: 5.0, load: Some(0.5), confidence: Some(0.9) },
+            CognitiveLoadSample { seconds: 10.0, load: None, confidence: None },
+        ];
+        let figure = to_cognitive_load_figure("Cognitive Load", &[("Nurse".to_owned(), samples)]);
+
+        let nurse = &figure.series[0];
+        assert_eq!((nurse.name.as_str(), &nurse.mark, nurse.show_legend), ("Nurse", &SeriesMark::Line, true));
+        assert_eq!(nurse.points[0].x, date_time(5));
+        assert_eq!(nurse.points[0].tooltip.as_deref(), Some("00:00:05, Nurse: 0.50 (confidence 0.90)"));
+        assert_eq!((nurse.points[1].y, nurse.points[1].tooltip.as_deref()), (None, Some("00:00:10, Nurse")));
+    }
+
+    fn window(end_secs: u32, counts: &[(&str, usize)], reliable: bool) -> AttentionWindow {
+        let category_counts: BTreeMap<String, usize> = counts.iter().map(|(category, count)| (category.to_string(), *count)).collect();
+        AttentionWindow {
+            start_secs: end_secs - 5,
+            end_secs,
+            category_weights: category_counts.iter().map(|(category, count)| (category.clone(), *count as f64)).collect(),
+            sample_count: category_counts.values().sum(),
+            category_counts,
+            reliable,
+        }
+    }
+
+    #[test]
+    fn visual_attention_figure_stacks_the_categories() {
+        let plotly_config = PlotlyConfig::load(Path::new("../plot-config")).unwrap();
+        let settings = &plotly_config.visual_attention_plot_settings;
+        let windows = vec![window(5, &[("Patient", 1), ("Monitor", 3)], false), window(10, &[], true)];
+        let figure = to_visual_attention_figure("Visual Attention", &windows, settings);
+
+        let (ordered, _) = order_categories(BTreeSet::from([&"Patient".to_owned(), &"Monitor".to_owned()]), settings);
+        assert_eq!(figure.series.iter().map(|series| (series.name.clone(), series.color.clone().unwrap())).collect::<Vec<_>>(), ordered);
+        let patient = figure.series.iter().find(|series| series.name == "Patient").unwrap();
+        assert_eq!(patient.mark, SeriesMark::StackedBar);
+        assert_eq!(patient.points[0].x, date_time(5));
+        assert_eq!(patient.points[0].tooltip.as_deref(), Some("00:00:05, Patient: 25% of 4 samples (unreliable)"));
+        assert_eq!(patient.points[1].y, None);
+        assert_eq!(figure.y_axis.range, Some((0.0.into(), 1.0.into())));
+    }
+}
@@ -0,0 +1,330 @@
+use crate::figure::model::{Axis, AxisScale, Band, Figure, FigureSeries, SeriesMark};
+use crate::value::PlotValue;
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use serde_json::{json, Map, Value};
+
+const SCHEMA: &str = "https://vega.github.io/schema/vega-lite/v5.json";
+const WIDTH: u32 = 900;
+const HEIGHT: u32 = 400;
+/// ISO 8601 without a time zone, which Vega-Lite parses as local time like the `DateTime` scale domains.
+const DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+fn data_value(value: &PlotValue) -> Value {
+    match value {
+        PlotValue::Number(number) => json!(number),
+        PlotValue::DateTime(date_time) => json!(date_time.format(DATE_TIME_FORMAT).to_string()),
+        PlotValue::Category(category) => json!(category),
+    }
+}
+
+/// Vega-Lite takes temporal scale domains as timestamps or `DateTime` objects, not as strings. Months are 1-based.
+fn domain_value(value: &PlotValue) -> Value {
+    match value {
+        PlotValue::DateTime(date_time) => date_time_object(date_time),
+        value => data_value(value),
+    }
+}
+
+fn date_time_object(date_time: &NaiveDateTime) -> Value {
+    json!({
+        "year": date_time.year(),
+        "month": date_time.month(),
+        "date": date_time.day(),
+        "hours": date_time.hour(),
+        "minutes": date_time.minute(),
+        "seconds": date_time.second(),
+    })
+}
+
+fn position_channel(axis: &Axis, field: &str) -> Value {
+    let mut channel = json!({
+        "field": field,
+        "type": match axis.scale { AxisScale::Time => "temporal", AxisScale::Linear => "quantitative" },
+        "title": axis.title,
+    });
+    if let Some((start, end)) = &axis.range {
+        channel["scale"] = json!({"domain": [domain_value(start), domain_value(end)]});
+    }
+    channel["axis"] = if axis.visible {
+        let mut axis_properties = json!({"grid": axis.scale == AxisScale::Linear});
+        if let Some(format) = &axis.format {
+            axis_properties["format"] = json!(format);
+        }
+        axis_properties
+    } else {
+        Value::Null
+    };
+    channel
+}
+
+/// Colors given in the data rather than mapped by a scale.
+fn literal_color() -> Value {
+    json!({"field": "color", "type": "nominal", "scale": null, "legend": null})
+}
+
+fn band_layers(figure: &Figure, with_y_range: bool) -> Vec<Value> {
+    let bands: Vec<&Band> = figure.bands.iter().filter(|band| band.y_range.is_some() == with_y_range).collect();
+    if bands.is_empty() {
+        return Vec::new();
+    }
+    let values: Vec<Value> = bands.iter()
+        .map(|band| {
+            let mut row = json!({"x0": data_value(&band.x0), "x1": data_value(&band.x1), "color": band.color, "label": band.label});
+            if let Some((y0, y1)) = band.y_range {
+                row["y0"] = json!(y0);
+                row["y1"] = json!(y1);
+            }
+            row
+        })
+        .collect();
+
+    let mut rect_encoding = json!({"x": position_channel(&figure.x_axis, "x0"), "x2": {"field": "x1"}, "color": literal_color()});
+    let mut label_encoding = json!({"x": position_channel(&figure.x_axis, "x0"), "text": {"field": "label"}});
+    if with_y_range {
+        rect_encoding["y"] = position_channel(&figure.y_axis, "y0");
+        rect_encoding["y2"] = json!({"field": "y1"});
+        label_encoding["y"] = position_channel(&figure.y_axis, "y1");
+    } else {
+        // bands without a y range span the whole height, their labels are at the top
+        label_encoding["y"] = json!({"value": 0});
+    }
+    let mut label_mark = json!({"type": "text", "align": "left", "baseline": "top", "dx": 2, "dy": 2});
+    if let Some(color) = &figure.font_color {
+        label_mark["color"] = json!(color);
+    }
+
+    let mut layers = vec![json!({"data": {"values": values}, "mark": {"type": "rect"}, "encoding": rect_encoding})];
+    if bands.iter().any(|band| band.label.is_some()) {
+        layers.push(json!({
+            "data": {"values": values},
+            "transform": [{"filter": "datum.label != null"}],
+            "mark": label_mark,
+            "encoding": label_encoding,
+        }));
+    }
+    layers
+}
+
+fn series_values(series: &[&FigureSeries]) -> Vec<Value> {
+    series.iter()
+        .flat_map(|series| series.points.iter().map(|point| json!({
+            "series": series.name,
+            "x": data_value(&point.x),
+            "y": point.y,
+            "color": point.color.as_ref().or(series.color.as_ref()),
+            "label": point.label,
+            "tooltip": point.tooltip,
+            "segment": point.segment,
+        })))
+        .collect()
+}
+
+/// Series listed in the legend are colored by name, with their own colors if they all have one. Otherwise the colors
+/// of the points are used as given.
+fn series_color(series: &[&FigureSeries]) -> Option<Value> {
+    if series.iter().all(|series| series.show_legend) {
+        let mut scale = json!({"domain": series.iter().map(|series| &series.name).collect::<Vec<_>>()});
+        if series.iter().all(|series| series.color.is_some()) {
+            scale["range"] = json!(series.iter().map(|series| &series.color).collect::<Vec<_>>());
+        }
+        Some(json!({"field": "series", "type": "nominal", "title": null, "scale": scale}))
+    } else if series.iter().all(|series| series.color.is_some() || series.points.iter().all(|point| point.color.is_some())) {
+        Some(literal_color())
+    } else {
+        None
+    }
+}
+
+fn series_layer(figure: &Figure, series: &[&FigureSeries], mark: Value) -> Value {
+    let mut encoding = json!({
+        "x": position_channel(&figure.x_axis, "x"),
+        "y": position_channel(&figure.y_axis, "y"),
+    });
+    if let Some(color) = series_color(series) {
+        encoding["color"] = color;
+    }
+    if series.iter().any(|series| series.points.iter().any(|point| point.tooltip.is_some())) {
+        encoding["tooltip"] = json!({"field": "tooltip", "type": "nominal", "title": null});
+    }
+    match mark["type"].as_str() {
+        Some("line") => encoding["detail"] = json!({"field": "segment", "type": "nominal"}),
+        Some("bar") => {
+            encoding["y"]["stack"] = json!("zero");
+            encoding["order"] = json!({"field": "seriesIndex"});
+        }
+        _ => {}
+    }
+    let mut values = series_values(series);
+    // stacked in the order of the series, the first one at the bottom
+    if mark["type"] == "bar" {
+        let names: Vec<&String> = series.iter().map(|series| &series.name).collect();
+        for value in values.iter_mut() {
+            value["seriesIndex"] = json!(names.iter().position(|name| value["series"] == json!(name)));
+        }
+    }
+    json!({"data": {"values": values}, "mark": mark, "encoding": encoding})
+}
+
+fn label_layer(figure: &Figure, series: &FigureSeries) -> Option<Value> {
+    if series.points.iter().all(|point| point.label.is_none()) {
+        return None;
+    }
+    let mut mark = json!({"type": "text", "baseline": "top", "dy": 6});
+    if let Some(color) = &figure.font_color {
+        mark["color"] = json!(color);
+    }
+    Some(json!({
+        "data": {"values": series_values(&[series])},
+        "transform": [{"filter": "datum.label != null"}],
+        "mark": mark,
+        "encoding": {
+            "x": position_channel(&figure.x_axis, "x"),
+            "y": position_channel(&figure.y_axis, "y"),
+            "text": {"field": "label"},
+        },
+    }))
+}
+
+/// Vega-Lite spec of the figure, with the data inlined. The bands are drawn first, then the series: all the stacked
+/// bars in one layer, all the lines in one layer, and a layer per point series with its labels.
+pub fn to_vega_lite(figure: &Figure) -> Value {
+    let mut layers = band_layers(figure, true);
+    layers.extend(band_layers(figure, false));
+
+    let lines: Vec<&FigureSeries> = figure.series.iter().filter(|series| series.mark == SeriesMark::Line).collect();
+    let bars: Vec<&FigureSeries> = figure.series.iter().filter(|series| series.mark == SeriesMark::StackedBar).collect();
+    if !bars.is_empty() {
+        layers.push(series_layer(figure, &bars, json!({"type": "bar"})));
+    }
+    if !lines.is_empty() {
+        layers.push(series_layer(figure, &lines, json!({"type": "line"})));
+    }
+    for series in &figure.series {
+        if let SeriesMark::Point(shape) = &series.mark {
+            layers.push(series_layer(figure, &[series], json!({"type": "point", "shape": shape, "filled": true, "opacity": 1})));
+            layers.extend(label_layer(figure, series));
+        }
+    }
+
+    let mut spec = Map::new();
+    spec.insert("$schema".to_owned(), json!(SCHEMA));
+    spec.insert("title".to_owned(), json!(figure.title));
+    spec.insert("width".to_owned(), json!(WIDTH));
+    spec.insert("height".to_owned(), json!(HEIGHT));
+    if let Some(color) = &figure.background_color {
+        spec.insert("background".to_owned(), json!(color));
+    }
+    if let Some(color) = &figure.font_color {
+        spec.insert("config".to_owned(), json!({
+            "title": {"color": color},
+            "axis": {"labelColor": color, "titleColor": color},
+            "legend": {"labelColor": color, "titleColor": color},
+        }));
+    }
+    spec.insert("layer".to_owned(), json!(layers));
+    // the bands and the points with their own colors don't share the color scale of the legend
+    spec.insert("resolve".to_owned(), json!({"scale": {"color": "independent"}}));
+    Value::Object(spec)
+}
+
+#[cfg(test)]
+mod tests_vega_lite {
+    use super::*;
+    use crate::figure::model::FigurePoint;
+    use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+
+    fn date_time(seconds: u32) -> PlotValue {
+        PlotValue::date_time(&seconds_to_csv_row_time(seconds).date_string)
+    }
+
+    fn figure(series: Vec<FigureSeries>) -> Figure {
+        let mut x_axis = Axis::new(AxisScale::Time, None);
+        x_axis.range = Some((date_time(0), date_time(90)));
+        x_axis.format = Some("%H:%M:%S".to_owned());
+        Figure {
+            title: "Session".to_owned(),
+            x_axis,
+            y_axis: Axis::new(AxisScale::Linear, Some("Load".to_owned())),
+            bands: vec![
+                Band { x0: date_time(0), x1: date_time(60), y_range: None, color: "#1f77b433".to_owned(), label: Some("Asystole".to_owned()) },
+                Band { x0: date_time(60), x1: date_time(90), y_range: None, color: "#d6272833".to_owned(), label: None },
+            ],
+            series,
+            background_color: Some("#ffffff".to_owned()),
+            font_color: None,
+        }
+    }
+
+    fn line(name: &str, color: Option<&str>) -> FigureSeries {
+        FigureSeries {
+            name: name.to_owned(),
+            mark: SeriesMark::Line,
+            color: color.map(str::to_owned),
+            show_legend: true,
+            points: vec![FigurePoint::new(date_time(5), Some(0.5)), FigurePoint::new(date_time(10), None)],
+        }
+    }
+
+    #[test]
+    fn bands_and_lines_are_layered() {
+        let spec = to_vega_lite(&figure(vec![line("Nurse", Some("#ff7f0e")), line("Leader", Some("#2ca02c"))]));
+
+        assert_eq!(spec["$schema"], SCHEMA);
+        assert_eq!(spec["background"], "#ffffff");
+        let layers = spec["layer"].as_array().unwrap();
+        // band rectangles, their labels, then the lines
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers[0]["mark"]["type"], "rect");
+        assert_eq!(layers[0]["encoding"].get("y"), None);
+        let domain_end = &layers[0]["encoding"]["x"]["scale"]["domain"][1];
+        assert_eq!((&domain_end["hours"], &domain_end["minutes"], &domain_end["seconds"]), (&json!(0), &json!(1), &json!(30)));
+        assert_eq!(layers[1]["encoding"]["y"], json!({"value": 0}));
+
+        let lines = &layers[2];
+        assert_eq!(lines["data"]["values"].as_array().unwrap().len(), 4);
+        assert!(lines["data"]["values"][0]["x"].as_str().unwrap().ends_with("T00:00:05"));
+        assert_eq!(lines["data"]["values"][1]["y"], Value::Null);
+        assert_eq!(lines["encoding"]["color"]["scale"], json!({"domain": ["Nurse", "Leader"], "range": ["#ff7f0e", "#2ca02c"]}));
+        assert_eq!(lines["encoding"]["y"]["title"], "Load");
+        assert_eq!(lines["encoding"].get("tooltip"), None);
+    }
+
+    #[test]
+    fn series_without_colors_use_the_default_palette() {
+        let spec = to_vega_lite(&figure(vec![line("Nurse", None), line("Leader", Some("#2ca02c"))]));
+        assert_eq!(spec["layer"][2]["encoding"]["color"]["scale"], json!({"domain": ["Nurse", "Leader"]}));
+    }
+
+    #[test]
+    fn points_keep_their_colors_and_labels() {
+        let mut point = FigurePoint::new(date_time(30), Some(2.0));
+        point.color = Some("#000000".to_owned());
+        point.label = Some("200J".to_owned());
+        point.tooltip = Some("00:00:30, Defib".to_owned());
+        let actions = FigureSeries { name: "Performed Actions".to_owned(), mark: SeriesMark::Point("square".to_owned()), color: None, show_legend: false, points: vec![point] };
+        let spec = to_vega_lite(&figure(vec![actions]));
+
+        let layers = spec["layer"].as_array().unwrap();
+        assert_eq!(layers.len(), 4);
+        assert_eq!(layers[2]["mark"]["shape"], "square");
+        assert_eq!(layers[2]["encoding"]["color"], literal_color());
+        assert_eq!(layers[2]["data"]["values"][0]["color"], "#000000");
+        assert_eq!(layers[2]["encoding"]["tooltip"]["field"], "tooltip");
+        assert_eq!(layers[3]["mark"]["type"], "text");
+        assert_eq!(layers[3]["data"]["values"][0]["label"], "200J");
+    }
+
+    #[test]
+    fn stacked_bars_are_ordered_by_series() {
+        let mut patient = line("Patient", Some("#d62728"));
+        patient.mark = SeriesMark::StackedBar;
+        let mut team = line("Team", Some("#1f77b4"));
+        team.mark = SeriesMark::StackedBar;
+        let spec = to_vega_lite(&figure(vec![patient, team]));
+
+        let bars = &spec["layer"][2];
+        assert_eq!(bars["mark"]["type"], "bar");
+        assert_eq!(bars["encoding"]["y"]["stack"], "zero");
+        assert_eq!(bars["data"]["values"][2]["seriesIndex"], 1);
+    }
+}
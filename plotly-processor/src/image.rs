@@ -1,11 +1,12 @@
+use crate::value::PlotValue;
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 #[derive(Clone)]
 pub struct Image {
     pub source: String,
-    pub x: String,
-    pub y: String,
+    pub x: PlotValue,
+    pub y: PlotValue,
     pub sizex: f64,
     pub sizey: f64,
     pub xref: String,
@@ -1,6 +1,9 @@
 use crate::annotation::Annotation;
+use crate::config::layout_settings::{LayoutSettings, Theme};
+use crate::font::Font;
 use crate::image::Image;
 use crate::shape::Shape;
+use crate::value::PlotValue;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -15,12 +18,24 @@ pub struct Layout {
     pub legend: Legend,
     pub shapes: Vec<Shape>,
     pub annotations: Vec<Annotation>,
-    pub images: Vec<Image>
+    pub images: Vec<Image>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paper_bgcolor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plot_bgcolor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font: Option<Font>
 }
 
 impl Layout{
-    pub fn new() -> Self{
-       Self {
+    pub fn new(settings: &LayoutSettings, theme: &Theme) -> Self{
+        let font = (settings.font_family.is_some() || theme.font_color.is_some()).then(|| Font {
+            size: 12,
+            color: theme.font_color.clone(),
+            family: settings.font_family.clone(),
+            weight: None,
+        });
+        Self {
             annotations:Vec::new(),
             autosize: true,
             images: Vec::new(),
@@ -29,25 +44,24 @@ impl Layout{
             y: 1.0,
             xanchor: "right".to_owned()
         },
-            margin: Margin {
-            t: 0.0,
-            l: 50.0,
-            r: 50.0,
-            b: 50.0,
-        },
+            margin: settings.margin.clone(),
             modebar: ModeBar {
             orientation: "v".to_owned(),
         },
             shapes: Vec::new(),
             showlegend: false,
-            title: Title { text: "Clinical Review Timeline".to_owned(), y: 0.99 },
+            title: Title { text: settings.title.clone(), y: settings.title_y },
             xaxis: XAxis {
             range: Vec::new(),
             title: "Time".to_owned(),
             showgrid: false,
             tickformat: "%H:%M:%S".to_owned(),
+            anchor: None,
         },
-            yaxis: YAxis { visible: false, range: Vec::new() },
+            yaxis: YAxis { visible: false, range: Vec::new(), domain: None, title: None },
+            paper_bgcolor: theme.paper_background_color.clone(),
+            plot_bgcolor: theme.plot_background_color.clone(),
+            font,
         }
     }
 }
@@ -58,7 +72,7 @@ pub struct Title {
     pub y: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Margin {
     pub t: f64,
     pub l: f64,
@@ -68,16 +82,26 @@ pub struct Margin {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct XAxis {
-    pub range: Vec<String>,
+    pub range: Vec<PlotValue>,
     pub title: String,
     pub showgrid: bool,
     pub tickformat: String,
+    /// Y axis the time axis is drawn at, figures with subplots anchor it to the bottom one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct YAxis {
     pub visible: bool,
-    pub range: Vec<String>,
+    /// Autoscaled when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub range: Vec<PlotValue>,
+    /// Vertical fraction of the figure the axis spans, the whole plot area when not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<[f64; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
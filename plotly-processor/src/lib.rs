@@ -4,7 +4,13 @@ pub mod annotation;
 pub mod font;
 pub mod image;
 pub mod layout;
+pub mod value;
 pub mod config;
 pub mod actions;
 #[path = "visual-attention/mod.rs"]
-pub mod visual_attention;
\ No newline at end of file
+pub mod visual_attention;
+#[path = "cognitive-load/mod.rs"]
+pub mod cognitive_load;
+pub mod dashboard;
+pub mod comparison;
+pub mod figure;
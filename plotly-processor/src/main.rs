@@ -0,0 +1,36 @@
+use mteam_dashboard_plotly_processor::config::plotly_mappings::PlotlyConfig;
+use mteam_dashboard_plotly_processor::config::validator::{validate_config, validate_session, SampleSession};
+use std::path::Path;
+use std::{env, process};
+
+/// Checks the plot-config folder, optionally against a session folder, and exits with 1 when it has errors.
+/// Usage: mteam-dashboard-plotly-processor <plot-config folder> [session folder]
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("Usage: {} <plot-config folder> [session folder]", args[0]);
+        process::exit(2);
+    }
+    let config = match PlotlyConfig::load(Path::new(&args[1])) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}: {}", args[1], e);
+            process::exit(1);
+        }
+    };
+    let mut report = validate_config(&config);
+    if let Some(session_dir) = args.get(2) {
+        match SampleSession::load(Path::new(session_dir), &config) {
+            Ok(session) => report.extend(validate_session(&config, &session)),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+    report.issues.iter().for_each(|issue| println!("{}", issue));
+    println!("{} error(s), {} warning(s)", report.errors().count(), report.warnings().count());
+    if report.has_errors() {
+        process::exit(1);
+    }
+}
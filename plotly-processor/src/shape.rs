@@ -1,15 +1,16 @@
 use crate::line::Line;
+use crate::value::PlotValue;
 use mteam_dashboard_action_processor::plot_structures::PlotLocation;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Shape {
-    pub x0: String,
-    pub x1: String,
+    pub x0: PlotValue,
+    pub x1: PlotValue,
     pub fillcolor: String,
     pub name: String,
-    pub y0: String,
-    pub y1: String,
+    pub y0: PlotValue,
+    pub y1: PlotValue,
     #[serde(rename = "type")]
     pub shape_type: String,
     pub xref: String,
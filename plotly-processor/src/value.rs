@@ -0,0 +1,127 @@
+use chrono::NaiveDateTime;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Format of the datetime values on the date axes of the plots.
+pub const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Coordinate or axis range value of a Plotly figure. Numbers serialize as JSON numbers, datetimes and categories as
+/// strings, which is the shape Plotly expects on linear, date and category axes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlotValue {
+    Number(f64),
+    DateTime(NaiveDateTime),
+    Category(String),
+}
+
+impl PlotValue {
+    /// Parses a datetime formatted as `DATE_TIME_FORMAT` (e.g. `CsvRowTime::date_string`), anything else is a category.
+    pub fn date_time(date_string: &str) -> Self {
+        NaiveDateTime::parse_from_str(date_string, DATE_TIME_FORMAT)
+            .map(PlotValue::DateTime)
+            .unwrap_or_else(|_| PlotValue::Category(date_string.to_owned()))
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            PlotValue::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn as_date_time(&self) -> Option<NaiveDateTime> {
+        match self {
+            PlotValue::DateTime(date_time) => Some(*date_time),
+            _ => None,
+        }
+    }
+}
+
+impl Default for PlotValue {
+    fn default() -> Self {
+        PlotValue::Number(0.0)
+    }
+}
+
+/// Goes through the decimal representation so that e.g. `0.3f32` becomes `0.3` rather than `0.30000001192092896`.
+impl From<f32> for PlotValue {
+    fn from(number: f32) -> Self {
+        PlotValue::Number(number.to_string().parse().unwrap_or(number as f64))
+    }
+}
+
+impl From<f64> for PlotValue {
+    fn from(number: f64) -> Self {
+        PlotValue::Number(number)
+    }
+}
+
+impl From<NaiveDateTime> for PlotValue {
+    fn from(date_time: NaiveDateTime) -> Self {
+        PlotValue::DateTime(date_time)
+    }
+}
+
+impl Serialize for PlotValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PlotValue::Number(number) => serializer.serialize_f64(*number),
+            PlotValue::DateTime(date_time) => serializer.collect_str(&date_time.format(DATE_TIME_FORMAT)),
+            PlotValue::Category(category) => serializer.serialize_str(category),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PlotValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PlotValueVisitor;
+
+        impl Visitor<'_> for PlotValueVisitor {
+            type Value = PlotValue;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a number or a string")
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                Ok(PlotValue::Number(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                Ok(PlotValue::Number(value as f64))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                Ok(PlotValue::Number(value as f64))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(PlotValue::date_time(value))
+            }
+        }
+
+        deserializer.deserialize_any(PlotValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests_plot_value {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serializes_to_plotly_values() {
+        let values = vec![PlotValue::from(0.3f32), PlotValue::date_time("2025-01-01 00:01:05"), PlotValue::Category("Airway".to_owned())];
+        assert_eq!(serde_json::to_value(&values).unwrap(), json!([0.3, "2025-01-01 00:01:05", "Airway"]));
+    }
+
+    #[test]
+    fn deserializes_by_value_type() {
+        let values: Vec<PlotValue> = serde_json::from_value(json!([2, 1.5, "2025-01-01 00:01:05", "Airway"])).unwrap();
+        assert_eq!(values[0], PlotValue::Number(2.0));
+        assert_eq!(values[1].as_number(), Some(1.5));
+        assert!(values[2].as_date_time().is_some());
+        assert_eq!(values[3], PlotValue::Category("Airway".to_owned()));
+    }
+}
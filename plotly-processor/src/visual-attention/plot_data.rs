@@ -1,12 +1,97 @@
+use crate::value::PlotValue;
+use mteam_dashboard_visual_attention_processor::analytics::{DwellStatistics, TransitionMatrix};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// One bar trace per category, `x` holds every window end so the traces line up. `y` is null for the windows
+/// without data (`noData`), `unreliable` flags the windows with data but fewer samples than configured.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct VisualAttentionCategory {
+    pub x: Vec<PlotValue>,
+    pub y: Vec<Option<f64>>,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub plot_type: String,
+    pub marker: HashMap<String, String>, //will only set color
+    #[serde(rename = "noData")]
+    pub no_data: Vec<bool>,
+    pub unreliable: Vec<bool>,
+    #[serde(rename = "sampleCount")]
+    pub sample_count: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VisualAttentionPlotData {
+    pub data: Vec<VisualAttentionCategory>,
+    /// Names the categories found in the data but missing from the configured color map.
+    pub warnings: Vec<String>,
+}
+
+/// Plotly heatmap of the transition probabilities, rows (`y`) are the categories transitioned from.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransitionHeatmap {
+    pub z: Vec<Vec<Option<f64>>>,
+    pub x: Vec<String>,
+    pub y: Vec<String>,
+    /// Transition counts behind the probabilities, shown on hover.
+    pub customdata: Vec<Vec<usize>>,
+    #[serde(rename = "type")]
+    pub plot_type: String,
+    pub colorscale: String,
+    pub hovertemplate: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EntropySeries {
+    pub x: Vec<String>,
+    pub y: Vec<Option<f64>>,
+    pub name: String,
+    pub mode: String,
+    #[serde(rename = "type")]
+    pub plot_type: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct VisualAttentionAnalyticsPlotData {
+    pub dwells: Vec<DwellStatistics>,
+    pub transitions: TransitionMatrix,
+    pub heatmap: TransitionHeatmap,
+    pub entropy: EntropySeries,
+}
+
+/// Plotly heatmap of the number of members attending each category (`y`) over time (`x`).
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JointAttentionHeatmap {
+    pub z: Vec<Vec<usize>>,
     pub x: Vec<String>,
-    pub y: Vec<f64>,
+    pub y: Vec<String>,
+    #[serde(rename = "type")]
+    pub plot_type: String,
+    pub colorscale: String,
+    pub zmin: usize,
+    pub zmax: usize,
+    pub hovertemplate: String,
+}
+
+/// Largest number of members attending the same category over time, the category is in `customdata`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JointAttentionSeries {
+    pub x: Vec<String>,
+    pub y: Vec<usize>,
+    pub customdata: Vec<Option<String>>,
     pub name: String,
+    pub mode: String,
     #[serde(rename = "type")]
     pub plot_type: String,
-    pub marker: HashMap<String, String> //will only set color
-}
\ No newline at end of file
+    pub line: HashMap<String, String>,
+    pub hovertemplate: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JointAttentionPlotData {
+    pub heatmap: JointAttentionHeatmap,
+    pub joint: JointAttentionSeries,
+    pub members: Vec<String>,
+    #[serde(rename = "resolutionSeconds")]
+    pub resolution_secs: u32,
+}
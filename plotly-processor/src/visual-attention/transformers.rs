@@ -1,34 +1,193 @@
-use crate::config::plotly_mappings::PlotlyConfig;
-use crate::visual_attention::plot_data::VisualAttentionCategory;
-use mteam_dashboard_visual_attention_processor::file_processor::process_visual_attention_data;
-use std::collections::HashMap;
+use crate::config::plotly_mappings::{PlotlyConfig, VisualAttentionPlotSettings};
+use crate::value::PlotValue;
+use crate::visual_attention::plot_data::{EntropySeries, JointAttentionHeatmap, JointAttentionPlotData, JointAttentionSeries, TransitionHeatmap, VisualAttentionAnalyticsPlotData, VisualAttentionCategory, VisualAttentionPlotData};
+use mteam_dashboard_utils::alignment::StreamOffset;
+use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+use mteam_dashboard_visual_attention_processor::category::CategoryMapping;
+use mteam_dashboard_visual_attention_processor::file_processor::{analyze_visual_attention_data, normalize_visual_attention_load_data, process_visual_attention_data};
+use mteam_dashboard_visual_attention_processor::joint_attention::joint_attention;
+use mteam_dashboard_visual_attention_processor::window::{AttentionWindow, WindowSettings};
+use std::collections::{BTreeSet, HashMap};
 use std::io;
 use std::io::Read;
 
-pub fn to_plotly_data(reader: &mut impl Read, window_duration_secs: u32, config: &PlotlyConfig) -> Result<Vec<VisualAttentionCategory>, io::Error> {
-    let ref mut category_map = HashMap::new();
-
-    process_visual_attention_data(reader, window_duration_secs)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))? // Convert String error to io::Error
-        .for_each(|(category, time, ratio)| {
-            let point = category_map.entry(category.clone()).or_insert_with(|| VisualAttentionCategory {
-                x: vec![],
-                y: vec![],
-                name: category,
-                plot_type: "bar".to_owned(),
-                marker: HashMap::new()
-            });
-            point.x.push(time);
-            point.y.push(ratio);
-        });
-
-    let mut ordered_categories = Vec::new();
-
-    for (category, color) in &config.visual_attention_plot_settings.ordered_category_color_tuples {
-        if let Some(mut category_data) = category_map.remove(category) {
-            category_data.marker.insert("color".to_owned(), color.to_owned());
-            ordered_categories.push(category_data);
-        }
+/// Configured categories in the configured order, then the unconfigured ones alphabetically with fallback colors.
+pub(crate) fn order_categories(found_categories: BTreeSet<&String>, settings: &VisualAttentionPlotSettings) -> (Vec<(String, String)>, Vec<String>) {
+    let mut ordered: Vec<(String, String)> = settings.ordered_category_color_tuples.iter()
+        .filter(|(category, _)| found_categories.contains(category))
+        .cloned()
+        .collect();
+
+    let unmapped: Vec<&String> = found_categories.into_iter()
+        .filter(|category| !settings.ordered_category_color_tuples.iter().any(|(configured, _)| configured == *category))
+        .collect();
+    let mut fallback_colors = settings.fallback_colors();
+    for category in &unmapped {
+        ordered.push(((*category).to_owned(), fallback_colors.next().unwrap_or_default()));
+    }
+
+    let warnings = unmapped.into_iter()
+        .map(|category| format!("Category \"{}\" is not in orderedColorMap of visual-attention-plot-settings.json, a fallback color is used", category))
+        .collect();
+    (ordered, warnings)
+}
+
+pub fn to_plotly_data(reader: &mut impl Read, window_settings: &WindowSettings, category_mapping: &CategoryMapping, alignment: &StreamOffset, config: &PlotlyConfig) -> Result<VisualAttentionPlotData, io::Error> {
+    let windows: Vec<AttentionWindow> = process_visual_attention_data(reader, window_settings, category_mapping, alignment)
+        .map_err(io::Error::other)? // Convert String error to io::Error
+        .collect();
+
+    let x: Vec<PlotValue> = windows.iter().map(|window| PlotValue::date_time(&seconds_to_csv_row_time(window.end_secs).date_string)).collect();
+    let no_data: Vec<bool> = windows.iter().map(|window| !window.has_data()).collect();
+    let unreliable: Vec<bool> = windows.iter().map(|window| window.has_data() && !window.reliable).collect();
+    let sample_count: Vec<usize> = windows.iter().map(|window| window.sample_count).collect();
+
+    let found_categories: BTreeSet<&String> = windows.iter().flat_map(|window| window.category_counts.keys()).collect();
+    let (ordered_category_colors, warnings) = order_categories(found_categories, &config.visual_attention_plot_settings);
+
+    let data = ordered_category_colors.into_iter()
+        .map(|(category, color)| VisualAttentionCategory {
+            x: x.clone(),
+            y: windows.iter().map(|window| window.ratio(&category)).collect(),
+            name: category,
+            plot_type: "bar".to_owned(),
+            marker: HashMap::from([("color".to_owned(), color)]),
+            no_data: no_data.clone(),
+            unreliable: unreliable.clone(),
+            sample_count: sample_count.clone(),
+        })
+        .collect();
+
+    Ok(VisualAttentionPlotData { data, warnings })
+}
+
+/// Dwell statistics, the transition matrix (also as a heatmap trace, in the configured category order) and the
+/// attention entropy per window.
+pub fn to_analytics_plotly_data(reader: &mut impl Read, window_settings: &WindowSettings, category_mapping: &CategoryMapping, alignment: &StreamOffset, config: &PlotlyConfig) -> Result<VisualAttentionAnalyticsPlotData, io::Error> {
+    let analytics = analyze_visual_attention_data(reader, window_settings, category_mapping, alignment)
+        .map_err(io::Error::other)?;
+
+    let configured_order: Vec<String> = config.visual_attention_plot_settings.ordered_category_color_tuples.iter()
+        .map(|(category, _)| category.to_owned())
+        .collect();
+    let transitions = analytics.transitions.reordered(&configured_order);
+
+    let heatmap = TransitionHeatmap {
+        z: transitions.probabilities.clone(),
+        x: transitions.categories.clone(),
+        y: transitions.categories.clone(),
+        customdata: transitions.counts.clone(),
+        plot_type: "heatmap".to_owned(),
+        colorscale: "Blues".to_owned(),
+        hovertemplate: "%{y} → %{x}: %{z:.2f} (%{customdata} transitions)<extra></extra>".to_owned(),
+    };
+    let entropy = EntropySeries {
+        x: analytics.entropy.iter().map(|window| seconds_to_csv_row_time(window.end_secs).date_string).collect(),
+        y: analytics.entropy.iter().map(|window| window.entropy).collect(),
+        name: "Attention Entropy".to_owned(),
+        mode: "lines".to_owned(),
+        plot_type: "scatter".to_owned(),
+    };
+
+    Ok(VisualAttentionAnalyticsPlotData {
+        dwells: analytics.dwells,
+        transitions,
+        heatmap,
+        entropy,
+    })
+}
+
+/// Joint attention of the team members, `members` are the member names with their visual attention data and alignment.
+/// The members' attention is sampled every `resolution_secs`, a member's category is the one of their latest sample
+/// unless it's older than `maxSampleDurationSeconds`.
+pub fn to_joint_attention_plotly_data<R: Read>(members: Vec<(String, R, StreamOffset)>, resolution_secs: u32, category_mapping: &CategoryMapping, config: &PlotlyConfig) -> Result<JointAttentionPlotData, io::Error> {
+    let settings = &config.visual_attention_plot_settings;
+    let mut member_names = Vec::with_capacity(members.len());
+    let mut member_samples = Vec::with_capacity(members.len());
+    for (name, mut reader, alignment) in members {
+        let mut samples: Vec<(f64, Option<String>)> = normalize_visual_attention_load_data(&mut reader, category_mapping, &alignment)
+            .map_err(|e| io::Error::other(format!("{}: {}", name, e)))?
+            .collect();
+        samples.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        member_names.push(name);
+        member_samples.push(samples);
+    }
+
+    let configured_order: Vec<String> = settings.ordered_category_color_tuples.iter()
+        .map(|(category, _)| category.to_owned())
+        .collect();
+    let joint = joint_attention(&member_samples, resolution_secs.max(1) as f64, settings.max_sample_duration_secs)
+        .reordered(&configured_order);
+    let x: Vec<String> = joint.times_secs.iter().map(|time| seconds_to_csv_row_time(*time as u32).date_string).collect();
+
+    Ok(JointAttentionPlotData {
+        heatmap: JointAttentionHeatmap {
+            z: joint.counts,
+            x: x.clone(),
+            y: joint.categories,
+            plot_type: "heatmap".to_owned(),
+            colorscale: "YlOrRd".to_owned(),
+            zmin: 0,
+            zmax: joint.member_count,
+            hovertemplate: "%{y}: %{z} members<extra></extra>".to_owned(),
+        },
+        joint: JointAttentionSeries {
+            x,
+            y: joint.joint_counts,
+            customdata: joint.joint_categories,
+            name: "Joint Attention".to_owned(),
+            mode: "lines".to_owned(),
+            plot_type: "scatter".to_owned(),
+            line: HashMap::from([("shape".to_owned(), "hv".to_owned())]),
+            hovertemplate: "%{y} members on %{customdata}".to_owned(),
+        },
+        members: member_names,
+        resolution_secs: resolution_secs.max(1),
+    })
+}
+
+#[cfg(test)]
+mod tests_visual_attention_transformers {
+    use super::*;
+
+    fn settings() -> VisualAttentionPlotSettings {
+        serde_json::from_str(r##"{
+            "windowSizeSeconds": 10,
+            "orderedColorMap": [["Patient", "#d62728"], ["Team", "#1f77b4"], ["Monitors", "#2ba02b"]],
+            "fallbackColors": ["#1F77B4", "#ff7f0e", "#8c564b"]
+        }"##).unwrap()
+    }
+
+    #[test]
+    fn unconfigured_categories_follow_the_configured_ones() {
+        let patient = "Patient".to_owned();
+        let team = "Team".to_owned();
+        let screen = "Screen".to_owned();
+        let door = "Door".to_owned();
+        let (ordered, warnings) = order_categories(BTreeSet::from([&patient, &team, &screen, &door]), &settings());
+        assert_eq!(ordered, vec![
+            ("Patient".to_owned(), "#d62728".to_owned()),
+            ("Team".to_owned(), "#1f77b4".to_owned()),
+            ("Door".to_owned(), "#ff7f0e".to_owned()),
+            ("Screen".to_owned(), "#8c564b".to_owned()),
+        ]);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("\"Door\""));
+    }
+
+    #[test]
+    fn fallback_colors_repeat_when_exhausted() {
+        let categories: Vec<String> = ["A", "B", "C"].iter().map(|c| c.to_string()).collect();
+        let (ordered, _) = order_categories(categories.iter().collect(), &settings());
+        assert_eq!(ordered[2].1, "#ff7f0e");
+    }
+
+    #[test]
+    fn empty_fallback_colors_use_the_built_in_palette() {
+        let mut settings = settings();
+        settings.fallback_colors.clear();
+        let door = "Door".to_owned();
+        let (ordered, _) = order_categories(BTreeSet::from([&door]), &settings);
+        assert_eq!(ordered, vec![("Door".to_owned(), "#ff7f0e".to_owned())]);
     }
-    Ok(ordered_categories)
-}
\ No newline at end of file
+}
@@ -0,0 +1,314 @@
+use crate::session_report::{summarize_actions, SessionMetrics};
+use chrono::{Datelike, NaiveDate};
+use mteam_dashboard_action_processor::plot_structures::ActionPlotPoint;
+use mteam_dashboard_plotly_processor::config::plotly_mappings::PlotlyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DAYS_PER_QUARTER: f64 = 365.25 / 4.0;
+const TIME_TO_PREFIX: &str = "timeTo:";
+
+/// Data of a session of the cohort, read and time aligned by the caller.
+pub struct CohortSessionInput {
+    pub session_name: String,
+    pub date: NaiveDate,
+    pub scenario_name: Option<String>,
+    pub action_points: Vec<ActionPlotPoint>,
+}
+
+/// Metrics of a session. They don't depend on the key actions of a cohort report, so they can be cached.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CohortSession {
+    pub session: String,
+    /// `YYYY-MM-DD`
+    pub date: String,
+    #[serde(rename = "scenarioName")]
+    pub scenario_name: Option<String>,
+    pub metrics: SessionMetrics,
+    #[serde(rename = "erroneousActionsByGroup")]
+    pub erroneous_actions_by_group: BTreeMap<String, usize>,
+    #[serde(rename = "missedActionsByStage")]
+    pub missed_actions_by_stage: BTreeMap<String, usize>,
+    /// Seconds into the session each action is first performed at.
+    #[serde(rename = "firstPerformedSeconds")]
+    pub first_performed_secs: BTreeMap<String, u32>,
+}
+
+impl CohortSession {
+    pub fn new(input: CohortSessionInput, plotly_config: &PlotlyConfig) -> Self {
+        let (metrics, _, _) = summarize_actions(&input.action_points, plotly_config);
+        let mut erroneous_actions_by_group: BTreeMap<String, usize> = BTreeMap::new();
+        let mut missed_actions_by_stage: BTreeMap<String, usize> = BTreeMap::new();
+        let mut first_performed_secs: BTreeMap<String, u32> = BTreeMap::new();
+        for point in &input.action_points {
+            let (name, seconds) = match point {
+                ActionPlotPoint::Action(action) => (&action.name, action.location.timestamp.total_seconds),
+                ActionPlotPoint::Error(action) => {
+                    *erroneous_actions_by_group.entry(plotly_config.get_action_group_name(&action.name)).or_default() += 1;
+                    (&action.name, action.location.timestamp.total_seconds)
+                }
+                ActionPlotPoint::MissedAction(action) => {
+                    *missed_actions_by_stage.entry(plotly_config.stages.display_name(&action.location.stage.1)).or_default() += 1;
+                    continue;
+                }
+                ActionPlotPoint::Period(..) => continue,
+            };
+            first_performed_secs.entry(name.clone())
+                .and_modify(|first| *first = (*first).min(seconds))
+                .or_insert(seconds);
+        }
+        Self {
+            session: input.session_name,
+            date: input.date.format(DATE_FORMAT).to_string(),
+            scenario_name: input.scenario_name,
+            metrics,
+            erroneous_actions_by_group,
+            missed_actions_by_stage,
+            first_performed_secs,
+        }
+    }
+
+    fn parsed_date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&self.date, DATE_FORMAT).ok()
+    }
+
+    /// `YYYY-Qn` of the session date.
+    pub fn quarter(&self) -> String {
+        self.parsed_date()
+            .map(|date| format!("{}-Q{}", date.year(), date.month0() / 3 + 1))
+            .unwrap_or_default()
+    }
+
+    /// Session metrics and the time to each key action (`timeTo:<action>`), null when the action isn't performed.
+    pub fn metric_values(&self, key_actions: &[String]) -> Vec<(String, Option<f64>)> {
+        let metrics = &self.metrics;
+        let mut values: Vec<(String, Option<f64>)> = [
+            ("durationSeconds", metrics.duration_secs as f64),
+            ("stageCount", metrics.stage_count as f64),
+            ("actionCount", metrics.action_count as f64),
+            ("erroneousActionCount", metrics.erroneous_action_count as f64),
+            ("missedActionCount", metrics.missed_action_count as f64),
+            ("cprPeriodCount", metrics.cpr_period_count as f64),
+            ("cprDurationSeconds", metrics.cpr_duration_secs as f64),
+        ].into_iter().map(|(name, value)| (name.to_owned(), Some(value))).collect();
+        values.extend(key_actions.iter().map(|action| {
+            (format!("{}{}", TIME_TO_PREFIX, action), self.first_performed_secs.get(action).map(|seconds| *seconds as f64))
+        }));
+        values
+    }
+}
+
+/// Session that couldn't be read, it is left out of the cohort.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SkippedSession {
+    pub session: String,
+    pub error: String,
+}
+
+/// Means of the metrics over the sessions of a quarter, a metric without any value is null.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CohortQuarter {
+    pub quarter: String,
+    #[serde(rename = "sessionCount")]
+    pub session_count: usize,
+    pub means: BTreeMap<String, Option<f64>>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MetricTrend {
+    pub metric: String,
+    /// Sessions with a value of the metric.
+    #[serde(rename = "sessionCount")]
+    pub session_count: usize,
+    pub mean: Option<f64>,
+    /// Slope of the least squares line through the session values, per quarter of a year. Null with fewer than two
+    /// sessions or when all sessions are on the same day.
+    #[serde(rename = "changePerQuarter")]
+    pub change_per_quarter: Option<f64>,
+}
+
+/// Metrics of all sessions in date order, their quarterly means and trends.
+#[derive(Serialize, Debug)]
+pub struct CohortReport {
+    #[serde(rename = "keyActions")]
+    pub key_actions: Vec<String>,
+    pub sessions: Vec<CohortSession>,
+    pub quarters: Vec<CohortQuarter>,
+    pub trends: Vec<MetricTrend>,
+    pub skipped: Vec<SkippedSession>,
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Least squares slope of `(day, value)` points, per quarter.
+fn change_per_quarter(points: &[(f64, f64)]) -> Option<f64> {
+    let day_mean = mean(&points.iter().map(|(day, _)| *day).collect::<Vec<_>>())?;
+    let value_mean = mean(&points.iter().map(|(_, value)| *value).collect::<Vec<_>>())?;
+    let covariance: f64 = points.iter().map(|(day, value)| (day - day_mean) * (value - value_mean)).sum();
+    let variance: f64 = points.iter().map(|(day, _)| (day - day_mean).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance * DAYS_PER_QUARTER)
+}
+
+impl CohortReport {
+    pub fn new(mut sessions: Vec<CohortSession>, skipped: Vec<SkippedSession>, key_actions: &[String]) -> Self {
+        sessions.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.session.cmp(&b.session)));
+        let session_values: Vec<Vec<(String, Option<f64>)>> = sessions.iter().map(|session| session.metric_values(key_actions)).collect();
+        let metric_names: Vec<String> = session_values.first()
+            .map(|values| values.iter().map(|(name, _)| name.clone()).collect())
+            .unwrap_or_default();
+
+        let mut quarters: Vec<CohortQuarter> = Vec::new();
+        for session in &sessions {
+            let quarter = session.quarter();
+            if quarters.last().is_none_or(|last| last.quarter != quarter) {
+                quarters.push(CohortQuarter { quarter, session_count: 0, means: BTreeMap::new() });
+            }
+            if let Some(last) = quarters.last_mut() {
+                last.session_count += 1;
+            }
+        }
+        for quarter in &mut quarters {
+            quarter.means = metric_names.iter().enumerate()
+                .map(|(index, name)| {
+                    let values: Vec<f64> = sessions.iter().zip(&session_values)
+                        .filter(|(session, _)| session.quarter() == quarter.quarter)
+                        .filter_map(|(_, values)| values[index].1)
+                        .collect();
+                    (name.clone(), mean(&values))
+                })
+                .collect();
+        }
+
+        let trends = metric_names.iter().enumerate()
+            .map(|(index, name)| {
+                let points: Vec<(f64, f64)> = sessions.iter().zip(&session_values)
+                    .filter_map(|(session, values)| {
+                        let day = session.parsed_date()?.num_days_from_ce() as f64;
+                        values[index].1.map(|value| (day, value))
+                    })
+                    .collect();
+                let values: Vec<f64> = points.iter().map(|(_, value)| *value).collect();
+                MetricTrend { metric: name.clone(), session_count: values.len(), mean: mean(&values), change_per_quarter: change_per_quarter(&points) }
+            })
+            .collect();
+
+        Self { key_actions: key_actions.to_vec(), sessions, quarters, trends, skipped }
+    }
+
+    /// A row per session: the metrics, the time to each key action, then the erroneous actions per action group and
+    /// the missed actions per stage found in any session. Missing values are empty.
+    pub fn to_csv(&self) -> Result<String, String> {
+        let groups: BTreeSet<&String> = self.sessions.iter().flat_map(|session| session.erroneous_actions_by_group.keys()).collect();
+        let stages: BTreeSet<&String> = self.sessions.iter().flat_map(|session| session.missed_actions_by_stage.keys()).collect();
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let mut header: Vec<String> = ["session", "date", "quarter", "scenarioName"].map(str::to_owned).to_vec();
+        if let Some(session) = self.sessions.first() {
+            header.extend(session.metric_values(&self.key_actions).into_iter().map(|(name, _)| name));
+        }
+        header.extend(groups.iter().map(|group| format!("erroneous:{}", group)));
+        header.extend(stages.iter().map(|stage| format!("missed:{}", stage)));
+        writer.write_record(&header).map_err(|e| e.to_string())?;
+
+        for session in &self.sessions {
+            let mut record = vec![session.session.clone(), session.date.clone(), session.quarter(), session.scenario_name.clone().unwrap_or_default()];
+            record.extend(session.metric_values(&self.key_actions).into_iter().map(|(_, value)| value.map(|value| value.to_string()).unwrap_or_default()));
+            record.extend(groups.iter().map(|group| session.erroneous_actions_by_group.get(*group).copied().unwrap_or_default().to_string()));
+            record.extend(stages.iter().map(|stage| session.missed_actions_by_stage.get(*stage).copied().unwrap_or_default().to_string()));
+            writer.write_record(&record).map_err(|e| e.to_string())?;
+        }
+        let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+        String::from_utf8(bytes).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests_cohort {
+    use super::*;
+    use crate::session_report::tests_session_report::{action_points, plotly_config};
+
+    fn session(name: &str, date: &str, erroneous_action_count: usize, first_defib_secs: Option<u32>) -> CohortSession {
+        CohortSession {
+            session: name.to_owned(),
+            date: date.to_owned(),
+            scenario_name: None,
+            metrics: SessionMetrics { erroneous_action_count, ..Default::default() },
+            erroneous_actions_by_group: BTreeMap::from([("Defibrillation".to_owned(), erroneous_action_count)]),
+            missed_actions_by_stage: BTreeMap::new(),
+            first_performed_secs: first_defib_secs.map(|seconds| BTreeMap::from([("Defib".to_owned(), seconds)])).unwrap_or_default(),
+        }
+    }
+
+    fn value<'a>(values: &'a BTreeMap<String, Option<f64>>, name: &str) -> &'a Option<f64> {
+        &values[name]
+    }
+
+    #[test]
+    fn session_metrics() {
+        let plotly_config = plotly_config();
+        let input = CohortSessionInput {
+            session_name: "09182024".to_owned(),
+            date: NaiveDate::from_ymd_opt(2024, 9, 18).unwrap(),
+            scenario_name: Some("Cardiac Arrest".to_owned()),
+            action_points: action_points(),
+        };
+        let session = CohortSession::new(input, &plotly_config);
+        assert_eq!(session.date, "2024-09-18");
+        assert_eq!(session.quarter(), "2024-Q3");
+        assert_eq!(session.metrics.erroneous_action_count, 1);
+        assert_eq!(session.erroneous_actions_by_group.values().sum::<usize>(), 1);
+        assert_eq!(session.missed_actions_by_stage, BTreeMap::from([("Stage B".to_owned(), 1)]));
+        assert_eq!(session.first_performed_secs, BTreeMap::from([("Check Pulse".to_owned(), 10), ("Defib".to_owned(), 130)]));
+
+        let values = session.metric_values(&["Defib".to_owned(), "Epinephrine".to_owned()]);
+        assert_eq!(values[3], ("erroneousActionCount".to_owned(), Some(1.0)));
+        assert_eq!(values[7], ("timeTo:Defib".to_owned(), Some(130.0)));
+        assert_eq!(values[8], ("timeTo:Epinephrine".to_owned(), None));
+    }
+
+    #[test]
+    fn quarters_and_trends() {
+        let sessions = vec![
+            session("01102025", "2025-01-10", 1, Some(60)),
+            session("06102024", "2024-06-10", 4, Some(120)),
+            session("09182024", "2024-09-18", 3, None),
+        ];
+        let report = CohortReport::new(sessions, Vec::new(), &["Defib".to_owned()]);
+        assert_eq!(report.sessions.iter().map(|session| session.session.as_str()).collect::<Vec<_>>(), vec!["06102024", "09182024", "01102025"]);
+        assert_eq!(report.quarters.iter().map(|quarter| (quarter.quarter.as_str(), quarter.session_count)).collect::<Vec<_>>(),
+                   vec![("2024-Q2", 1), ("2024-Q3", 1), ("2025-Q1", 1)]);
+        assert_eq!(value(&report.quarters[1].means, "erroneousActionCount"), &Some(3.0));
+        assert_eq!(value(&report.quarters[1].means, "timeTo:Defib"), &None);
+
+        let erroneous = report.trends.iter().find(|trend| trend.metric == "erroneousActionCount").unwrap();
+        assert_eq!((erroneous.session_count, erroneous.mean), (3, Some(8.0 / 3.0)));
+        assert!(erroneous.change_per_quarter.unwrap() < 0.0);
+        let defib = report.trends.iter().find(|trend| trend.metric == "timeTo:Defib").unwrap();
+        assert_eq!(defib.session_count, 2);
+        // 60 seconds faster over 214 days
+        assert!((defib.change_per_quarter.unwrap() + 60.0 / 214.0 * DAYS_PER_QUARTER).abs() < 1e-9);
+    }
+
+    #[test]
+    fn trend_needs_two_days() {
+        assert_eq!(change_per_quarter(&[(1.0, 2.0)]), None);
+        assert_eq!(change_per_quarter(&[(1.0, 2.0), (1.0, 4.0)]), None);
+        assert_eq!(change_per_quarter(&[(0.0, 0.0), (DAYS_PER_QUARTER, 2.0)]), Some(2.0));
+    }
+
+    #[test]
+    fn csv_rows() {
+        let mut sessions = vec![session("06102024", "2024-06-10", 2, Some(120)), session("09182024", "2024-09-18", 0, None)];
+        sessions[1].erroneous_actions_by_group.clear();
+        sessions[1].missed_actions_by_stage.insert("VF".to_owned(), 2);
+        sessions[1].scenario_name = Some("Cardiac Arrest, Adult".to_owned());
+        let csv = CohortReport::new(sessions, Vec::new(), &["Defib".to_owned()]).to_csv().unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "session,date,quarter,scenarioName,durationSeconds,stageCount,actionCount,erroneousActionCount,missedActionCount,cprPeriodCount,cprDurationSeconds,timeTo:Defib,erroneous:Defibrillation,missed:VF");
+        assert_eq!(lines[1], "06102024,2024-06-10,2024-Q2,,0,0,0,2,0,0,0,120,2,0");
+        assert_eq!(lines[2], "09182024,2024-09-18,2024-Q3,\"Cardiac Arrest, Adult\",0,0,0,0,0,0,0,,0,2");
+    }
+}
@@ -0,0 +1,294 @@
+use crate::session_report::{format_duration, SessionReport};
+use mteam_dashboard_plotly_processor::actions::renderer::{icon_data_uri, render_actions_svg, RenderOptions};
+use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::path::Path;
+
+const PLOTLY_SCRIPT_URL: &str = "https://cdn.plot.ly/plotly-2.35.2.min.js";
+
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>
+body { font-family: Arial, sans-serif; color: #333; margin: 2em auto; max-width: 1400px; padding: 0 1em; }
+h1 { margin-bottom: 0.2em; }
+.subtitle { color: #777; margin-top: 0; }
+.metrics { display: flex; flex-wrap: wrap; gap: 1em; }
+.metric { border: 1px solid #ddd; border-radius: 4px; padding: 0.6em 1em; min-width: 9em; }
+.metric .value { font-size: 1.6em; font-weight: bold; }
+.metric .label { color: #777; font-size: 0.85em; }
+table { border-collapse: collapse; width: 100%; margin: 0.5em 0 1.5em; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.4em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f5f5f5; }
+.erroneous { color: #c0392b; }
+.missed { color: #e67e22; }
+.chart { width: 100%; min-height: 300px; }
+.fallback svg { width: 100%; height: auto; }
+</style>
+<script src="{{plotly_script_url}}"></script>
+</head>
+<body>
+<h1>{{title}}</h1>
+<p class="subtitle">{{subtitle}}</p>
+
+<h2>Metrics</h2>
+<div class="metrics">
+{{metrics}}
+</div>
+
+<h2>Clinical Review Timeline</h2>
+<div id="timeline" class="chart"><div class="fallback">{{timeline_svg}}</div></div>
+
+<h2>Stages</h2>
+{{stages}}
+
+<h2>Missed and Erroneous Actions</h2>
+{{issues}}
+
+<h2>Cognitive Load</h2>
+{{cognitive_load}}
+<div id="cognitive-load" class="chart"></div>
+
+<h2>Visual Attention</h2>
+{{visual_attention}}
+
+<script id="report-data" type="application/json">{{report_data}}</script>
+<script>
+(function () {
+    if (!window.Plotly) {
+        return; // offline, the timeline falls back to the embedded svg
+    }
+    var report = JSON.parse(document.getElementById('report-data').textContent);
+    var timeline = document.getElementById('timeline');
+    timeline.innerHTML = '';
+    Plotly.newPlot(timeline, report.timeline.data, report.timeline.layout, {responsive: true});
+    if (report.cognitiveLoad.length > 0) {
+        Plotly.newPlot('cognitive-load', report.cognitiveLoad, {
+            xaxis: {title: 'Time', tickformat: '%H:%M:%S'},
+            yaxis: {title: 'Cognitive Load'},
+            margin: {t: 20}
+        }, {responsive: true});
+    }
+    report.visualAttention.forEach(function (member, index) {
+        Plotly.newPlot('visual-attention-' + index, member.data, {
+            title: member.name,
+            barmode: 'stack',
+            xaxis: {title: 'Time', tickformat: '%H:%M:%S'},
+            yaxis: {title: 'Ratio', range: [0, 1]},
+            margin: {t: 40}
+        }, {responsive: true});
+    });
+})();
+</script>
+</body>
+</html>
+"#;
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map(|value| format!("{:.2}", value)).unwrap_or_else(|| "-".to_owned())
+}
+
+fn table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut html = String::from("<table>\n<tr>");
+    headers.iter().for_each(|header| { let _ = write!(html, "<th>{}</th>", escape_html(header)); });
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        row.iter().for_each(|cell| { let _ = write!(html, "<td>{}</td>", cell); });
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Plotly traces and layouts the page script plots, with the action group icons embedded as data URIs so that the
+/// downloaded document does not depend on the dashboard.
+fn report_data(report: &SessionReport, icon_dir: Option<&Path>) -> Value {
+    let mut timeline = serde_json::to_value(&report.timeline).unwrap_or(Value::Null);
+    if let Some(images) = timeline.pointer_mut("/layout/images").and_then(Value::as_array_mut) {
+        for image in images {
+            let data_uri = icon_dir.zip(image["source"].as_str()).and_then(|(icon_dir, source)| icon_data_uri(source, icon_dir));
+            match data_uri {
+                Some(data_uri) => image["source"] = json!(data_uri),
+                None => image["visible"] = json!(false),
+            }
+        }
+    }
+    let cognitive_load: Vec<Value> = report.cognitive_load.iter()
+        .map(|member| json!({
+            "x": member.samples.iter().map(|(seconds, _)| seconds_to_csv_row_time(*seconds as u32).date_string).collect::<Vec<_>>(),
+            "y": member.samples.iter().map(|(_, load)| *load).collect::<Vec<_>>(),
+            "name": member.name,
+            "mode": "lines",
+            "type": "scatter",
+        }))
+        .collect();
+    let visual_attention: Vec<Value> = report.visual_attention.iter()
+        .map(|member| json!({"name": member.name, "data": member.plot_data.data}))
+        .collect();
+    json!({"timeline": timeline, "cognitiveLoad": cognitive_load, "visualAttention": visual_attention})
+}
+
+pub(crate) fn report_title(report: &SessionReport) -> String {
+    format!("Debrief Report: {}", report.scenario_name.as_ref().unwrap_or(&report.session_name))
+}
+
+/// Fills the `{{name}}` placeholders of the template in a single pass, placeholders in the inserted values are kept
+/// as they are. Unknown placeholders are left in place.
+fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut html = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        html.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        let value = placeholder[2..].find("}}").and_then(|end| {
+            let name = &placeholder[2..2 + end];
+            values.iter().find(|(key, _)| *key == name).map(|(_, value)| (*value, end + 4))
+        });
+        match value {
+            Some((value, length)) => {
+                html.push_str(value);
+                rest = &placeholder[length..];
+            }
+            None => {
+                html.push_str("{{");
+                rest = &placeholder[2..];
+            }
+        }
+    }
+    html.push_str(rest);
+    html
+}
+
+/// Standalone HTML debrief document. The charts are drawn by Plotly.js from the embedded plot data, the timeline
+/// falls back to a static SVG when Plotly.js can not be loaded.
+pub fn render_html(report: &SessionReport, icon_dir: Option<&Path>) -> String {
+    let metrics = &report.metrics;
+    let metric_cards: String = [
+        (format_duration(metrics.duration_secs), "Duration"),
+        (metrics.stage_count.to_string(), "Stages"),
+        (metrics.action_count.to_string(), "Performed Actions"),
+        (metrics.erroneous_action_count.to_string(), "Erroneous Actions"),
+        (metrics.missed_action_count.to_string(), "Missed Actions"),
+        (format!("{} ({})", metrics.cpr_period_count, format_duration(metrics.cpr_duration_secs)), "CPR Periods"),
+    ]
+        .iter()
+        .map(|(value, label)| format!("<div class=\"metric\"><div class=\"value\">{}</div><div class=\"label\">{}</div></div>\n", escape_html(value), label))
+        .collect();
+
+    let stages = table(
+        &["Stage", "Start", "End", "Actions", "Erroneous", "Missed"],
+        report.stages.iter()
+            .map(|stage| vec![
+                escape_html(&stage.name),
+                format_duration(stage.start_secs),
+                format_duration(stage.end_secs),
+                stage.action_count.to_string(),
+                stage.erroneous_action_count.to_string(),
+                stage.missed_action_count.to_string(),
+            ])
+            .collect(),
+    );
+    let issues = if report.issues.is_empty() {
+        "<p>No missed or erroneous actions.</p>".to_owned()
+    } else {
+        table(
+            &["Time", "Type", "Action", "Stage", "Violation", "Advice"],
+            report.issues.iter()
+                .map(|issue| vec![
+                    escape_html(&issue.time),
+                    format!("<span class=\"{}\">{}</span>", issue.kind.label().to_lowercase(), issue.kind.label()),
+                    escape_html(&issue.name),
+                    escape_html(&issue.stage),
+                    escape_html(&issue.violation),
+                    escape_html(&issue.advice),
+                ])
+                .collect(),
+        )
+    };
+    let cognitive_load = table(
+        &["Team Member", "Mean", "Peak"],
+        report.cognitive_load.iter()
+            .map(|member| vec![escape_html(&member.name), format_optional(member.mean), format_optional(member.peak)])
+            .collect(),
+    );
+    let mut visual_attention = String::new();
+    for (index, member) in report.visual_attention.iter().enumerate() {
+        let shares = member.shares.iter()
+            .map(|share| format!("{} {:.0}%", escape_html(&share.category), share.share * 100.0))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = writeln!(visual_attention, "<h3>{}</h3>\n<p>{}</p>\n<div id=\"visual-attention-{}\" class=\"chart\"></div>", escape_html(&member.name), shares, index);
+    }
+
+    let timeline_svg = render_actions_svg(&report.timeline, &RenderOptions { icon_dir: icon_dir.map(Path::to_path_buf), ..Default::default() });
+    // "</" would end the script element the data is embedded in
+    let report_data = report_data(report, icon_dir).to_string().replace("</", "<\\/");
+    let subtitle = format!("Session {} &middot; generated {}", escape_html(&report.session_name), escape_html(&report.generated_at));
+
+    fill_template(TEMPLATE, &[
+        ("title", &escape_html(&report_title(report))),
+        ("plotly_script_url", PLOTLY_SCRIPT_URL),
+        ("subtitle", &subtitle),
+        ("metrics", &metric_cards),
+        ("stages", &stages),
+        ("issues", &issues),
+        ("cognitive_load", &cognitive_load),
+        ("visual_attention", &visual_attention),
+        ("timeline_svg", &timeline_svg),
+        ("report_data", &report_data),
+    ])
+}
+
+#[cfg(test)]
+mod tests_html_report {
+    use super::*;
+    use crate::session_report::tests_session_report::report;
+
+    #[test]
+    fn contains_every_section() {
+        let html = render_html(&report(), None);
+        assert!(html.contains("<title>Debrief Report: Cardiac Arrest</title>"));
+        assert!(html.contains("<div class=\"value\">5:00</div>"));
+        assert!(html.contains("<td>Shock &lt;before&gt; epinephrine</td>"));
+        assert!(html.contains("<td>Team Lead</td><td>0.40</td><td>0.60</td>"));
+        assert!(html.contains("Patient 75%"));
+        assert!(html.contains("<div class=\"fallback\"><svg"));
+        assert!(!html.contains("{{"));
+    }
+
+    #[test]
+    fn placeholders_in_the_session_data_are_not_expanded() {
+        let mut report = report();
+        report.session_name = "{{report_data}}".to_owned();
+        report.issues[0].advice = "{{timeline_svg}}".to_owned();
+        let html = render_html(&report, None);
+        assert!(html.contains("Session {{report_data}} &middot;"));
+        assert!(html.contains("<td>{{timeline_svg}}</td>"));
+        assert_eq!(html.matches("<div class=\"fallback\"><svg").count(), 1);
+    }
+
+    #[test]
+    fn template_is_filled_in_a_single_pass() {
+        let html = fill_template("<p>{{a}}</p>{{b}}{{unknown}}", &[("a", "{{b}}"), ("b", "x")]);
+        assert_eq!(html, "<p>{{b}}</p>x{{unknown}}");
+    }
+
+    #[test]
+    fn plot_data_is_embedded() {
+        let html = render_html(&report(), None);
+        let start = html.find("application/json\">").unwrap() + "application/json\">".len();
+        let end = start + html[start..].find("</script>").unwrap();
+        let data: Value = serde_json::from_str(&html[start..end]).unwrap();
+        assert_eq!(data["cognitiveLoad"][0]["y"], json!([0.2, null, 0.6]));
+        assert_eq!(data["visualAttention"][0]["data"][0]["name"], "Patient");
+        assert!(data["timeline"]["layout"]["images"].as_array().unwrap().iter().all(|image| image["visible"] == false));
+    }
+}
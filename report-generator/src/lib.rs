@@ -0,0 +1,5 @@
+pub mod session_report;
+pub mod session_comparison;
+pub mod cohort;
+pub mod html;
+pub mod pdf;
@@ -0,0 +1,402 @@
+use crate::html::{escape_html, report_title};
+use crate::session_report::{format_duration, MemberCognitiveLoad, MemberVisualAttention, SessionReport};
+use mteam_dashboard_plotly_processor::actions::renderer::{font_database, render_actions_svg, RenderOptions};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref};
+use std::fmt::Write;
+use std::path::Path;
+use svg2pdf::usvg::{PostProcessingSteps, TreeParsing, TreePostProc};
+
+/// A4 landscape in points, the SVG pages use one pixel per point.
+const PAGE_WIDTH: f64 = 842.0;
+const PAGE_HEIGHT: f64 = 595.0;
+const MARGIN: f64 = 36.0;
+const CONTENT_WIDTH: f64 = PAGE_WIDTH - 2.0 * MARGIN;
+const BODY_FONT_SIZE: f64 = 9.0;
+const LINE_HEIGHT_RATIO: f64 = 1.35;
+/// Approximate width of a character relative to the font size, used to wrap the table cells.
+const CHAR_WIDTH_RATIO: f64 = 0.52;
+const TEXT_COLOR: &str = "#333333";
+const SERIES_COLORS: [&str; 8] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f"];
+
+fn page_svg(body: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{PAGE_WIDTH}\" height=\"{PAGE_HEIGHT}\" viewBox=\"0 0 {PAGE_WIDTH} {PAGE_HEIGHT}\" font-family=\"Arial, sans-serif\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n{}</svg>\n",
+        body
+    )
+}
+
+fn text(svg: &mut String, x: f64, y: f64, size: f64, weight: u16, color: &str, content: &str) {
+    let _ = writeln!(svg, r#"<text x="{x:.1}" y="{y:.1}" font-size="{size}" font-weight="{weight}" fill="{color}">{}</text>"#, escape_html(content));
+}
+
+/// Splits `text` into lines of at most `max_chars` characters at the spaces, longer words are cut.
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > max_chars {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..max_chars).collect());
+        }
+        let word: String = word.into_iter().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Lays out the report top to bottom, starting a new page when the next block does not fit.
+struct PageWriter {
+    pages: Vec<String>,
+    body: String,
+    y: f64,
+}
+
+impl PageWriter {
+    fn new() -> Self {
+        Self { pages: Vec::new(), body: String::new(), y: MARGIN }
+    }
+
+    fn break_page(&mut self) {
+        if !self.body.is_empty() {
+            self.pages.push(page_svg(&std::mem::take(&mut self.body)));
+        }
+        self.y = MARGIN;
+    }
+
+    fn ensure_space(&mut self, height: f64) {
+        if self.y + height > PAGE_HEIGHT - MARGIN {
+            self.break_page();
+        }
+    }
+
+    fn push_page(&mut self, svg: String) {
+        self.break_page();
+        self.pages.push(svg);
+    }
+
+    fn heading(&mut self, content: &str, size: f64) {
+        // keep the heading on the page of the block that follows it
+        self.ensure_space(size * LINE_HEIGHT_RATIO + 60.0);
+        self.y += size * 0.5;
+        text(&mut self.body, MARGIN, self.y + size, size, 700, TEXT_COLOR, content);
+        self.y += size * LINE_HEIGHT_RATIO + 4.0;
+    }
+
+    fn paragraph(&mut self, content: &str, color: &str) {
+        let line_height = BODY_FONT_SIZE * LINE_HEIGHT_RATIO;
+        for line in wrap(content, (CONTENT_WIDTH / (BODY_FONT_SIZE * CHAR_WIDTH_RATIO)) as usize) {
+            self.ensure_space(line_height);
+            text(&mut self.body, MARGIN, self.y + BODY_FONT_SIZE, BODY_FONT_SIZE, 400, color, &line);
+            self.y += line_height;
+        }
+        self.y += 4.0;
+    }
+
+    /// Draws a block of `height` with `draw(svg, top)`.
+    fn block(&mut self, height: f64, draw: impl FnOnce(&mut String, f64)) {
+        self.ensure_space(height);
+        draw(&mut self.body, self.y);
+        self.y += height + 8.0;
+    }
+
+    fn wrap_cells(columns: &[(&str, f64)], cells: &[String]) -> Vec<Vec<String>> {
+        columns.iter().zip(cells)
+            .map(|((_, width), cell)| wrap(cell, ((width * CONTENT_WIDTH - 8.0) / (BODY_FONT_SIZE * CHAR_WIDTH_RATIO)) as usize))
+            .collect()
+    }
+
+    fn row_height(cells: &[Vec<String>]) -> f64 {
+        cells.iter().map(Vec::len).max().unwrap_or(1) as f64 * BODY_FONT_SIZE * LINE_HEIGHT_RATIO + 6.0
+    }
+
+    fn table_row(&mut self, columns: &[(&str, f64)], cells: &[Vec<String>], header: bool) {
+        let row_height = Self::row_height(cells);
+        if header {
+            let _ = writeln!(self.body, r##"<rect x="{MARGIN}" y="{:.1}" width="{CONTENT_WIDTH}" height="{row_height:.1}" fill="#f0f0f0"/>"##, self.y);
+        }
+        let mut x = MARGIN;
+        for ((_, width), lines) in columns.iter().zip(cells) {
+            for (index, line) in lines.iter().enumerate() {
+                let y = self.y + 3.0 + BODY_FONT_SIZE + index as f64 * BODY_FONT_SIZE * LINE_HEIGHT_RATIO;
+                text(&mut self.body, x + 4.0, y, BODY_FONT_SIZE, if header { 700 } else { 400 }, TEXT_COLOR, line);
+            }
+            x += width * CONTENT_WIDTH;
+        }
+        self.y += row_height;
+        let _ = writeln!(self.body, r##"<line x1="{MARGIN}" y1="{0:.1}" x2="{1}" y2="{0:.1}" stroke="#dddddd"/>"##, self.y, MARGIN + CONTENT_WIDTH);
+    }
+
+    /// `columns` are header and width as a fraction of the content width, the header is repeated on every page.
+    fn table(&mut self, columns: &[(&str, f64)], rows: Vec<Vec<String>>) {
+        let headers: Vec<String> = columns.iter().map(|(header, _)| header.to_string()).collect();
+        let headers = Self::wrap_cells(columns, &headers);
+        self.ensure_space(3.0 * BODY_FONT_SIZE * LINE_HEIGHT_RATIO);
+        self.table_row(columns, &headers, true);
+        for row in rows {
+            let cells = Self::wrap_cells(columns, &row);
+            if self.y + Self::row_height(&cells) > PAGE_HEIGHT - MARGIN {
+                self.break_page();
+                self.table_row(columns, &headers, true);
+            }
+            self.table_row(columns, &cells, false);
+        }
+        self.y += 10.0;
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        self.break_page();
+        self.pages
+    }
+}
+
+fn draw_legend(svg: &mut String, x: f64, top: f64, entries: &[(String, String)]) {
+    for (index, (name, color)) in entries.iter().enumerate() {
+        let y = top + index as f64 * 14.0;
+        let _ = writeln!(svg, r#"<rect x="{x:.1}" y="{y:.1}" width="10" height="10" fill="{}"/>"#, escape_html(color));
+        text(svg, x + 14.0, y + 9.0, BODY_FONT_SIZE, 400, TEXT_COLOR, name);
+    }
+}
+
+/// Line chart of every member's cognitive load over the session time.
+fn draw_cognitive_load_chart(svg: &mut String, top: f64, height: f64, members: &[MemberCognitiveLoad]) {
+    let (left, width) = (MARGIN + 40.0, CONTENT_WIDTH - 40.0 - 150.0);
+    let plot_height = height - 24.0;
+    let x_max = members.iter().flat_map(|member| member.samples.last().map(|(seconds, _)| *seconds)).fold(1.0, f64::max);
+    let y_max = members.iter().filter_map(|member| member.peak).fold(0.0, f64::max).max(f64::EPSILON) * 1.1;
+    let x = |seconds: f64| left + seconds / x_max * width;
+    let y = |load: f64| top + plot_height - load / y_max * plot_height;
+
+    let _ = writeln!(svg, r##"<polyline points="{left},{top} {left},{0} {1},{0}" fill="none" stroke="#999999"/>"##, top + plot_height, left + width);
+    for tick in 0..=4 {
+        let load = y_max * tick as f64 / 4.0;
+        text(svg, MARGIN, y(load) + 3.0, BODY_FONT_SIZE - 1.0, 400, TEXT_COLOR, &format!("{:.2}", load));
+    }
+    for tick in 0..=6 {
+        let seconds = x_max * tick as f64 / 6.0;
+        text(svg, x(seconds) - 10.0, top + plot_height + 14.0, BODY_FONT_SIZE - 1.0, 400, TEXT_COLOR, &format_duration(seconds as u32));
+    }
+    let mut legend = Vec::new();
+    for (index, member) in members.iter().enumerate() {
+        let color = SERIES_COLORS[index % SERIES_COLORS.len()];
+        // missing loads break the line
+        for segment in member.samples.split(|(_, load)| load.is_none()).filter(|segment| !segment.is_empty()) {
+            let points: Vec<String> = segment.iter()
+                .filter_map(|(seconds, load)| load.map(|load| format!("{:.1},{:.1}", x(*seconds), y(load))))
+                .collect();
+            let _ = writeln!(svg, r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.2"/>"#, points.join(" "), color);
+        }
+        legend.push((member.name.clone(), color.to_owned()));
+    }
+    draw_legend(svg, left + width + 16.0, top, &legend);
+}
+
+/// One bar per member split by the share of each visual attention category.
+fn draw_visual_attention_shares(svg: &mut String, top: f64, members: &[MemberVisualAttention]) {
+    let (left, width, bar_height) = (MARGIN + 130.0, CONTENT_WIDTH - 130.0 - 150.0, 16.0);
+    let mut legend: Vec<(String, String)> = Vec::new();
+    for (index, member) in members.iter().enumerate() {
+        let y = top + index as f64 * (bar_height + 8.0);
+        text(svg, MARGIN, y + 12.0, BODY_FONT_SIZE, 400, TEXT_COLOR, &member.name);
+        let mut x = left;
+        for share in &member.shares {
+            let share_width = share.share * width;
+            let _ = writeln!(svg, r#"<rect x="{x:.1}" y="{y:.1}" width="{share_width:.1}" height="{bar_height}" fill="{}"/>"#, escape_html(&share.color));
+            if share_width > 28.0 {
+                text(svg, x + 3.0, y + 12.0, BODY_FONT_SIZE - 1.0, 400, "white", &format!("{:.0}%", share.share * 100.0));
+            }
+            x += share_width;
+            if !legend.iter().any(|(category, _)| *category == share.category) {
+                legend.push((share.category.clone(), share.color.clone()));
+            }
+        }
+    }
+    draw_legend(svg, left + width + 16.0, top, &legend);
+}
+
+fn visual_attention_height(report: &SessionReport) -> f64 {
+    let legend_count = report.visual_attention.iter()
+        .flat_map(|member| member.shares.iter().map(|share| &share.category))
+        .collect::<std::collections::BTreeSet<_>>()
+        .len();
+    (report.visual_attention.len() as f64 * 24.0).max(legend_count as f64 * 14.0)
+}
+
+/// SVG of every page of the PDF debrief document.
+pub fn render_pages(report: &SessionReport, icon_dir: Option<&Path>) -> Vec<String> {
+    let mut writer = PageWriter::new();
+    writer.heading(&report_title(report), 18.0);
+    writer.paragraph(&format!("Session {} · generated {}", report.session_name, report.generated_at), "#777777");
+
+    let metrics = &report.metrics;
+    writer.heading("Metrics", 13.0);
+    writer.table(
+        &[("Duration", 0.16), ("Stages", 0.14), ("Performed Actions", 0.18), ("Erroneous Actions", 0.18), ("Missed Actions", 0.16), ("CPR Periods", 0.18)],
+        vec![vec![
+            format_duration(metrics.duration_secs),
+            metrics.stage_count.to_string(),
+            metrics.action_count.to_string(),
+            metrics.erroneous_action_count.to_string(),
+            metrics.missed_action_count.to_string(),
+            format!("{} ({})", metrics.cpr_period_count, format_duration(metrics.cpr_duration_secs)),
+        ]],
+    );
+
+    writer.heading("Stages", 13.0);
+    writer.table(
+        &[("Stage", 0.4), ("Start", 0.12), ("End", 0.12), ("Actions", 0.12), ("Erroneous", 0.12), ("Missed", 0.12)],
+        report.stages.iter()
+            .map(|stage| vec![
+                stage.name.clone(),
+                format_duration(stage.start_secs),
+                format_duration(stage.end_secs),
+                stage.action_count.to_string(),
+                stage.erroneous_action_count.to_string(),
+                stage.missed_action_count.to_string(),
+            ])
+            .collect(),
+    );
+
+    let timeline_options = RenderOptions {
+        width: PAGE_WIDTH as u32,
+        height: PAGE_HEIGHT as u32,
+        scale: 1.0,
+        icon_dir: icon_dir.map(Path::to_path_buf),
+    };
+    writer.push_page(render_actions_svg(&report.timeline, &timeline_options));
+
+    writer.heading("Missed and Erroneous Actions", 13.0);
+    if report.issues.is_empty() {
+        writer.paragraph("No missed or erroneous actions.", TEXT_COLOR);
+    } else {
+        writer.table(
+            &[("Time", 0.08), ("Type", 0.09), ("Action", 0.2), ("Stage", 0.15), ("Violation", 0.18), ("Advice", 0.3)],
+            report.issues.iter()
+                .map(|issue| vec![
+                    issue.time.clone(),
+                    issue.kind.label().to_owned(),
+                    issue.name.clone(),
+                    issue.stage.clone(),
+                    issue.violation.clone(),
+                    issue.advice.clone(),
+                ])
+                .collect(),
+        );
+    }
+
+    if !report.cognitive_load.is_empty() {
+        writer.heading("Cognitive Load", 13.0);
+        writer.block(220.0, |svg, top| draw_cognitive_load_chart(svg, top, 220.0, &report.cognitive_load));
+        writer.table(
+            &[("Team Member", 0.5), ("Mean", 0.25), ("Peak", 0.25)],
+            report.cognitive_load.iter()
+                .map(|member| vec![
+                    member.name.clone(),
+                    member.mean.map(|mean| format!("{:.2}", mean)).unwrap_or_else(|| "-".to_owned()),
+                    member.peak.map(|peak| format!("{:.2}", peak)).unwrap_or_else(|| "-".to_owned()),
+                ])
+                .collect(),
+        );
+    }
+
+    if !report.visual_attention.is_empty() {
+        writer.heading("Visual Attention", 13.0);
+        writer.block(visual_attention_height(report), |svg, top| draw_visual_attention_shares(svg, top, &report.visual_attention));
+    }
+    writer.finish()
+}
+
+/// Converts SVG pages to a PDF with one page per SVG, text is converted to paths with the system fonts.
+pub fn svg_pages_to_pdf(pages: &[String]) -> Result<Vec<u8>, String> {
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let mut next_id = Ref::new(3);
+    let mut pdf = Pdf::new();
+    let mut page_ids = Vec::with_capacity(pages.len());
+    let svg_name = Name(b"S1");
+
+    for (index, page) in pages.iter().enumerate() {
+        let mut tree = svg2pdf::usvg::Tree::from_str(page, &svg2pdf::usvg::Options::default())
+            .map_err(|e| format!("Error parsing page {}: {}", index + 1, e))?;
+        tree.postprocess(PostProcessingSteps::default(), font_database());
+        let (width, height) = (tree.size.width(), tree.size.height());
+
+        let page_id = next_id.bump();
+        let content_id = next_id.bump();
+        let svg_id = next_id.bump();
+        next_id = svg2pdf::convert_tree_into(&tree, svg2pdf::Options::default(), &mut pdf, svg_id);
+
+        let mut pdf_page = pdf.page(page_id);
+        pdf_page.media_box(Rect::new(0.0, 0.0, width, height));
+        pdf_page.parent(page_tree_id);
+        pdf_page.contents(content_id);
+        pdf_page.resources().x_objects().pair(svg_name, svg_id);
+        pdf_page.finish();
+
+        let mut content = Content::new();
+        content.transform([width, 0.0, 0.0, height, 0.0, 0.0]).x_object(svg_name);
+        pdf.stream(content_id, &content.finish());
+        page_ids.push(page_id);
+    }
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).count(page_ids.len() as i32).kids(page_ids);
+    Ok(pdf.finish())
+}
+
+/// PDF debrief document with the same content as the HTML one, the charts are drawn statically.
+pub fn render_pdf(report: &SessionReport, icon_dir: Option<&Path>) -> Result<Vec<u8>, String> {
+    svg_pages_to_pdf(&render_pages(report, icon_dir))
+}
+
+#[cfg(test)]
+mod tests_pdf_report {
+    use super::*;
+    use crate::session_report::tests_session_report::report;
+
+    #[test]
+    fn wrap_lines() {
+        assert_eq!(wrap("Give 1mg epinephrine every 3 minutes", 12), vec!["Give 1mg", "epinephrine", "every 3", "minutes"]);
+        assert_eq!(wrap("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(wrap("", 3), vec![""]);
+    }
+
+    #[test]
+    fn long_tables_continue_on_new_pages() {
+        let mut writer = PageWriter::new();
+        writer.table(&[("Name", 1.0)], (0..100).map(|index| vec![format!("Row {}", index)]).collect());
+        let pages = writer.finish();
+        assert!(pages.len() > 1);
+        assert!(pages.iter().all(|page| page.contains(">Name</text>")));
+    }
+
+    #[test]
+    fn pages() {
+        let pages = render_pages(&report(), None);
+        assert_eq!(pages.len(), 3);
+        assert!(pages[0].contains(">Debrief Report: Cardiac Arrest</text>"));
+        assert!(pages[1].contains(">Clinical Review Timeline</text>"));
+        assert!(pages[2].contains(">Shock &lt;before&gt; epinephrine</text>"));
+        assert!(pages[2].contains(">75%</text>"));
+    }
+
+    #[test]
+    fn pdf() {
+        let pdf = render_pdf(&report(), None).unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+        let pdf = String::from_utf8_lossy(&pdf);
+        assert!(pdf.contains("/Count 3"));
+    }
+}
@@ -0,0 +1,299 @@
+use crate::session_report::{summarize_actions, SessionMetrics, StageSummary};
+use mteam_dashboard_action_processor::plot_structures::ActionPlotPoint;
+use mteam_dashboard_plotly_processor::comparison::plot_data::ComparisonPlotData;
+use mteam_dashboard_plotly_processor::comparison::transformers::{to_comparison_plotly_data, ComparedSession};
+use mteam_dashboard_plotly_processor::config::layout_settings::Theme;
+use mteam_dashboard_plotly_processor::config::plotly_mappings::PlotlyConfig;
+use mteam_dashboard_utils::stage_alignment::{stage_spans, StageAlignment, StageSpan};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Data of a session to compare, read and time aligned by the caller.
+pub struct ComparisonInput {
+    pub session_name: String,
+    pub scenario_name: Option<String>,
+    pub action_points: Vec<ActionPlotPoint>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SessionOverview {
+    pub name: String,
+    #[serde(rename = "scenarioName")]
+    pub scenario_name: Option<String>,
+    pub metrics: SessionMetrics,
+    pub stages: Vec<StageSummary>,
+    /// Stages without a matching stage in the baseline session, their actions are not compared.
+    #[serde(rename = "unmatchedStages")]
+    pub unmatched_stages: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CountChange {
+    pub baseline: usize,
+    pub session: usize,
+    pub delta: i64,
+}
+
+impl CountChange {
+    fn new(baseline: usize, session: usize) -> Self {
+        Self { baseline, session, delta: session as i64 - baseline as i64 }
+    }
+}
+
+/// First time an action is performed in the baseline and in the compared session.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ActionTimingDelta {
+    pub action: String,
+    pub stage: String,
+    #[serde(rename = "baselineSeconds")]
+    pub baseline_secs: u32,
+    #[serde(rename = "sessionSeconds")]
+    pub session_secs: u32,
+    /// Time of the session mapped onto the stages of the baseline.
+    #[serde(rename = "alignedSeconds")]
+    pub aligned_secs: f64,
+    /// `alignedSeconds - baselineSeconds`, positive when the action is later in its stage than in the baseline.
+    #[serde(rename = "deltaSeconds")]
+    pub delta_secs: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StageCountChanges {
+    pub stage: String,
+    #[serde(rename = "erroneousActions")]
+    pub erroneous_actions: CountChange,
+    #[serde(rename = "missedActions")]
+    pub missed_actions: CountChange,
+}
+
+/// Differences of a session to the baseline session.
+#[derive(Serialize, Debug)]
+pub struct SessionDiff {
+    pub session: String,
+    /// Actions performed in the baseline but not in the session.
+    #[serde(rename = "onlyInBaseline")]
+    pub only_in_baseline: Vec<String>,
+    #[serde(rename = "onlyInSession")]
+    pub only_in_session: Vec<String>,
+    #[serde(rename = "timingDeltas")]
+    pub timing_deltas: Vec<ActionTimingDelta>,
+    #[serde(rename = "erroneousActions")]
+    pub erroneous_actions: CountChange,
+    #[serde(rename = "missedActions")]
+    pub missed_actions: CountChange,
+    /// Changes per stage of the baseline that the session has too.
+    pub stages: Vec<StageCountChanges>,
+}
+
+/// Sessions of the same scenario compared to the first (baseline) one, see `SessionComparison::new`.
+#[derive(Serialize, Debug)]
+pub struct SessionComparison {
+    pub baseline: String,
+    pub sessions: Vec<SessionOverview>,
+    pub diffs: Vec<SessionDiff>,
+    pub figure: ComparisonPlotData,
+    pub warnings: Vec<String>,
+}
+
+/// Performed (including erroneous) actions with the time they are first performed, in time order.
+fn first_performed_actions(points: &[ActionPlotPoint]) -> Vec<(String, u32)> {
+    let mut actions: Vec<(String, u32)> = Vec::new();
+    for point in points {
+        let (name, seconds) = match point {
+            ActionPlotPoint::Action(action) => (&action.name, action.location.timestamp.total_seconds),
+            ActionPlotPoint::Error(action) => (&action.name, action.location.timestamp.total_seconds),
+            _ => continue,
+        };
+        match actions.iter_mut().find(|(known, _)| known == name) {
+            Some(first) => first.1 = first.1.min(seconds),
+            None => actions.push((name.clone(), seconds)),
+        }
+    }
+    actions.sort_by_key(|(_, seconds)| *seconds);
+    actions
+}
+
+fn stage_at(stages: &[StageSpan], seconds: u32) -> Option<&StageSpan> {
+    stages.iter().find(|stage| stage.start_secs <= seconds && seconds <= stage.end_secs)
+}
+
+/// Summaries of stages repeated in a session are matched in order, like the stage spans.
+fn matching_stage_summary<'a>(stages: &'a [StageSummary], name: &str, occurrence: usize) -> Option<&'a StageSummary> {
+    stages.iter().filter(|stage| stage.name == name).nth(occurrence)
+}
+
+impl SessionComparison {
+    /// Aligns the sessions by the stages of the first one (stage boundaries rather than absolute time) and compares
+    /// every other session to it. `key_actions` limits the timing deltas to these actions, all actions performed in
+    /// both sessions are compared when empty.
+    pub fn new(inputs: Vec<ComparisonInput>, key_actions: &[String], plotly_config: &PlotlyConfig, theme: &Theme) -> Result<Self, String> {
+        if inputs.len() < 2 {
+            return Err(format!("At least two sessions are needed for a comparison, got {}", inputs.len()));
+        }
+        let baseline = &inputs[0];
+        let reference_stages = stage_spans(&baseline.action_points);
+        let alignments: Vec<StageAlignment> = inputs.iter()
+            .map(|input| StageAlignment::new(&stage_spans(&input.action_points), &reference_stages))
+            .collect();
+
+        let mut warnings = Vec::new();
+        let scenario_names: BTreeSet<&String> = inputs.iter().filter_map(|input| input.scenario_name.as_ref()).collect();
+        if scenario_names.len() > 1 {
+            warnings.push(format!("The sessions are of different scenarios: {}", scenario_names.into_iter().cloned().collect::<Vec<_>>().join(", ")));
+        }
+
+        let sessions: Vec<SessionOverview> = inputs.iter().zip(&alignments)
+            .map(|(input, alignment)| {
+                let (metrics, stages, _) = summarize_actions(&input.action_points, plotly_config);
+                let unmatched_stages: Vec<String> = alignment.unmatched.iter().map(|stage| plotly_config.stages.display_name(&stage.name)).collect();
+                if !unmatched_stages.is_empty() {
+                    warnings.push(format!("Stages of {} not in {}: {}", input.session_name, baseline.session_name, unmatched_stages.join(", ")));
+                }
+                SessionOverview { name: input.session_name.clone(), scenario_name: input.scenario_name.clone(), metrics, stages, unmatched_stages }
+            })
+            .collect();
+
+        let baseline_actions = first_performed_actions(&baseline.action_points);
+        let diffs = inputs.iter().zip(&alignments).zip(&sessions).skip(1)
+            .map(|((input, alignment), overview)| {
+                let session_actions = first_performed_actions(&input.action_points);
+                let baseline_names: BTreeSet<&String> = baseline_actions.iter().map(|(name, _)| name).collect();
+                let session_names: BTreeSet<&String> = session_actions.iter().map(|(name, _)| name).collect();
+
+                let timing_deltas = baseline_actions.iter()
+                    .filter(|(name, _)| key_actions.is_empty() || key_actions.contains(name))
+                    .filter_map(|(name, baseline_secs)| {
+                        let (_, session_secs) = session_actions.iter().find(|(session_name, _)| session_name == name)?;
+                        let aligned_secs = alignment.align(*session_secs as f64)?;
+                        let stage = stage_at(&reference_stages, *baseline_secs).map(|stage| plotly_config.stages.display_name(&stage.name)).unwrap_or_default();
+                        Some(ActionTimingDelta {
+                            action: name.clone(),
+                            stage,
+                            baseline_secs: *baseline_secs,
+                            session_secs: *session_secs,
+                            aligned_secs,
+                            delta_secs: aligned_secs - *baseline_secs as f64,
+                        })
+                    })
+                    .collect();
+
+                let stages = reference_stages.iter()
+                    .filter_map(|stage| {
+                        let name = plotly_config.stages.display_name(&stage.name);
+                        let baseline_stage = matching_stage_summary(&sessions[0].stages, &name, stage.occurrence)?;
+                        let session_stage = matching_stage_summary(&overview.stages, &name, stage.occurrence)?;
+                        Some(StageCountChanges {
+                            erroneous_actions: CountChange::new(baseline_stage.erroneous_action_count, session_stage.erroneous_action_count),
+                            missed_actions: CountChange::new(baseline_stage.missed_action_count, session_stage.missed_action_count),
+                            stage: name,
+                        })
+                    })
+                    .collect();
+
+                SessionDiff {
+                    session: input.session_name.clone(),
+                    only_in_baseline: baseline_names.difference(&session_names).map(|name| name.to_string()).collect(),
+                    only_in_session: session_names.difference(&baseline_names).map(|name| name.to_string()).collect(),
+                    timing_deltas,
+                    erroneous_actions: CountChange::new(sessions[0].metrics.erroneous_action_count, overview.metrics.erroneous_action_count),
+                    missed_actions: CountChange::new(sessions[0].metrics.missed_action_count, overview.metrics.missed_action_count),
+                    stages,
+                }
+            })
+            .collect();
+
+        let compared_sessions: Vec<ComparedSession> = inputs.iter().zip(&alignments)
+            .map(|(input, alignment)| ComparedSession { name: &input.session_name, action_points: &input.action_points, alignment })
+            .collect();
+        let figure = to_comparison_plotly_data(&compared_sessions, &reference_stages, plotly_config, theme);
+
+        Ok(Self { baseline: baseline.session_name.clone(), sessions, diffs, figure, warnings })
+    }
+}
+
+#[cfg(test)]
+mod tests_session_comparison {
+    use super::*;
+    use crate::session_report::tests_session_report::{action_points, plotly_config};
+    use mteam_dashboard_action_processor::plot_structures::{Action, MissedAction, ErrorInfo, PeriodType, PlotLocation};
+    use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+
+    fn location(seconds: u32, stage: &str) -> PlotLocation {
+        PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (1, stage.to_owned()) }
+    }
+
+    fn action(seconds: u32, stage: &str, name: &str) -> ActionPlotPoint {
+        ActionPlotPoint::Action(Action { location: location(seconds, stage), name: name.to_owned(), action_category: String::new(), shock_value: String::new() })
+    }
+
+    /// Same scenario as `action_points` with a stage A twice as long, a pulse check later in its stage, a defib
+    /// without an error, an extra action and no missed action.
+    fn second_session() -> Vec<ActionPlotPoint> {
+        vec![
+            ActionPlotPoint::Period(PeriodType::Stage, location(0, "Stage A"), location(240, "Stage A")),
+            ActionPlotPoint::Period(PeriodType::Stage, location(240, "Stage B"), location(420, "Stage B")),
+            ActionPlotPoint::Period(PeriodType::Stage, location(420, "Stage C"), location(480, "Stage C")),
+            action(60, "Stage A", "Check Pulse"),
+            action(250, "Stage B", "Defib"),
+            action(300, "Stage B", "Airway"),
+            action(450, "Stage C", "Epinephrine"),
+        ]
+    }
+
+    fn inputs() -> Vec<ComparisonInput> {
+        vec![
+            ComparisonInput { session_name: "06102024".to_owned(), scenario_name: Some("Cardiac Arrest".to_owned()), action_points: action_points() },
+            ComparisonInput { session_name: "09182024".to_owned(), scenario_name: Some("Cardiac Arrest".to_owned()), action_points: second_session() },
+        ]
+    }
+
+    #[test]
+    fn diff_to_the_baseline() {
+        let plotly_config = plotly_config();
+        let comparison = SessionComparison::new(inputs(), &[], &plotly_config, plotly_config.layout_settings.default_theme()).unwrap();
+        assert_eq!(comparison.baseline, "06102024");
+        assert_eq!(comparison.sessions[1].unmatched_stages, vec!["Stage C"]);
+        assert_eq!(comparison.warnings, vec!["Stages of 09182024 not in 06102024: Stage C"]);
+
+        let diff = &comparison.diffs[0];
+        assert_eq!(diff.session, "09182024");
+        assert!(diff.only_in_baseline.is_empty());
+        assert_eq!(diff.only_in_session, vec!["Airway", "Epinephrine"]);
+        assert_eq!(diff.erroneous_actions, CountChange { baseline: 1, session: 0, delta: -1 });
+        assert_eq!(diff.missed_actions, CountChange { baseline: 1, session: 0, delta: -1 });
+
+        // 60 of 240 seconds into stage A is 30 seconds into the 120 seconds of the baseline stage A
+        assert_eq!(diff.timing_deltas, vec![
+            ActionTimingDelta { action: "Check Pulse".to_owned(), stage: "Stage A".to_owned(), baseline_secs: 10, session_secs: 60, aligned_secs: 30.0, delta_secs: 20.0 },
+            ActionTimingDelta { action: "Defib".to_owned(), stage: "Stage B".to_owned(), baseline_secs: 130, session_secs: 250, aligned_secs: 130.0, delta_secs: 0.0 },
+        ]);
+        assert_eq!(diff.stages.len(), 2);
+        assert_eq!(diff.stages[1].erroneous_actions.delta, -1);
+        assert_eq!(diff.stages[1].missed_actions, CountChange { baseline: 1, session: 0, delta: -1 });
+    }
+
+    #[test]
+    fn key_actions_and_scenarios() {
+        let plotly_config = plotly_config();
+        let mut inputs = inputs();
+        inputs[1].scenario_name = Some("Bradycardia".to_owned());
+        inputs[1].action_points.push(ActionPlotPoint::MissedAction(MissedAction {
+            location: location(100, "Stage A"),
+            name: "Epinephrine".to_owned(),
+            error_info: ErrorInfo { action_rule: String::new(), violation: String::new(), advice: String::new() },
+        }));
+        let comparison = SessionComparison::new(inputs, &["Defib".to_owned()], &plotly_config, plotly_config.layout_settings.default_theme()).unwrap();
+        assert_eq!(comparison.warnings[0], "The sessions are of different scenarios: Bradycardia, Cardiac Arrest");
+        let diff = &comparison.diffs[0];
+        assert_eq!(diff.timing_deltas.iter().map(|delta| delta.action.as_str()).collect::<Vec<_>>(), vec!["Defib"]);
+        assert_eq!(diff.missed_actions.delta, 0);
+    }
+
+    #[test]
+    fn needs_two_sessions() {
+        let plotly_config = plotly_config();
+        let mut inputs = inputs();
+        inputs.truncate(1);
+        assert!(SessionComparison::new(inputs, &[], &plotly_config, plotly_config.layout_settings.default_theme()).is_err());
+    }
+}
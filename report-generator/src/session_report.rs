@@ -0,0 +1,381 @@
+use mteam_dashboard_action_processor::plot_structures::{ActionPlotPoint, PeriodType, PlotLocation};
+use mteam_dashboard_cognitive_load_processor::file_processor::CognitiveLoadSample;
+use mteam_dashboard_plotly_processor::actions::plot_data::ActionsPlotData;
+use mteam_dashboard_plotly_processor::actions::transformers::to_plotly_data;
+use mteam_dashboard_plotly_processor::config::plotly_mappings::PlotlyConfig;
+use mteam_dashboard_plotly_processor::visual_attention::plot_data::VisualAttentionPlotData;
+use serde::{Deserialize, Serialize};
+
+/// Data of a session the report is generated from, read and time aligned by the caller.
+pub struct SessionReportInput {
+    pub session_name: String,
+    pub scenario_name: Option<String>,
+    /// Formatted date and time the report is generated at, printed in the header.
+    pub generated_at: String,
+    pub action_points: Vec<ActionPlotPoint>,
+    /// Team member name and samples, in the order the members should appear.
+    pub cognitive_load: Vec<(String, Vec<CognitiveLoadSample>)>,
+    /// Team member name and visual attention plot data, in the order the members should appear.
+    pub visual_attention: Vec<(String, VisualAttentionPlotData)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SessionMetrics {
+    /// End of the last stage.
+    #[serde(rename = "durationSeconds")]
+    pub duration_secs: u32,
+    #[serde(rename = "stageCount")]
+    pub stage_count: usize,
+    /// Performed actions including the erroneous ones.
+    #[serde(rename = "actionCount")]
+    pub action_count: usize,
+    #[serde(rename = "erroneousActionCount")]
+    pub erroneous_action_count: usize,
+    #[serde(rename = "missedActionCount")]
+    pub missed_action_count: usize,
+    #[serde(rename = "cprPeriodCount")]
+    pub cpr_period_count: usize,
+    #[serde(rename = "cprDurationSeconds")]
+    pub cpr_duration_secs: u32,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StageSummary {
+    pub name: String,
+    #[serde(rename = "startSeconds")]
+    pub start_secs: u32,
+    #[serde(rename = "endSeconds")]
+    pub end_secs: u32,
+    #[serde(rename = "actionCount")]
+    pub action_count: usize,
+    #[serde(rename = "erroneousActionCount")]
+    pub erroneous_action_count: usize,
+    #[serde(rename = "missedActionCount")]
+    pub missed_action_count: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueKind {
+    Erroneous,
+    Missed,
+}
+
+impl IssueKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            IssueKind::Erroneous => "Erroneous",
+            IssueKind::Missed => "Missed",
+        }
+    }
+}
+
+/// Erroneous or missed action with the rule it violates and the advice text of the CSV.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ActionIssue {
+    pub kind: IssueKind,
+    /// `HH:MM:SS` time of the action in the session.
+    pub time: String,
+    pub stage: String,
+    pub name: String,
+    pub violation: String,
+    pub advice: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MemberCognitiveLoad {
+    pub name: String,
+    pub samples: Vec<(f64, Option<f64>)>,
+    pub mean: Option<f64>,
+    pub peak: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CategoryShare {
+    pub category: String,
+    pub color: String,
+    /// Average ratio of the category over the windows with data.
+    pub share: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MemberVisualAttention {
+    pub name: String,
+    #[serde(rename = "plotData")]
+    pub plot_data: VisualAttentionPlotData,
+    pub shares: Vec<CategoryShare>,
+}
+
+/// Everything the HTML and PDF debrief documents show, see `html::render_html` and `pdf::render_pdf`.
+#[derive(Serialize, Debug)]
+pub struct SessionReport {
+    #[serde(rename = "sessionName")]
+    pub session_name: String,
+    #[serde(rename = "scenarioName")]
+    pub scenario_name: Option<String>,
+    #[serde(rename = "generatedAt")]
+    pub generated_at: String,
+    pub metrics: SessionMetrics,
+    pub stages: Vec<StageSummary>,
+    pub issues: Vec<ActionIssue>,
+    pub timeline: ActionsPlotData,
+    #[serde(rename = "cognitiveLoad")]
+    pub cognitive_load: Vec<MemberCognitiveLoad>,
+    #[serde(rename = "visualAttention")]
+    pub visual_attention: Vec<MemberVisualAttention>,
+}
+
+impl SessionReport {
+    pub fn new(input: SessionReportInput, plotly_config: &PlotlyConfig) -> Self {
+        let (metrics, stages, issues) = summarize_actions(&input.action_points, plotly_config);
+        let timeline = to_plotly_data(plotly_config, plotly_config.layout_settings.default_theme(), input.action_points.into_iter().map(Ok));
+        Self {
+            session_name: input.session_name,
+            scenario_name: input.scenario_name,
+            generated_at: input.generated_at,
+            metrics,
+            stages,
+            issues,
+            timeline,
+            cognitive_load: input.cognitive_load.into_iter().map(|(name, samples)| summarize_cognitive_load(name, &samples)).collect(),
+            visual_attention: input.visual_attention.into_iter().map(|(name, plot_data)| summarize_visual_attention(name, plot_data)).collect(),
+        }
+    }
+}
+
+fn stage_name(location: &PlotLocation, plotly_config: &PlotlyConfig) -> String {
+    plotly_config.stages.display_name(&location.stage.1)
+}
+
+/// Occurrence of the stage an action belongs to: among the stages of the same name, the one with the same stage number
+/// (`location.stage.0`), then the one containing the action time, then the first one. `stage_keys` are the stages as
+/// read from the CSV, in the order of `stages`.
+fn find_stage<'a>(stages: &'a mut [StageSummary], stage_keys: &[(u32, String)], location: &PlotLocation, plotly_config: &PlotlyConfig) -> Option<&'a mut StageSummary> {
+    let name = stage_name(location, plotly_config);
+    let same_name: Vec<usize> = (0..stages.len()).filter(|&index| stages[index].name == name).collect();
+    let same_number: Vec<usize> = same_name.iter().copied().filter(|&index| stage_keys[index] == location.stage).collect();
+    let candidates = if same_number.is_empty() { same_name } else { same_number };
+    let seconds = location.timestamp.total_seconds;
+    let index = candidates.iter().copied()
+        .find(|&index| stages[index].start_secs <= seconds && seconds <= stages[index].end_secs)
+        .or(candidates.first().copied())?;
+    stages.get_mut(index)
+}
+
+/// Session metrics, stage summaries in stage order and the erroneous and missed actions in CSV order.
+pub fn summarize_actions(points: &[ActionPlotPoint], plotly_config: &PlotlyConfig) -> (SessionMetrics, Vec<StageSummary>, Vec<ActionIssue>) {
+    let mut metrics = SessionMetrics::default();
+    let mut stages: Vec<StageSummary> = Vec::new();
+    let mut stage_keys: Vec<(u32, String)> = Vec::new();
+    let mut issues = Vec::new();
+    for point in points {
+        if let ActionPlotPoint::Period(PeriodType::Stage, start, end) = point {
+            metrics.duration_secs = metrics.duration_secs.max(end.timestamp.total_seconds);
+            stage_keys.push(start.stage.clone());
+            stages.push(StageSummary {
+                name: stage_name(start, plotly_config),
+                start_secs: start.timestamp.total_seconds,
+                end_secs: end.timestamp.total_seconds,
+                action_count: 0,
+                erroneous_action_count: 0,
+                missed_action_count: 0,
+            });
+        }
+    }
+    for point in points {
+        match point {
+            ActionPlotPoint::Action(action) => {
+                metrics.action_count += 1;
+                if let Some(stage) = find_stage(&mut stages, &stage_keys, &action.location, plotly_config) {
+                    stage.action_count += 1;
+                }
+            }
+            ActionPlotPoint::Error(action) => {
+                metrics.action_count += 1;
+                metrics.erroneous_action_count += 1;
+                if let Some(stage) = find_stage(&mut stages, &stage_keys, &action.location, plotly_config) {
+                    stage.action_count += 1;
+                    stage.erroneous_action_count += 1;
+                }
+                issues.push(ActionIssue {
+                    kind: IssueKind::Erroneous,
+                    time: action.location.timestamp.timestamp.clone(),
+                    stage: stage_name(&action.location, plotly_config),
+                    name: action.name.clone(),
+                    violation: action.error_info.violation.clone(),
+                    advice: action.error_info.advice.clone(),
+                });
+            }
+            ActionPlotPoint::MissedAction(action) => {
+                metrics.missed_action_count += 1;
+                if let Some(stage) = find_stage(&mut stages, &stage_keys, &action.location, plotly_config) {
+                    stage.missed_action_count += 1;
+                }
+                issues.push(ActionIssue {
+                    kind: IssueKind::Missed,
+                    time: action.location.timestamp.timestamp.clone(),
+                    stage: stage_name(&action.location, plotly_config),
+                    name: action.name.clone(),
+                    violation: action.error_info.violation.clone(),
+                    advice: action.error_info.advice.clone(),
+                });
+            }
+            ActionPlotPoint::Period(PeriodType::CPR, start, end) => {
+                metrics.cpr_period_count += 1;
+                metrics.cpr_duration_secs += end.timestamp.total_seconds.saturating_sub(start.timestamp.total_seconds);
+            }
+            ActionPlotPoint::Period(PeriodType::Stage, _, _) => {}
+        }
+    }
+    metrics.stage_count = stages.len();
+    (metrics, stages, issues)
+}
+
+pub fn summarize_cognitive_load(name: String, samples: &[CognitiveLoadSample]) -> MemberCognitiveLoad {
+    let loads: Vec<f64> = samples.iter().filter_map(|sample| sample.load).collect();
+    MemberCognitiveLoad {
+        name,
+        samples: samples.iter().map(|sample| (sample.seconds, sample.load)).collect(),
+        mean: (!loads.is_empty()).then(|| loads.iter().sum::<f64>() / loads.len() as f64),
+        peak: loads.iter().copied().reduce(f64::max),
+    }
+}
+
+pub fn summarize_visual_attention(name: String, plot_data: VisualAttentionPlotData) -> MemberVisualAttention {
+    let shares = plot_data.data.iter()
+        .map(|category| {
+            let ratios: Vec<f64> = category.y.iter().flatten().copied().collect();
+            CategoryShare {
+                category: category.name.clone(),
+                color: category.marker.get("color").cloned().unwrap_or_default(),
+                share: if ratios.is_empty() { 0.0 } else { ratios.iter().sum::<f64>() / ratios.len() as f64 },
+            }
+        })
+        .collect();
+    MemberVisualAttention { name, plot_data, shares }
+}
+
+/// `H:MM:SS`, or `M:SS` below an hour.
+pub fn format_duration(seconds: u32) -> String {
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests_session_report {
+    use super::*;
+    use mteam_dashboard_action_processor::plot_structures::{Action, ErroneousAction, ErrorInfo, MissedAction};
+    use mteam_dashboard_utils::date_parser::seconds_to_csv_row_time;
+    use std::path::Path;
+
+    pub(crate) fn plotly_config() -> PlotlyConfig {
+        PlotlyConfig::load(Path::new("../plot-config")).unwrap()
+    }
+
+    fn location(seconds: u32, stage: &str) -> PlotLocation {
+        PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (1, stage.to_owned()) }
+    }
+
+    fn error_info(advice: &str) -> ErrorInfo {
+        ErrorInfo { action_rule: String::new(), violation: "Too late".to_owned(), advice: advice.to_owned() }
+    }
+
+    pub(crate) fn action_points() -> Vec<ActionPlotPoint> {
+        vec![
+            ActionPlotPoint::Period(PeriodType::Stage, location(0, "Stage A"), location(120, "Stage A")),
+            ActionPlotPoint::Period(PeriodType::Stage, location(120, "Stage B"), location(300, "Stage B")),
+            ActionPlotPoint::Action(Action { location: location(10, "Stage A"), name: "Check Pulse".to_owned(), action_category: String::new(), shock_value: String::new() }),
+            ActionPlotPoint::Error(ErroneousAction {
+                location: location(130, "Stage B"),
+                name: "Defib".to_owned(),
+                action_category: String::new(),
+                shock_value: "200J".to_owned(),
+                error_info: error_info("Shock <before> epinephrine"),
+            }),
+            ActionPlotPoint::MissedAction(MissedAction { location: location(150, "Stage B"), name: "Epinephrine".to_owned(), error_info: error_info("Give 1mg") }),
+            ActionPlotPoint::Period(PeriodType::CPR, location(140, "Stage B"), location(260, "Stage B")),
+        ]
+    }
+
+    pub(crate) fn report() -> SessionReport {
+        let input = SessionReportInput {
+            session_name: "09182024".to_owned(),
+            scenario_name: Some("Cardiac Arrest".to_owned()),
+            generated_at: "2025-01-01 10:00".to_owned(),
+            action_points: action_points(),
+            cognitive_load: vec![("Team Lead".to_owned(), vec![
+                CognitiveLoadSample { seconds: 0.0, load: Some(0.2), confidence: None },
+                CognitiveLoadSample { seconds: 1.0, load: None, confidence: None },
+                CognitiveLoadSample { seconds: 2.0, load: Some(0.6), confidence: None },
+            ])],
+            visual_attention: vec![("Team Lead".to_owned(), serde_json::from_str(r##"{"data": [
+                {"x": ["a", "b", "c"], "y": [0.5, 1.0, null], "name": "Patient", "type": "bar", "marker": {"color": "#d62728"}, "noData": [false, false, true], "unreliable": [false, false, false], "sampleCount": [2, 2, 0]},
+                {"x": ["a", "b", "c"], "y": [0.5, 0.0, null], "name": "Team", "type": "bar", "marker": {"color": "#1f77b4"}, "noData": [false, false, true], "unreliable": [false, false, false], "sampleCount": [2, 2, 0]}
+            ], "warnings": []}"##).unwrap())],
+        };
+        SessionReport::new(input, &plotly_config())
+    }
+
+    #[test]
+    fn metrics_and_stages() {
+        let (metrics, stages, issues) = summarize_actions(&action_points(), &plotly_config());
+        assert_eq!(metrics, SessionMetrics {
+            duration_secs: 300,
+            stage_count: 2,
+            action_count: 2,
+            erroneous_action_count: 1,
+            missed_action_count: 1,
+            cpr_period_count: 1,
+            cpr_duration_secs: 120,
+        });
+        assert_eq!((stages[0].action_count, stages[0].erroneous_action_count, stages[0].missed_action_count), (1, 0, 0));
+        assert_eq!((stages[1].action_count, stages[1].erroneous_action_count, stages[1].missed_action_count), (1, 1, 1));
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].kind, IssueKind::Erroneous);
+        assert_eq!(issues[0].time, "00:02:10");
+        assert_eq!(issues[1].advice, "Give 1mg");
+    }
+
+    #[test]
+    fn repeated_stages_are_counted_per_occurrence() {
+        let stage = |number: u32, start: u32, end: u32| {
+            let location = |seconds: u32| PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (number, "VF".to_owned()) };
+            ActionPlotPoint::Period(PeriodType::Stage, location(start), location(end))
+        };
+        let action = |number: u32, seconds: u32| ActionPlotPoint::Action(Action {
+            location: PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (number, "VF".to_owned()) },
+            name: "Defib".to_owned(),
+            action_category: String::new(),
+            shock_value: String::new(),
+        });
+        let points = vec![
+            stage(1, 0, 60),
+            stage(3, 100, 160),
+            action(1, 30),
+            action(3, 120),
+            action(3, 130),
+            // without a matching stage number the time decides
+            action(0, 150),
+        ];
+        let (_, stages, _) = summarize_actions(&points, &plotly_config());
+        assert_eq!(stages.iter().map(|stage| stage.action_count).collect::<Vec<_>>(), vec![1, 3]);
+    }
+
+    #[test]
+    fn member_summaries() {
+        let report = report();
+        assert_eq!(report.cognitive_load[0].mean, Some(0.4));
+        assert_eq!(report.cognitive_load[0].peak, Some(0.6));
+        assert_eq!(report.visual_attention[0].shares[0].share, 0.75);
+        assert_eq!(report.visual_attention[0].shares[1].color, "#1f77b4");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(75), "1:15");
+        assert_eq!(format_duration(3725), "1:02:05");
+    }
+}
@@ -0,0 +1,174 @@
+use crate::date_parser::seconds_to_csv_row_time;
+use mteam_dashboard_action_processor::plot_structures::{ActionPlotPoint, CsvRowTime, PlotLocation};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Linear correction applied to the timestamps of a stream: `aligned = seconds * (1 + drift_factor) + offset_secs`.
+/// The drift factor is the number of seconds the stream gains (positive) or loses (negative) per second.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StreamOffset {
+    #[serde(rename = "offsetSeconds", default)]
+    pub offset_secs: f64,
+    #[serde(rename = "driftFactor", default)]
+    pub drift_factor: f64,
+}
+
+impl StreamOffset {
+    pub fn apply(&self, seconds: f64) -> f64 {
+        seconds * (1.0 + self.drift_factor) + self.offset_secs
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.offset_secs == 0.0 && self.drift_factor == 0.0
+    }
+}
+
+/// Correction for a stream made of several files (one per team member), the per-file values are added to the stream values.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StreamAlignment {
+    #[serde(flatten)]
+    pub offset: StreamOffset,
+    #[serde(default)]
+    pub files: HashMap<String, StreamOffset>,
+}
+
+impl StreamAlignment {
+    pub fn for_file(&self, file_id: &str) -> StreamOffset {
+        match self.files.get(file_id) {
+            Some(file_offset) => StreamOffset {
+                offset_secs: self.offset.offset_secs + file_offset.offset_secs,
+                drift_factor: self.offset.drift_factor + file_offset.drift_factor,
+            },
+            None => self.offset.clone(),
+        }
+    }
+}
+
+/// Per-session time alignment of the data streams against the clinical review timeline.
+/// The video offset is not applied on the server, it is handed to the frontend to shift the video playhead.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SessionAlignment {
+    #[serde(default)]
+    pub actions: StreamOffset,
+    #[serde(default)]
+    pub video: StreamOffset,
+    #[serde(rename = "cognitiveLoad", default)]
+    pub cognitive_load: StreamAlignment,
+    #[serde(rename = "visualAttention", default)]
+    pub visual_attention: StreamAlignment,
+}
+
+/// An error when the time is shifted before the start of the timeline.
+pub fn align_csv_row_time(time: &CsvRowTime, offset: &StreamOffset) -> Result<CsvRowTime, String> {
+    let aligned_seconds = offset.apply(time.total_seconds as f64).round();
+    if aligned_seconds < 0.0 {
+        return Err(format!("{} is out of range, the alignment shifts it {} seconds before the start of the timeline", time.timestamp, -aligned_seconds));
+    }
+    Ok(seconds_to_csv_row_time(aligned_seconds as u32))
+}
+
+fn align_plot_location(location: PlotLocation, offset: &StreamOffset) -> Result<PlotLocation, String> {
+    Ok(PlotLocation {
+        timestamp: align_csv_row_time(&location.timestamp, offset)?,
+        stage: location.stage,
+    })
+}
+
+/// Events shifted before the start of the timeline are out of range, a period that starts before it is cut at the
+/// start and is out of range when it ends before it too.
+pub fn align_action_plot_point(point: ActionPlotPoint, offset: &StreamOffset) -> Result<ActionPlotPoint, String> {
+    if offset.is_identity() {
+        return Ok(point);
+    }
+    Ok(match point {
+        ActionPlotPoint::Action(mut action) => {
+            action.location = align_plot_location(action.location, offset)?;
+            ActionPlotPoint::Action(action)
+        }
+        ActionPlotPoint::Error(mut action) => {
+            action.location = align_plot_location(action.location, offset)?;
+            ActionPlotPoint::Error(action)
+        }
+        ActionPlotPoint::MissedAction(mut action) => {
+            action.location = align_plot_location(action.location, offset)?;
+            ActionPlotPoint::MissedAction(action)
+        }
+        ActionPlotPoint::Period(period_type, start, end) => {
+            let end = align_plot_location(end, offset)?;
+            let start_stage = start.stage.clone();
+            let start = align_plot_location(start, offset)
+                .unwrap_or_else(|_| PlotLocation { timestamp: seconds_to_csv_row_time(0), stage: start_stage });
+            ActionPlotPoint::Period(period_type, start, end)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests_alignment {
+    use super::*;
+    use mteam_dashboard_action_processor::plot_structures::PeriodType;
+
+    #[test]
+    fn offset_and_drift() {
+        let offset = StreamOffset { offset_secs: 2.0, drift_factor: 0.01 };
+        assert_eq!(offset.apply(100.0), 103.0);
+        assert!(StreamOffset::default().is_identity());
+    }
+
+    #[test]
+    fn file_offset_is_added_to_stream_offset() {
+        let alignment = StreamAlignment {
+            offset: StreamOffset { offset_secs: 1.0, drift_factor: 0.0 },
+            files: HashMap::from([("team_lead.json".to_owned(), StreamOffset { offset_secs: -3.0, drift_factor: 0.5 })]),
+        };
+        assert_eq!(alignment.for_file("team_lead.json"), StreamOffset { offset_secs: -2.0, drift_factor: 0.5 });
+        assert_eq!(alignment.for_file("airway.json"), StreamOffset { offset_secs: 1.0, drift_factor: 0.0 });
+    }
+
+    #[test]
+    fn deserialize_with_defaults() {
+        let alignment: SessionAlignment = serde_json::from_str(
+            r#"{"video": {"offsetSeconds": 4.5}, "cognitiveLoad": {"offsetSeconds": 1, "files": {"airway.json": {"driftFactor": 0.001}}}}"#,
+        ).unwrap();
+        assert_eq!(alignment.actions, StreamOffset::default());
+        assert_eq!(alignment.video.offset_secs, 4.5);
+        assert_eq!(alignment.cognitive_load.offset.offset_secs, 1.0);
+        assert_eq!(alignment.cognitive_load.files["airway.json"].drift_factor, 0.001);
+        assert!(alignment.visual_attention.files.is_empty());
+    }
+
+    #[test]
+    fn negative_times_are_out_of_range() {
+        let offset = StreamOffset { offset_secs: -5.0, drift_factor: 0.0 };
+        assert!(align_csv_row_time(&seconds_to_csv_row_time(3), &offset).unwrap_err().contains("00:00:03 is out of range"));
+        assert_eq!(align_csv_row_time(&seconds_to_csv_row_time(5), &offset).unwrap().total_seconds, 0);
+    }
+
+    #[test]
+    fn periods_are_cut_at_the_start_of_the_timeline() {
+        let location = |seconds| PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (1, "Stage".to_owned()) };
+        let offset = StreamOffset { offset_secs: -10.0, drift_factor: 0.0 };
+        let aligned = align_action_plot_point(ActionPlotPoint::Period(PeriodType::CPR, location(5), location(20)), &offset).unwrap();
+        if let ActionPlotPoint::Period(PeriodType::CPR, start, end) = aligned {
+            assert_eq!((start.timestamp.total_seconds, end.timestamp.total_seconds), (0, 10));
+            assert_eq!(start.stage, (1, "Stage".to_owned()));
+        } else {
+            panic!("Expected ActionPlotPoint::Period with PeriodType::CPR");
+        }
+        assert!(align_action_plot_point(ActionPlotPoint::Period(PeriodType::CPR, location(2), location(8)), &offset).is_err());
+    }
+
+    #[test]
+    fn period_boundaries_are_shifted() {
+        let location = |seconds| PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (1, "Stage".to_owned()) };
+        let point = ActionPlotPoint::Period(PeriodType::Stage, location(10), location(20));
+        let aligned = align_action_plot_point(point, &StreamOffset { offset_secs: 5.0, drift_factor: 0.0 }).unwrap();
+        if let ActionPlotPoint::Period(PeriodType::Stage, start, end) = aligned {
+            assert_eq!((start.timestamp.total_seconds, end.timestamp.total_seconds), (15, 25));
+            assert_eq!(start.timestamp.timestamp, "00:00:15");
+            assert_eq!(start.stage, (1, "Stage".to_owned()));
+        } else {
+            panic!("Expected ActionPlotPoint::Period with PeriodType::Stage");
+        }
+    }
+}
@@ -0,0 +1,58 @@
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a. Unlike the std hashers it is guaranteed to give the same value across builds, platforms and Rust
+/// releases, so its hashes can be persisted (e.g. in cache keys) or pick colors that must not change.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(FNV_OFFSET_BASIS)
+    }
+}
+
+impl StableHasher {
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.0 = bytes.iter().fold(self.0, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME));
+    }
+
+    /// Writes the length before the bytes, so that e.g. `["ab", "c"]` and `["a", "bc"]` hash differently.
+    pub fn write_field(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = StableHasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests_hash {
+    use super::*;
+
+    #[test]
+    fn hashes_are_pinned() {
+        // a change would invalidate the persisted hashes and recolor the stages
+        assert_eq!(stable_hash(b"VF"), 0x093b_6207_b5cc_43dd);
+        assert_eq!(stable_hash(b""), FNV_OFFSET_BASIS);
+    }
+
+    #[test]
+    fn fields_are_delimited() {
+        let hash = |fields: &[&str]| {
+            let mut hasher = StableHasher::default();
+            fields.iter().for_each(|field| hasher.write_field(field.as_bytes()));
+            hasher.finish()
+        };
+        assert_ne!(hash(&["ab", "c"]), hash(&["a", "bc"]));
+        assert_eq!(hash(&["ab", "c"]), hash(&["ab", "c"]));
+    }
+}
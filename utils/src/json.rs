@@ -1,6 +1,41 @@
 use serde_json::value::Value;
+use std::borrow::Cow;
 use std::io::{BufReader, Read};
 
+fn is_word_byte(byte: Option<&u8>) -> bool {
+    byte.is_some_and(|byte| byte.is_ascii_alphanumeric() || *byte == b'_')
+}
+
+/// Replaces the bare `NaN` tokens that some exporters write for missing numbers with `null`. A `NaN` within a
+/// string (e.g. a category label) is kept.
+pub fn replace_nan_tokens(json: &str) -> Cow<'_, str> {
+    if !json.contains("NaN") {
+        return Cow::Borrowed(json);
+    }
+    let bytes = json.as_bytes();
+    let mut sanitized = String::with_capacity(json.len());
+    let mut copied_to = 0;
+    let (mut in_string, mut escaped) = (false, false);
+    for (index, byte) in bytes.iter().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+        } else if *byte == b'"' {
+            in_string = true;
+        } else if bytes[index..].starts_with(b"NaN") && !is_word_byte(index.checked_sub(1).map(|previous| &bytes[previous])) && !is_word_byte(bytes.get(index + 3)) {
+            sanitized.push_str(&json[copied_to..index]);
+            sanitized.push_str("null");
+            copied_to = index + 3;
+        }
+    }
+    sanitized.push_str(&json[copied_to..]);
+    Cow::Owned(sanitized)
+}
+
 ///If a reader returns a JSON array, this function will parse it and return it as a Vec<Value>.
  ///e.g.: Your reader has a JSON array but nothing else: [{...}, {...}, {...}]
  pub fn parse_json_array_root<R: Read>(reader: R) -> Result<Vec<Value>, String> {
@@ -11,7 +46,7 @@ use std::io::{BufReader, Read};
          .map_err(|e| format!("Error reading JSON: {}", e))?;
 
      // Replace invalid NaN tokens with valid null tokens.
-     let sanitized_json = raw_json.replace("NaN", "null");
+     let sanitized_json = replace_nan_tokens(&raw_json);
 
      // parse the sanitized JSON.
      let root: Value = serde_json::from_str(&sanitized_json)
@@ -22,3 +57,21 @@ use std::io::{BufReader, Read};
          _ => Err("JSON root is not an array".to_string()),
      }
  }
+
+#[cfg(test)]
+mod tests_json {
+    use super::*;
+
+    #[test]
+    fn bare_nan_tokens_are_replaced() {
+        assert_eq!(replace_nan_tokens("[[1, NaN], [2,NaN,0.5]]"), "[[1, null], [2,null,0.5]]");
+        assert_eq!(replace_nan_tokens("{\"load\": NaN}"), "{\"load\": null}");
+        assert!(matches!(replace_nan_tokens("[1, 2]"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn strings_are_kept() {
+        assert_eq!(replace_nan_tokens("[1, NaN, \"NaN\", \"a \\\" NaN\", \"Banana\"]"), "[1, null, \"NaN\", \"a \\\" NaN\", \"Banana\"]");
+        assert_eq!(parse_json_array_root("[{\"category\": \"NaN\", \"time\": NaN}]".as_bytes()).unwrap(), vec![serde_json::json!({"category": "NaN", "time": null})]);
+    }
+}
@@ -1,3 +1,6 @@
 pub mod json;
 pub mod date_parser;
-pub mod strings;
\ No newline at end of file
+pub mod strings;
+pub mod alignment;
+pub mod stage_alignment;
+pub mod hash;
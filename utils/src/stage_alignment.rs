@@ -0,0 +1,109 @@
+use mteam_dashboard_action_processor::plot_structures::{ActionPlotPoint, PeriodType};
+use serde::Serialize;
+
+/// Stage of a session, identified by its name and the number of earlier stages with the same name so that repeated
+/// stages (e.g. a second VF) of two sessions are matched in order.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StageSpan {
+    pub name: String,
+    pub occurrence: usize,
+    #[serde(rename = "startSeconds")]
+    pub start_secs: u32,
+    #[serde(rename = "endSeconds")]
+    pub end_secs: u32,
+}
+
+impl StageSpan {
+    fn matches(&self, other: &StageSpan) -> bool {
+        self.name == other.name && self.occurrence == other.occurrence
+    }
+
+    fn contains(&self, seconds: f64) -> bool {
+        self.start_secs as f64 <= seconds && seconds <= self.end_secs as f64
+    }
+}
+
+/// Stage periods of a session in time order.
+pub fn stage_spans(points: &[ActionPlotPoint]) -> Vec<StageSpan> {
+    let mut spans: Vec<StageSpan> = points.iter()
+        .filter_map(|point| match point {
+            ActionPlotPoint::Period(PeriodType::Stage, start, end) => Some((start.stage.1.clone(), start.timestamp.total_seconds, end.timestamp.total_seconds)),
+            _ => None,
+        })
+        .map(|(name, start_secs, end_secs)| StageSpan { name, occurrence: 0, start_secs, end_secs })
+        .collect();
+    spans.sort_by_key(|span| span.start_secs);
+    for index in 0..spans.len() {
+        spans[index].occurrence = spans[..index].iter().filter(|earlier| earlier.name == spans[index].name).count();
+    }
+    spans
+}
+
+/// Maps the times of a session onto the stage boundaries of a reference session: a time a fraction into a stage is
+/// mapped to the same fraction into the matching reference stage, so sessions of the same scenario line up by stage
+/// rather than by absolute time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageAlignment {
+    matched: Vec<(StageSpan, StageSpan)>,
+    /// Stages of the session without a matching reference stage, their times can't be mapped.
+    pub unmatched: Vec<StageSpan>,
+}
+
+impl StageAlignment {
+    pub fn new(stages: &[StageSpan], reference_stages: &[StageSpan]) -> Self {
+        let mut matched = Vec::new();
+        let mut unmatched = Vec::new();
+        for stage in stages {
+            match reference_stages.iter().find(|reference| reference.matches(stage)) {
+                Some(reference) => matched.push((stage.clone(), reference.clone())),
+                None => unmatched.push(stage.clone()),
+            }
+        }
+        Self { matched, unmatched }
+    }
+
+    /// Seconds on the reference timeline, `None` when the time is outside the matched stages.
+    pub fn align(&self, seconds: f64) -> Option<f64> {
+        self.matched.iter()
+            .find(|(stage, _)| stage.contains(seconds))
+            .map(|(stage, reference)| {
+                let duration = stage.end_secs.saturating_sub(stage.start_secs) as f64;
+                let reference_duration = reference.end_secs.saturating_sub(reference.start_secs) as f64;
+                let fraction = if duration > 0.0 { (seconds - stage.start_secs as f64) / duration } else { 0.0 };
+                reference.start_secs as f64 + fraction * reference_duration
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests_stage_alignment {
+    use super::*;
+    use crate::date_parser::seconds_to_csv_row_time;
+    use mteam_dashboard_action_processor::plot_structures::PlotLocation;
+
+    fn stage(name: &str, start: u32, end: u32) -> ActionPlotPoint {
+        let location = |seconds| PlotLocation { timestamp: seconds_to_csv_row_time(seconds), stage: (1, name.to_owned()) };
+        ActionPlotPoint::Period(PeriodType::Stage, location(start), location(end))
+    }
+
+    #[test]
+    fn repeated_stages_are_numbered() {
+        let spans = stage_spans(&[stage("VF", 100, 200), stage("Asystole", 0, 100), stage("VF", 200, 260)]);
+        let keys: Vec<(&str, usize, u32)> = spans.iter().map(|span| (span.name.as_str(), span.occurrence, span.start_secs)).collect();
+        assert_eq!(keys, vec![("Asystole", 0, 0), ("VF", 0, 100), ("VF", 1, 200)]);
+    }
+
+    #[test]
+    fn times_are_mapped_within_the_matching_stage() {
+        let reference = stage_spans(&[stage("Asystole", 0, 100), stage("VF", 100, 200)]);
+        // the session spends twice as long in asystole and has an extra stage
+        let session = stage_spans(&[stage("Asystole", 0, 200), stage("VF", 200, 250), stage("ROSC", 250, 300)]);
+        let alignment = StageAlignment::new(&session, &reference);
+
+        assert_eq!(alignment.align(50.0), Some(25.0));
+        assert_eq!(alignment.align(200.0), Some(100.0));
+        assert_eq!(alignment.align(225.0), Some(150.0));
+        assert_eq!(alignment.align(280.0), None);
+        assert_eq!(alignment.unmatched.iter().map(|span| span.name.as_str()).collect::<Vec<_>>(), vec!["ROSC"]);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::window::{aggregate_windows, sample_durations, AttentionWindow, WindowSettings};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Uninterrupted gaze on one category. The duration is the sum of the sample durations (capped like with
+/// `SampleWeighting::Duration`), so a tracking dropout doesn't lengthen the dwell before it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Dwell {
+    pub category: String,
+    #[serde(rename = "startSeconds")]
+    pub start_secs: f64,
+    #[serde(rename = "durationSeconds")]
+    pub duration_secs: f64,
+    #[serde(rename = "sampleCount")]
+    pub sample_count: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DwellStatistics {
+    pub category: String,
+    pub count: usize,
+    #[serde(rename = "totalSeconds")]
+    pub total_secs: f64,
+    #[serde(rename = "meanSeconds")]
+    pub mean_secs: f64,
+    #[serde(rename = "medianSeconds")]
+    pub median_secs: f64,
+    #[serde(rename = "maxSeconds")]
+    pub max_secs: f64,
+}
+
+/// Transitions between consecutive dwells, rows are the categories transitioned from, columns the ones transitioned to.
+/// `probabilities` is the first order Markov matrix, a row is `None` when the category is never left.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TransitionMatrix {
+    pub categories: Vec<String>,
+    pub counts: Vec<Vec<usize>>,
+    pub probabilities: Vec<Vec<Option<f64>>>,
+}
+
+/// Shannon entropy (bits) of the category distribution of a window, `None` for a window without data.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WindowEntropy {
+    #[serde(rename = "startSeconds")]
+    pub start_secs: u32,
+    #[serde(rename = "endSeconds")]
+    pub end_secs: u32,
+    pub entropy: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AttentionAnalytics {
+    pub dwells: Vec<DwellStatistics>,
+    pub transitions: TransitionMatrix,
+    pub entropy: Vec<WindowEntropy>,
+}
+
+/// Groups consecutive samples of the same category into dwells, samples without a category end the dwell.
+pub fn find_dwells(samples: &[(f64, Option<String>)], max_sample_duration_secs: f64) -> Vec<Dwell> {
+    let times: Vec<f64> = samples.iter().map(|(time, _)| *time).collect();
+    let durations = sample_durations(&times, max_sample_duration_secs);
+
+    let mut dwells: Vec<Dwell> = Vec::new();
+    let mut previous_category: Option<&String> = None;
+    for ((time, category), duration) in samples.iter().zip(durations) {
+        match category {
+            Some(category) if previous_category == Some(category) => {
+                if let Some(dwell) = dwells.last_mut() {
+                    dwell.duration_secs += duration;
+                    dwell.sample_count += 1;
+                }
+            }
+            Some(category) => dwells.push(Dwell {
+                category: category.clone(),
+                start_secs: *time,
+                duration_secs: duration,
+                sample_count: 1,
+            }),
+            None => {}
+        }
+        previous_category = category.as_ref();
+    }
+    dwells
+}
+
+fn median(sorted_values: &[f64]) -> f64 {
+    let middle = sorted_values.len() / 2;
+    if sorted_values.len().is_multiple_of(2) {
+        (sorted_values[middle - 1] + sorted_values[middle]) / 2.0
+    } else {
+        sorted_values[middle]
+    }
+}
+
+pub fn dwell_statistics(dwells: &[Dwell]) -> Vec<DwellStatistics> {
+    let mut durations_by_category: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for dwell in dwells {
+        durations_by_category.entry(&dwell.category).or_default().push(dwell.duration_secs);
+    }
+    durations_by_category.into_iter()
+        .map(|(category, mut durations)| {
+            durations.sort_by(f64::total_cmp);
+            let total_secs: f64 = durations.iter().sum();
+            DwellStatistics {
+                category: category.to_owned(),
+                count: durations.len(),
+                total_secs,
+                mean_secs: total_secs / durations.len() as f64,
+                median_secs: median(&durations),
+                max_secs: durations.last().copied().unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Dwells separated only by samples without a category still count as a transition between their categories.
+pub fn transition_matrix(dwells: &[Dwell]) -> TransitionMatrix {
+    let categories: Vec<String> = dwells.iter()
+        .map(|dwell| dwell.category.clone())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let index = |category: &str| categories.iter().position(|c| c == category).unwrap_or_default();
+
+    let mut counts = vec![vec![0; categories.len()]; categories.len()];
+    for pair in dwells.windows(2) {
+        if pair[0].category != pair[1].category {
+            counts[index(&pair[0].category)][index(&pair[1].category)] += 1;
+        }
+    }
+    TransitionMatrix::from_counts(categories, counts)
+}
+
+impl TransitionMatrix {
+    fn from_counts(categories: Vec<String>, counts: Vec<Vec<usize>>) -> Self {
+        let probabilities = counts.iter()
+            .map(|row| {
+                let total: usize = row.iter().sum();
+                row.iter().map(|count| if total > 0 { Some(*count as f64 / total as f64) } else { None }).collect()
+            })
+            .collect();
+        Self {
+            categories,
+            counts,
+            probabilities,
+        }
+    }
+
+    /// Reorders rows and columns by `order`, categories missing from it keep their relative order after the listed ones.
+    pub fn reordered(&self, order: &[String]) -> Self {
+        let mut indexes: Vec<usize> = (0..self.categories.len()).collect();
+        indexes.sort_by_key(|index| order.iter().position(|category| *category == self.categories[*index]).unwrap_or(order.len()));
+        let categories = indexes.iter().map(|index| self.categories[*index].clone()).collect();
+        let counts = indexes.iter()
+            .map(|row| indexes.iter().map(|column| self.counts[*row][*column]).collect())
+            .collect();
+        Self::from_counts(categories, counts)
+    }
+}
+
+pub fn window_entropy(window: &AttentionWindow) -> WindowEntropy {
+    let entropy = window.has_data().then(|| {
+        window.category_counts.keys()
+            .filter_map(|category| window.ratio(category))
+            .filter(|ratio| *ratio > 0.0)
+            .map(|ratio| -ratio * ratio.log2())
+            .sum::<f64>()
+            .abs()
+    });
+    WindowEntropy {
+        start_secs: window.start_secs,
+        end_secs: window.end_secs,
+        entropy,
+    }
+}
+
+/// Dwell, transition and per window entropy analytics of time ordered `(seconds, category)` samples.
+pub fn analyze_attention(samples: Vec<(f64, Option<String>)>, settings: &WindowSettings) -> AttentionAnalytics {
+    let dwells = find_dwells(&samples, settings.max_sample_duration_secs);
+    let entropy = aggregate_windows(samples.into_iter(), settings).map(|window| window_entropy(&window)).collect();
+    AttentionAnalytics {
+        dwells: dwell_statistics(&dwells),
+        transitions: transition_matrix(&dwells),
+        entropy,
+    }
+}
+
+#[cfg(test)]
+mod tests_analytics {
+    use super::*;
+
+    fn samples(data: &[(f64, Option<&str>)]) -> Vec<(f64, Option<String>)> {
+        data.iter().map(|(time, category)| (*time, category.map(String::from))).collect()
+    }
+
+    #[test]
+    fn dwells_end_on_category_change_and_missing_category() {
+        let data = samples(&[(0.0, Some("A")), (0.5, Some("A")), (1.0, Some("B")), (1.5, None), (2.0, Some("B")), (2.5, Some("A"))]);
+        let dwells = find_dwells(&data, 1.0);
+        assert_eq!(dwells.iter().map(|d| (d.category.as_str(), d.start_secs, d.sample_count)).collect::<Vec<_>>(),
+                   vec![("A", 0.0, 2), ("B", 1.0, 1), ("B", 2.0, 1), ("A", 2.5, 1)]);
+        assert_eq!(dwells[0].duration_secs, 1.0);
+    }
+
+    #[test]
+    fn dwell_before_a_dropout_is_capped() {
+        let dwells = find_dwells(&samples(&[(0.0, Some("A")), (30.0, Some("B"))]), 1.0);
+        assert_eq!(dwells[0].duration_secs, 1.0);
+    }
+
+    #[test]
+    fn statistics() {
+        let data = samples(&[(0.0, Some("A")), (1.0, Some("B")), (2.0, Some("A")), (2.5, Some("A")), (4.0, Some("B"))]);
+        let statistics = dwell_statistics(&find_dwells(&data, 1.0));
+        assert_eq!(statistics[0], DwellStatistics { category: "A".to_owned(), count: 2, total_secs: 2.5, mean_secs: 1.25, median_secs: 1.25, max_secs: 1.5 });
+        assert_eq!(statistics[1].count, 2);
+    }
+
+    #[test]
+    fn transitions() {
+        let data = samples(&[(0.0, Some("A")), (1.0, Some("B")), (2.0, None), (3.0, Some("A")), (4.0, Some("C")), (5.0, Some("A"))]);
+        let matrix = transition_matrix(&find_dwells(&data, 1.0));
+        assert_eq!(matrix.categories, vec!["A", "B", "C"]);
+        assert_eq!(matrix.counts, vec![vec![0, 1, 1], vec![1, 0, 0], vec![1, 0, 0]]);
+        assert_eq!(matrix.probabilities[0], vec![Some(0.0), Some(0.5), Some(0.5)]);
+
+        let reordered = matrix.reordered(&["C".to_owned(), "A".to_owned()]);
+        assert_eq!(reordered.categories, vec!["C", "A", "B"]);
+        assert_eq!(reordered.counts, vec![vec![0, 1, 0], vec![1, 0, 1], vec![0, 1, 0]]);
+    }
+
+    #[test]
+    fn category_never_left_has_no_probabilities() {
+        let matrix = transition_matrix(&find_dwells(&samples(&[(0.0, Some("A")), (1.0, Some("B"))]), 1.0));
+        assert_eq!(matrix.probabilities[1], vec![None, None]);
+    }
+
+    #[test]
+    fn entropy() {
+        let data = samples(&[(1.0, Some("A")), (2.0, Some("B")), (11.0, Some("A")), (35.0, Some("A"))]);
+        let analytics = analyze_attention(data, &WindowSettings::new(10));
+        assert_eq!(analytics.entropy.iter().map(|e| e.entropy).collect::<Vec<_>>(), vec![Some(1.0), Some(0.0), None, Some(0.0)]);
+    }
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Separates the levels of a hierarchical category, e.g. "Patient/Airway".
+pub const HIERARCHY_SEPARATOR: char = '/';
+
+/// Level of detail of hierarchical categories.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum CategoryLevel {
+    /// "Patient/Airway" rolls up into "Patient".
+    #[default]
+    #[serde(rename = "group")]
+    Group,
+    /// "Patient/Airway" is kept as is.
+    #[serde(rename = "detail")]
+    Detail,
+}
+
+/// Maps the area of interest labels of the eye tracking exports to the plotted categories.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CategoryMapping {
+    /// Label to category, e.g. "Vitals Screen" -> "Monitors" or "Airway" -> "Patient/Airway". Labels are matched
+    /// exactly first, then case-insensitively (the first alias in sort order wins), unlisted labels are kept.
+    pub aliases: BTreeMap<String, String>,
+    pub level: CategoryLevel,
+}
+
+impl CategoryMapping {
+    pub fn map(&self, label: &str) -> String {
+        let category = self.aliases.get(label)
+            .or_else(|| self.aliases.iter().find(|(alias, _)| alias.eq_ignore_ascii_case(label)).map(|(_, category)| category))
+            .map(String::as_str)
+            .unwrap_or(label);
+        match self.level {
+            CategoryLevel::Group => category.split(HIERARCHY_SEPARATOR).next().unwrap_or(category).trim().to_owned(),
+            CategoryLevel::Detail => category.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_category_mapping {
+    use super::*;
+
+    fn mapping(level: CategoryLevel) -> CategoryMapping {
+        CategoryMapping {
+            aliases: BTreeMap::from([
+                ("Vitals Screen".to_owned(), "Monitors".to_owned()),
+                ("Screen".to_owned(), "Monitors".to_owned()),
+                ("Airway".to_owned(), "Patient/Airway".to_owned()),
+            ]),
+            level,
+        }
+    }
+
+    #[test]
+    fn aliases() {
+        let mapping = mapping(CategoryLevel::Detail);
+        assert_eq!(mapping.map("Vitals Screen"), "Monitors");
+        assert_eq!(mapping.map("screen"), "Monitors");
+        assert_eq!(mapping.map("Team"), "Team");
+    }
+
+    #[test]
+    fn case_insensitive_matches_are_stable() {
+        let mut mapping = mapping(CategoryLevel::Detail);
+        mapping.aliases.insert("SCREEN".to_owned(), "Team".to_owned());
+        mapping.aliases.insert("sCreen".to_owned(), "Patient".to_owned());
+        assert_eq!(mapping.map("Screen"), "Monitors");
+        assert_eq!(mapping.map("screen"), "Team");
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(mapping(CategoryLevel::Detail).map("Airway"), "Patient/Airway");
+        assert_eq!(mapping(CategoryLevel::Group).map("Airway"), "Patient");
+        assert_eq!(mapping(CategoryLevel::Group).map("Patient/Chest"), "Patient");
+        assert_eq!(mapping(CategoryLevel::Group).map("Monitors"), "Monitors");
+    }
+}
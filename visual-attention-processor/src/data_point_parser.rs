@@ -1,11 +1,12 @@
+use crate::category::CategoryMapping;
 use serde_json::value::Value;
 
-pub(crate) fn map_time_to_date(visual_attention_data: Value, first_timestamp: Option<f64>) -> Option<(f64, Option<String>, Option<f64>)> {
+pub(crate) fn map_time_to_date(visual_attention_data: Value, first_timestamp: Option<f64>, category_mapping: &CategoryMapping) -> Option<(f64, Option<String>, Option<f64>)> {
     if let Value::Object(map) = visual_attention_data {
         let time = map.get("time")?.as_f64()?;
         let category = map
             .get("category")
-            .and_then(|v| v.as_str().map(String::from));
+            .and_then(|v| v.as_str().map(|label| category_mapping.map(label)));
         let start_seconds = first_timestamp.unwrap_or(time);
         let normalized_seconds = time - start_seconds;
         Some((
@@ -16,4 +17,4 @@ pub(crate) fn map_time_to_date(visual_attention_data: Value, first_timestamp: Op
     } else {
         None
     }
-}
\ No newline at end of file
+}
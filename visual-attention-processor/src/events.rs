@@ -0,0 +1,117 @@
+use crate::window::sample_durations;
+use serde::Serialize;
+
+/// Normalized (and aligned) gaze sample.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GazeEvent {
+    #[serde(rename = "timeSeconds")]
+    pub time_secs: f64,
+    pub category: Option<String>,
+}
+
+/// Consecutive events on the same category. The run ends with the duration of its last event, capped the same way
+/// as the sample durations, so a tracking dropout splits the run.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GazeRun {
+    pub category: Option<String>,
+    #[serde(rename = "startSeconds")]
+    pub start_secs: f64,
+    #[serde(rename = "endSeconds")]
+    pub end_secs: f64,
+    #[serde(rename = "eventCount")]
+    pub event_count: usize,
+}
+
+/// Keeps the events within `[from_secs, to_secs]`, either bound is optional, in time order since the samples of a
+/// file aren't necessarily ordered.
+pub fn filter_time_range(events: impl Iterator<Item = (f64, Option<String>)>, from_secs: Option<f64>, to_secs: Option<f64>) -> Vec<GazeEvent> {
+    let mut events: Vec<GazeEvent> = events
+        .filter(|(time, _)| from_secs.is_none_or(|from| *time >= from) && to_secs.is_none_or(|to| *time <= to))
+        .map(|(time_secs, category)| GazeEvent { time_secs, category })
+        .collect();
+    events.sort_by(|a, b| a.time_secs.total_cmp(&b.time_secs));
+    events
+}
+
+/// Run-length encodes time ordered events, events without a category form runs too (`category` is `None`). An
+/// event lasts until the next one, the last event as long as the one before it, capped at
+/// `max_sample_duration_secs` like the sample durations of the windows.
+pub fn run_length_encode(events: &[GazeEvent], max_sample_duration_secs: f64) -> Vec<GazeRun> {
+    let times: Vec<f64> = events.iter().map(|event| event.time_secs).collect();
+    let durations = sample_durations(&times, max_sample_duration_secs);
+    let mut runs: Vec<GazeRun> = Vec::new();
+    for (event, duration) in events.iter().zip(durations) {
+        match runs.last_mut() {
+            Some(run) if run.category == event.category && run.end_secs >= event.time_secs => {
+                run.end_secs = event.time_secs + duration;
+                run.event_count += 1;
+            }
+            _ => runs.push(GazeRun {
+                category: event.category.clone(),
+                start_secs: event.time_secs,
+                end_secs: event.time_secs + duration,
+                event_count: 1,
+            }),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests_events {
+    use super::*;
+
+    fn events(data: &[(f64, Option<&str>)]) -> Vec<(f64, Option<String>)> {
+        data.iter().map(|(time, category)| (*time, category.map(String::from))).collect()
+    }
+
+    #[test]
+    fn time_range() {
+        let data = events(&[(1.0, Some("A")), (2.0, Some("B")), (3.0, None), (4.0, Some("A"))]);
+        let filtered = filter_time_range(data.clone().into_iter(), Some(2.0), Some(3.0));
+        assert_eq!(filtered, vec![GazeEvent { time_secs: 2.0, category: Some("B".to_owned()) }, GazeEvent { time_secs: 3.0, category: None }]);
+        assert_eq!(filter_time_range(data.into_iter(), None, None).len(), 4);
+    }
+
+    #[test]
+    fn runs() {
+        let data = events(&[(0.0, Some("A")), (0.5, Some("A")), (1.0, Some("B")), (1.5, None), (2.0, Some("A")), (2.5, Some("A"))]);
+        let runs = run_length_encode(&filter_time_range(data.into_iter(), None, None), 1.0);
+        assert_eq!(runs.iter().map(|run| (run.category.as_deref(), run.start_secs, run.end_secs, run.event_count)).collect::<Vec<_>>(), vec![
+            (Some("A"), 0.0, 1.0, 2),
+            (Some("B"), 1.0, 1.5, 1),
+            (None, 1.5, 2.0, 1),
+            (Some("A"), 2.0, 3.0, 2),
+        ]);
+    }
+
+    #[test]
+    fn unordered_events_are_sorted() {
+        let data = events(&[(1.0, Some("A")), (0.0, Some("A")), (0.5, Some("B"))]);
+        let filtered = filter_time_range(data.into_iter(), None, None);
+        assert_eq!(filtered.iter().map(|event| event.time_secs).collect::<Vec<_>>(), vec![0.0, 0.5, 1.0]);
+        let runs = run_length_encode(&filtered, 1.0);
+        assert_eq!(runs.iter().map(|run| (run.category.as_deref(), run.start_secs, run.end_secs)).collect::<Vec<_>>(), vec![
+            (Some("A"), 0.0, 0.5),
+            (Some("B"), 0.5, 1.0),
+            (Some("A"), 1.0, 1.5),
+        ]);
+    }
+
+    #[test]
+    fn last_event_duration_is_capped() {
+        let data = events(&[(0.0, Some("A")), (5.0, Some("A"))]);
+        let runs = run_length_encode(&filter_time_range(data.into_iter(), None, None), 1.0);
+        assert_eq!(runs.last().unwrap().end_secs, 6.0);
+        let single = run_length_encode(&filter_time_range(events(&[(2.0, None)]).into_iter(), None, None), 0.25);
+        assert_eq!(single[0].end_secs, 2.25);
+    }
+
+    #[test]
+    fn dropout_splits_a_run() {
+        let data = events(&[(0.0, Some("A")), (30.0, Some("A"))]);
+        let runs = run_length_encode(&filter_time_range(data.into_iter(), None, None), 1.0);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].end_secs, 1.0);
+    }
+}
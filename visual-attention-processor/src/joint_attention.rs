@@ -0,0 +1,160 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// How many team members look at each category over time.
+///
+/// `counts[c][t]` is the number of members attending `categories[c]` at `times_secs[t]`, `joint_counts[t]` the
+/// largest of those and `joint_categories[t]` the category it belongs to (`None` when no member has data).
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct JointAttention {
+    #[serde(rename = "timesSeconds")]
+    pub times_secs: Vec<f64>,
+    pub categories: Vec<String>,
+    pub counts: Vec<Vec<usize>>,
+    #[serde(rename = "jointCounts")]
+    pub joint_counts: Vec<usize>,
+    #[serde(rename = "jointCategories")]
+    pub joint_categories: Vec<Option<String>>,
+    #[serde(rename = "memberCount")]
+    pub member_count: usize,
+}
+
+/// Category a member attends at `time`: the one of the latest sample at or before it, unless that sample is older
+/// than `max_sample_age_secs` (tracking dropout).
+fn category_at(samples: &[(f64, Option<String>)], time: f64, max_sample_age_secs: f64) -> Option<&String> {
+    let next = samples.partition_point(|(sample_time, _)| *sample_time <= time);
+    let (sample_time, category) = samples.get(next.checked_sub(1)?)?;
+    if time - sample_time <= max_sample_age_secs {
+        category.as_ref()
+    } else {
+        None
+    }
+}
+
+/// Samples every member's attention each `resolution_secs` from 0 to the last sample of any member.
+/// Each member's samples must be time ordered and aligned to the same timeline, ties between categories are
+/// resolved by the order of `categories` (alphabetical).
+pub fn joint_attention(members: &[Vec<(f64, Option<String>)>], resolution_secs: f64, max_sample_age_secs: f64) -> JointAttention {
+    let categories: Vec<String> = members.iter()
+        .flat_map(|samples| samples.iter().filter_map(|(_, category)| category.clone()))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let resolution_secs = if resolution_secs > 0.0 { resolution_secs } else { 1.0 };
+    let last_time = members.iter()
+        .filter_map(|samples| samples.last().map(|(time, _)| *time))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let step_count = if last_time >= 0.0 { (last_time / resolution_secs).floor() as usize + 1 } else { 0 };
+
+    let times_secs: Vec<f64> = (0..step_count).map(|step| step as f64 * resolution_secs).collect();
+    let mut counts = vec![vec![0; step_count]; categories.len()];
+    for (step, time) in times_secs.iter().enumerate() {
+        for samples in members {
+            if let Some(category) = category_at(samples, *time, max_sample_age_secs) {
+                if let Some(index) = categories.iter().position(|c| c == category) {
+                    counts[index][step] += 1;
+                }
+            }
+        }
+    }
+
+    let mut joint_counts = Vec::with_capacity(step_count);
+    let mut joint_categories = Vec::with_capacity(step_count);
+    for step in 0..step_count {
+        let mut joint: Option<(usize, usize)> = None;
+        for (index, category_counts) in counts.iter().enumerate() {
+            let count = category_counts[step];
+            if count > 0 && joint.is_none_or(|(_, joint_count)| count > joint_count) {
+                joint = Some((index, count));
+            }
+        }
+        joint_counts.push(joint.map(|(_, count)| count).unwrap_or(0));
+        joint_categories.push(joint.map(|(index, _)| categories[index].clone()));
+    }
+
+    JointAttention {
+        times_secs,
+        categories,
+        counts,
+        joint_counts,
+        joint_categories,
+        member_count: members.len(),
+    }
+}
+
+impl JointAttention {
+    /// Reorders the categories by `order` (unlisted ones keep their order after the listed ones) and resolves
+    /// ties between categories in the new order.
+    pub fn reordered(&self, order: &[String]) -> Self {
+        let mut indexes: Vec<usize> = (0..self.categories.len()).collect();
+        indexes.sort_by_key(|index| order.iter().position(|category| *category == self.categories[*index]).unwrap_or(order.len()));
+        let categories: Vec<String> = indexes.iter().map(|index| self.categories[*index].clone()).collect();
+        let counts: Vec<Vec<usize>> = indexes.iter().map(|index| self.counts[*index].clone()).collect();
+        let joint_categories = (0..self.times_secs.len())
+            .map(|step| {
+                let joint_count = self.joint_counts[step];
+                (joint_count > 0).then(|| counts.iter().position(|category_counts| category_counts[step] == joint_count))
+                    .flatten()
+                    .map(|index| categories[index].clone())
+            })
+            .collect();
+        Self {
+            times_secs: self.times_secs.clone(),
+            categories,
+            counts,
+            joint_counts: self.joint_counts.clone(),
+            joint_categories,
+            member_count: self.member_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_joint_attention {
+    use super::*;
+
+    fn samples(data: &[(f64, &str)]) -> Vec<(f64, Option<String>)> {
+        data.iter().map(|(time, category)| (*time, Some(category.to_string()))).collect()
+    }
+
+    #[test]
+    fn members_on_the_same_category() {
+        let members = vec![
+            samples(&[(0.0, "Patient"), (1.0, "Patient"), (2.0, "Team")]),
+            samples(&[(0.0, "Monitors"), (1.0, "Patient"), (2.0, "Patient")]),
+            samples(&[(0.5, "Patient"), (2.0, "Patient")]),
+        ];
+        let joint = joint_attention(&members, 1.0, 1.0);
+        assert_eq!(joint.times_secs, vec![0.0, 1.0, 2.0]);
+        assert_eq!(joint.categories, vec!["Monitors", "Patient", "Team"]);
+        assert_eq!(joint.counts, vec![vec![1, 0, 0], vec![1, 3, 2], vec![0, 0, 1]]);
+        assert_eq!(joint.joint_counts, vec![1, 3, 2]);
+        assert_eq!(joint.joint_categories, vec![Some("Monitors".to_owned()), Some("Patient".to_owned()), Some("Patient".to_owned())]);
+        assert_eq!(joint.member_count, 3);
+    }
+
+    #[test]
+    fn stale_samples_are_ignored() {
+        let members = vec![samples(&[(0.0, "Patient"), (5.0, "Team")])];
+        let joint = joint_attention(&members, 1.0, 1.0);
+        assert_eq!(joint.joint_counts, vec![1, 1, 0, 0, 0, 1]);
+        assert_eq!(joint.joint_categories[3], None);
+    }
+
+    #[test]
+    fn ties_follow_the_category_order() {
+        let members = vec![samples(&[(0.0, "Team")]), samples(&[(0.0, "Patient")])];
+        let joint = joint_attention(&members, 1.0, 1.0);
+        assert_eq!(joint.joint_categories, vec![Some("Patient".to_owned())]);
+        let reordered = joint.reordered(&["Team".to_owned()]);
+        assert_eq!(reordered.categories, vec!["Team", "Patient"]);
+        assert_eq!(reordered.joint_categories, vec![Some("Team".to_owned())]);
+    }
+
+    #[test]
+    fn no_members() {
+        let joint = joint_attention(&[], 1.0, 1.0);
+        assert!(joint.times_secs.is_empty());
+        assert_eq!(joint.member_count, 0);
+    }
+}
@@ -1,2 +1,7 @@
 pub mod file_processor;
+pub mod window;
+pub mod category;
+pub mod analytics;
+pub mod joint_attention;
+pub mod events;
 mod data_point_parser;
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How much a sample counts in the windows it falls into.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum SampleWeighting {
+    /// Every sample counts once.
+    #[default]
+    #[serde(rename = "samples")]
+    Samples,
+    /// A sample counts by the seconds until the next sample, so irregular sampling rates don't skew the ratios.
+    #[serde(rename = "duration")]
+    Duration,
+}
+
+/// How the normalized samples are grouped into windows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowSettings {
+    pub window_size_secs: u32,
+    /// Seconds between the starts of consecutive windows, the windows overlap when it's smaller than the window size.
+    pub step_secs: u32,
+    /// Windows with data but fewer samples than this are marked unreliable.
+    pub min_sample_count: usize,
+    pub weighting: SampleWeighting,
+    /// Upper bound of a sample's duration with `SampleWeighting::Duration`, so the sample before a tracking dropout
+    /// doesn't take the whole dropout.
+    pub max_sample_duration_secs: f64,
+}
+
+impl WindowSettings {
+    /// Consecutive non-overlapping windows counting samples.
+    pub fn new(window_size_secs: u32) -> Self {
+        Self {
+            window_size_secs,
+            step_secs: window_size_secs,
+            min_sample_count: 0,
+            weighting: SampleWeighting::Samples,
+            max_sample_duration_secs: 1.0,
+        }
+    }
+}
+
+/// Category attention of a `(start_secs, end_secs]` window, the first window also includes its start.
+/// A window without any categorized sample (e.g. during a tracking dropout) is still emitted with `sample_count` 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttentionWindow {
+    pub start_secs: u32,
+    pub end_secs: u32,
+    pub category_counts: BTreeMap<String, usize>,
+    /// Sum of the sample weights per category, equal to the counts with `SampleWeighting::Samples`.
+    pub category_weights: BTreeMap<String, f64>,
+    pub sample_count: usize,
+    pub reliable: bool,
+}
+
+impl AttentionWindow {
+    pub fn has_data(&self) -> bool {
+        self.sample_count > 0
+    }
+
+    /// Weighted share of the window on the category, `None` when the window has no data.
+    /// Falls back to the sample counts when all the samples have a zero duration.
+    pub fn ratio(&self, category: &str) -> Option<f64> {
+        if !self.has_data() {
+            return None;
+        }
+        let total_weight: f64 = self.category_weights.values().sum();
+        if total_weight > 0.0 {
+            Some(self.category_weights.get(category).copied().unwrap_or(0.0) / total_weight)
+        } else {
+            let count = self.category_counts.get(category).copied().unwrap_or(0);
+            Some(count as f64 / self.sample_count as f64)
+        }
+    }
+}
+
+/// Seconds until the next sample, capped by `max_duration`. The last sample takes the duration of the one before it.
+pub(crate) fn sample_durations(times: &[f64], max_duration: f64) -> Vec<f64> {
+    let mut durations: Vec<f64> = times.windows(2)
+        .map(|pair| (pair[1] - pair[0]).clamp(0.0, max_duration))
+        .collect();
+    durations.push(durations.last().copied().unwrap_or(max_duration));
+    durations
+}
+
+/// Groups `(seconds, category)` samples into windows of `window_size_secs` starting every `step_secs` from 0, until
+/// the window holding the last sample. Samples before 0 are counted in the first window, samples without a category
+/// are not counted (but still end the duration of the sample before them).
+pub fn aggregate_windows(data_iter: impl Iterator<Item = (f64, Option<String>)>, settings: &WindowSettings) -> impl Iterator<Item = AttentionWindow> {
+    // samples shifted before the start of the timeline by the alignment are out of range
+    let mut samples: Vec<(f64, Option<String>)> = data_iter.filter(|(time, _)| *time >= 0.0).collect();
+    samples.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    let times: Vec<f64> = samples.iter().map(|(time, _)| *time).collect();
+    let weights = match settings.weighting {
+        SampleWeighting::Samples => vec![1.0; samples.len()],
+        SampleWeighting::Duration => sample_durations(&times, settings.max_sample_duration_secs),
+    };
+
+    let window_size = settings.window_size_secs.max(1);
+    let step = settings.step_secs.max(1);
+    let last_time = times.last().copied();
+
+    let mut windows = Vec::new();
+    let mut window_start = 0u32;
+    while let Some(last_time) = last_time {
+        if window_start > 0 && window_start as f64 >= last_time {
+            break;
+        }
+        let window_end = window_start + window_size;
+        let first = if window_start == 0 { 0 } else { times.partition_point(|time| *time <= window_start as f64) };
+        let last = times.partition_point(|time| *time <= window_end as f64);
+
+        let mut category_counts = BTreeMap::new();
+        let mut category_weights = BTreeMap::new();
+        let mut sample_count = 0;
+        for ((_, category), weight) in samples[first..last].iter().zip(&weights[first..last]) {
+            if let Some(category) = category {
+                *category_counts.entry(category.clone()).or_insert(0) += 1;
+                *category_weights.entry(category.clone()).or_insert(0.0) += weight;
+                sample_count += 1;
+            }
+        }
+
+        windows.push(AttentionWindow {
+            start_secs: window_start,
+            end_secs: window_end,
+            category_counts,
+            category_weights,
+            sample_count,
+            reliable: sample_count > 0 && sample_count >= settings.min_sample_count,
+        });
+        window_start += step;
+    }
+    windows.into_iter()
+}
+
+#[cfg(test)]
+mod tests_window {
+    use super::*;
+
+    fn samples(data: &[(f64, &str)]) -> Vec<(f64, Option<String>)> {
+        data.iter().map(|(time, category)| (*time, Some(category.to_string()))).collect()
+    }
+
+    #[test]
+    fn ratios_per_window() {
+        let data = samples(&[(0.0, "Patient"), (5.0, "Team"), (10.0, "Patient"), (12.0, "Team")]);
+        let windows: Vec<AttentionWindow> = aggregate_windows(data.into_iter(), &WindowSettings::new(10)).collect();
+        assert_eq!(windows.len(), 2);
+        assert_eq!((windows[0].start_secs, windows[0].end_secs), (0, 10));
+        assert_eq!(windows[0].ratio("Patient"), Some(2.0 / 3.0));
+        assert_eq!(windows[1].ratio("Team"), Some(1.0));
+        assert_eq!(windows[1].ratio("Patient"), Some(0.0));
+    }
+
+    #[test]
+    fn gap_yields_empty_windows() {
+        let data = samples(&[(1.0, "Patient"), (45.0, "Team")]);
+        let windows: Vec<AttentionWindow> = aggregate_windows(data.into_iter(), &WindowSettings::new(10)).collect();
+        assert_eq!(windows.iter().map(|w| w.end_secs).collect::<Vec<_>>(), vec![10, 20, 30, 40, 50]);
+        assert_eq!(windows.iter().map(AttentionWindow::has_data).collect::<Vec<_>>(), vec![true, false, false, false, true]);
+        assert_eq!(windows[2].ratio("Patient"), None);
+        assert_eq!(windows[4].ratio("Team"), Some(1.0));
+    }
+
+    #[test]
+    fn uncategorized_samples_are_not_counted() {
+        let data = vec![(1.0, None), (2.0, Some("Team".to_owned())), (15.0, None)];
+        let windows: Vec<AttentionWindow> = aggregate_windows(data.into_iter(), &WindowSettings::new(10)).collect();
+        assert_eq!(windows[0].sample_count, 1);
+        assert!(!windows[1].has_data());
+    }
+
+    #[test]
+    fn samples_before_the_start_are_not_counted() {
+        let data = samples(&[(-12.0, "Team"), (-0.5, "Team"), (1.0, "Patient"), (11.0, "Team")]);
+        let windows: Vec<AttentionWindow> = aggregate_windows(data.into_iter(), &WindowSettings::new(10)).collect();
+        assert_eq!(windows.iter().map(|w| w.start_secs).collect::<Vec<_>>(), vec![0, 10]);
+        assert_eq!((windows[0].sample_count, windows[0].ratio("Patient")), (1, Some(1.0)));
+    }
+
+    #[test]
+    fn windows_below_min_sample_count_are_unreliable() {
+        let data = samples(&[(1.0, "Patient"), (2.0, "Patient"), (11.0, "Team")]);
+        let settings = WindowSettings { min_sample_count: 2, ..WindowSettings::new(10) };
+        let windows: Vec<AttentionWindow> = aggregate_windows(data.into_iter(), &settings).collect();
+        assert!(windows[0].reliable);
+        assert!(!windows[1].reliable);
+    }
+
+    #[test]
+    fn overlapping_windows() {
+        let data = samples(&[(1.0, "Patient"), (3.0, "Team"), (5.0, "Team"), (7.0, "Patient")]);
+        let settings = WindowSettings { step_secs: 2, ..WindowSettings::new(4) };
+        let windows: Vec<AttentionWindow> = aggregate_windows(data.into_iter(), &settings).collect();
+        assert_eq!(windows.iter().map(|w| (w.start_secs, w.end_secs)).collect::<Vec<_>>(), vec![(0, 4), (2, 6), (4, 8), (6, 10)]);
+        assert_eq!(windows.iter().map(|w| w.sample_count).collect::<Vec<_>>(), vec![2, 2, 2, 1]);
+        assert_eq!(windows[1].ratio("Team"), Some(1.0));
+        assert_eq!(windows[2].ratio("Team"), Some(0.5));
+    }
+
+    #[test]
+    fn duration_weighting() {
+        let data = samples(&[(0.0, "Patient"), (0.2, "Team"), (1.0, "Patient"), (1.5, "Team")]);
+        let settings = WindowSettings { weighting: SampleWeighting::Duration, ..WindowSettings::new(10) };
+        let windows: Vec<AttentionWindow> = aggregate_windows(data.into_iter(), &settings).collect();
+        // Patient: 0.2 + 0.5, Team: 0.8 + 0.5 (the last sample repeats the previous duration)
+        assert!((windows[0].ratio("Patient").unwrap() - 0.35).abs() < 1e-9);
+    }
+
+    #[test]
+    fn durations_are_capped() {
+        assert_eq!(sample_durations(&[0.0, 0.5, 30.0], 1.0), vec![0.5, 1.0, 1.0]);
+        assert_eq!(sample_durations(&[4.0], 1.0), vec![1.0]);
+    }
+}
@@ -0,0 +1,92 @@
+use mteam_dashboard_utils::alignment::SessionAlignment;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::sync::Mutex;
+
+/// `<data_source_id>.json` in the folder, ids that could point outside of it are rejected.
+pub(crate) fn data_source_file_path(root_dir: &Path, data_source_id: &str) -> Result<PathBuf, String> {
+    if data_source_id.is_empty() || data_source_id.contains(['/', '\\']) || data_source_id.starts_with('.') {
+        return Err(format!("Invalid data source id: {}", data_source_id));
+    }
+    Ok(root_dir.join(format!("{}.json", data_source_id)))
+}
+
+/// Keeps the per-session time alignment as `<data_source_id>.json` files in a local folder.
+///
+/// The alignment is stored next to the application rather than in the data source itself so that it can be
+/// updated for read-only data sources (e.g. Google Drive) as well.
+pub struct AlignmentStore {
+    root_dir: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl AlignmentStore {
+    pub fn new<P: AsRef<Path>>(root_dir: P) -> Self {
+        Self {
+            root_dir: root_dir.as_ref().to_path_buf(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    fn alignment_file_path(&self, data_source_id: &str) -> Result<PathBuf, String> {
+        data_source_file_path(&self.root_dir, data_source_id)
+    }
+
+    /// Returns the saved alignment of the data source, `None` when nothing is saved yet.
+    pub async fn load(&self, data_source_id: &str) -> Result<Option<SessionAlignment>, String> {
+        let file_path = self.alignment_file_path(data_source_id)?;
+        match fs::read_to_string(&file_path).await {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| format!("Error deserializing alignment file {:?}: {}", file_path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Error reading alignment file {:?}: {}", file_path, e)),
+        }
+    }
+
+    pub async fn save(&self, data_source_id: &str, alignment: &SessionAlignment) -> Result<(), String> {
+        let file_path = self.alignment_file_path(data_source_id)?;
+        let content = serde_json::to_string_pretty(alignment).map_err(|e| e.to_string())?;
+
+        let _guard = self.write_lock.lock().await;
+        fs::create_dir_all(&self.root_dir)
+            .await
+            .map_err(|e| format!("Error creating alignment folder {:?}: {}", self.root_dir, e))?;
+        fs::write(&file_path, content)
+            .await
+            .map_err(|e| format!("Error writing alignment file {:?}: {}", file_path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests_alignment_store {
+    use super::*;
+    use mteam_dashboard_utils::alignment::StreamOffset;
+
+    #[tokio::test]
+    async fn none_when_not_saved() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = AlignmentStore::new(temp_dir.path());
+        assert_eq!(store.load("09182024").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn save_and_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = AlignmentStore::new(temp_dir.path().join("alignment"));
+        let alignment = SessionAlignment {
+            video: StreamOffset { offset_secs: 3.5, drift_factor: 0.0 },
+            ..Default::default()
+        };
+        store.save("09182024", &alignment).await.unwrap();
+        assert_eq!(store.load("09182024").await.unwrap(), Some(alignment));
+    }
+
+    #[tokio::test]
+    async fn rejects_path_like_ids() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = AlignmentStore::new(temp_dir.path());
+        assert!(store.load("../config").await.is_err());
+        assert!(store.save("a/b", &SessionAlignment::default()).await.is_err());
+    }
+}
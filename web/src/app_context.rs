@@ -1,8 +1,55 @@
+use crate::alignment_store::AlignmentStore;
+use crate::cohort_cache::CohortCache;
 use crate::data_source::DataSource;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::HttpRequest;
+use mteam_dashboard_plotly_processor::config::init::PlotConfigHandle;
 use mteam_dashboard_plotly_processor::config::plotly_mappings::PlotlyConfig;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 pub struct AppContext {
     pub datasource_provider: Arc<dyn DataSource>,
-    pub plotly_config: &'static PlotlyConfig
-}
\ No newline at end of file
+    pub plot_config: Arc<PlotConfigHandle>,
+    pub alignment_store: AlignmentStore,
+    pub cohort_cache: CohortCache,
+    /// Frontend static files folder, the root of the action group icon paths.
+    pub static_files_path: PathBuf,
+    /// Bearer token of the `/admin` endpoints, they are disabled without one.
+    pub admin_token: Option<String>
+}
+impl AppContext {
+    /// Snapshot of the active plot configuration, a request keeps using it even if the configuration is reloaded meanwhile.
+    pub fn plotly_config(&self) -> Arc<PlotlyConfig> {
+        self.plot_config.current()
+    }
+
+    /// Whether the request carries the admin token as `Authorization: Bearer <token>`.
+    pub fn is_admin(&self, request: &HttpRequest) -> bool {
+        let authorization = request.headers().get(AUTHORIZATION).and_then(|value| value.to_str().ok());
+        self.admin_token.as_deref().is_some_and(|token| bearer_token_matches(authorization, token))
+    }
+}
+
+/// Compares every byte so that the time taken doesn't tell how much of the token was right.
+fn bearer_token_matches(authorization: Option<&str>, token: &str) -> bool {
+    let Some(given) = authorization.and_then(|authorization| authorization.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    !token.is_empty() && given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests_admin_token {
+    use super::*;
+
+    #[test]
+    fn bearer_token() {
+        assert!(bearer_token_matches(Some("Bearer s3cret"), "s3cret"));
+        assert!(!bearer_token_matches(Some("Bearer s3cre"), "s3cret"));
+        assert!(!bearer_token_matches(Some("Bearer s3creT"), "s3cret"));
+        assert!(!bearer_token_matches(Some("s3cret"), "s3cret"));
+        assert!(!bearer_token_matches(None, "s3cret"));
+        assert!(!bearer_token_matches(Some("Bearer "), ""));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::alignment_store::data_source_file_path;
+use mteam_dashboard_report_generator::cohort::CohortSession;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::sync::Mutex;
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    #[serde(rename = "cacheKey")]
+    cache_key: String,
+    session: CohortSession,
+}
+
+/// Keeps the cohort metrics of each session as `<data_source_id>.json` files in a local folder, so that the cohort
+/// analytics don't process the actions file of every session each time.
+///
+/// An entry is stored with the key it was computed with (e.g. the plot configuration fingerprint, the alignment of
+/// the session and a hash of its files) and is ignored once the key changes.
+pub struct CohortCache {
+    root_dir: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl CohortCache {
+    pub fn new<P: AsRef<Path>>(root_dir: P) -> Self {
+        Self {
+            root_dir: root_dir.as_ref().to_path_buf(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// `None` when the session isn't cached, or cached with another key.
+    pub async fn load(&self, data_source_id: &str, cache_key: &str) -> Result<Option<CohortSession>, String> {
+        let file_path = data_source_file_path(&self.root_dir, data_source_id)?;
+        match fs::read_to_string(&file_path).await {
+            Ok(content) => serde_json::from_str::<CacheEntry>(&content)
+                .map(|entry| (entry.cache_key == cache_key).then_some(entry.session))
+                .map_err(|e| format!("Error deserializing cohort cache file {:?}: {}", file_path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Error reading cohort cache file {:?}: {}", file_path, e)),
+        }
+    }
+
+    pub async fn save(&self, data_source_id: &str, cache_key: &str, session: &CohortSession) -> Result<(), String> {
+        let file_path = data_source_file_path(&self.root_dir, data_source_id)?;
+        let entry = CacheEntry { cache_key: cache_key.to_owned(), session: session.clone() };
+        let content = serde_json::to_string_pretty(&entry).map_err(|e| e.to_string())?;
+
+        let _guard = self.write_lock.lock().await;
+        fs::create_dir_all(&self.root_dir)
+            .await
+            .map_err(|e| format!("Error creating cohort cache folder {:?}: {}", self.root_dir, e))?;
+        fs::write(&file_path, content)
+            .await
+            .map_err(|e| format!("Error writing cohort cache file {:?}: {}", file_path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests_cohort_cache {
+    use super::*;
+    use mteam_dashboard_report_generator::session_report::SessionMetrics;
+    use std::collections::BTreeMap;
+
+    fn session() -> CohortSession {
+        CohortSession {
+            session: "09182024".to_owned(),
+            date: "2024-09-18".to_owned(),
+            scenario_name: None,
+            metrics: SessionMetrics { missed_action_count: 3, ..Default::default() },
+            erroneous_actions_by_group: BTreeMap::new(),
+            missed_actions_by_stage: BTreeMap::from([("VF".to_owned(), 3)]),
+            first_performed_secs: BTreeMap::from([("Defib".to_owned(), 95)]),
+        }
+    }
+
+    #[tokio::test]
+    async fn save_and_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = CohortCache::new(temp_dir.path().join("cohort"));
+        assert_eq!(cache.load("09182024", "key").await.unwrap(), None);
+        cache.save("09182024", "key", &session()).await.unwrap();
+        assert_eq!(cache.load("09182024", "key").await.unwrap(), Some(session()));
+    }
+
+    #[tokio::test]
+    async fn entries_of_another_key_are_ignored() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = CohortCache::new(temp_dir.path());
+        cache.save("09182024", "old config", &session()).await.unwrap();
+        assert_eq!(cache.load("09182024", "new config").await.unwrap(), None);
+        assert!(cache.load("../09182024", "old config").await.is_err());
+    }
+}
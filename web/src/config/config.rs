@@ -1,9 +1,12 @@
 use std::env;
 use std::sync::Arc;
-use log::debug;
+use log::{debug, warn};
 use serde::Deserialize;
-use mteam_dashboard_plotly_processor::config::init::init_plot_config;
-use mteam_dashboard_plotly_processor::config::plotly_mappings::PlotlyConfig;
+use mteam_dashboard_cognitive_load_processor::format::SUPPORTED_FILE_EXTENSIONS;
+use mteam_dashboard_plotly_processor::config::init::{init_plot_config, PlotConfigHandle};
+use mteam_dashboard_plotly_processor::config::schema::{schema_document, unknown_properties, validate_json, JsonSchema};
+use serde_json::{json, Value};
+use crate::config::plot_config_watcher::log_config_warnings;
 use crate::config::resolve_file_path::{resolve_config_file_path, resolve_first_path};
 use crate::CREDENTIALS_FILE_HOME;
 use crate::data_providers::file_provider::LocalFileDataSource;
@@ -23,6 +26,29 @@ impl PlotType {
             PlotType::VisualAttention => "visual-attention"
         }
     }
+
+    pub fn from_folder_name(folder_name: &str) -> Option<PlotType> {
+        [PlotType::CognitiveLoad, PlotType::VisualAttention].into_iter().find(|plot_type| plot_type.as_str() == folder_name)
+    }
+
+    /// Extensions of the data files listed in the plot folder.
+    pub fn data_file_extensions(&self) -> &'static [&'static str] {
+        match self {
+            PlotType::CognitiveLoad => SUPPORTED_FILE_EXTENSIONS,
+            PlotType::VisualAttention => &["json"]
+        }
+    }
+
+    /// Whether the file is listed as a data file of the plot folder, files of unknown folders are listed if they are json.
+    pub fn is_data_file(category_folder_name: &str, file_name: &str) -> bool {
+        let extensions = PlotType::from_folder_name(category_folder_name)
+            .map(|plot_type| plot_type.data_file_extensions())
+            .unwrap_or(&["json"]);
+        std::path::Path::new(file_name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.iter().any(|extension| ext.eq_ignore_ascii_case(extension)))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -45,7 +71,57 @@ pub struct AppConfig {
     pub data_source_type: DataSourceType,
     pub port: u16,
     #[serde(rename = "staticFilesPath")]
-    pub static_files_path: String
+    pub static_files_path: String,
+    #[serde(rename = "alignmentPath", default = "default_alignment_path")]
+    pub alignment_path: String,
+    #[serde(rename = "cohortCachePath", default = "default_cohort_cache_path")]
+    pub cohort_cache_path: String,
+    /// Seconds between checks of the plot-config folder for changes, 0 turns the automatic reload off.
+    #[serde(rename = "plotConfigReloadIntervalSeconds", default = "default_plot_config_reload_interval_secs")]
+    pub plot_config_reload_interval_secs: u64,
+    /// Bearer token of the `/admin` endpoints, they are disabled without one.
+    #[serde(rename = "adminToken", default)]
+    pub admin_token: Option<String>
+}
+
+pub const APP_CONFIG_FILE: &str = "config.json";
+
+impl JsonSchema for AppConfig {
+    fn json_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "gdriveCredentialsFile": {"description": "Service account credentials of the Google Drive data source.", "type": "string"},
+                "gdriveRootFolderId": {"description": "Drive folder containing the session folders.", "type": "string"},
+                "plotConfigPath": {"description": "Folder of the plot configuration files.", "type": "string"},
+                "fileSystemPath": {"description": "Folder containing the session folders of the local file data source.", "type": "string"},
+                "dataSourceType": {"enum": ["LocalFile", "GoogleDrive"]},
+                "port": {"type": "integer", "minimum": 0, "maximum": 65535},
+                "staticFilesPath": {"description": "Folder of the frontend build served at /.", "type": "string"},
+                "alignmentPath": {"description": "Folder the session alignments are stored in.", "type": "string"},
+                "cohortCachePath": {"description": "Folder the session metrics of the cohort analytics are cached in.", "type": "string"},
+                "plotConfigReloadIntervalSeconds": {
+                    "description": "Seconds between checks of the plot-config folder for changes, 0 turns the automatic reload off.",
+                    "type": "integer",
+                    "minimum": 0
+                },
+                "adminToken": {"description": "Bearer token of the /api/admin endpoints, they are disabled without one.", "type": "string"}
+            },
+            "required": ["gdriveCredentialsFile", "gdriveRootFolderId", "plotConfigPath", "fileSystemPath", "dataSourceType", "port", "staticFilesPath"]
+        })
+    }
+}
+
+fn default_alignment_path() -> String {
+    "alignment".to_owned()
+}
+
+fn default_cohort_cache_path() -> String {
+    "cohort-cache".to_owned()
+}
+
+fn default_plot_config_reload_interval_secs() -> u64 {
+    5
 }
 
 impl AppConfig {
@@ -55,8 +131,17 @@ impl AppConfig {
         let config_path = resolve_config_file_path(&args, &vec![config_file])?;
         debug!("Using configuration file: {:?}", config_path);
 
-        // Load the configuration file as a struct
-        let config: AppConfig = serde_json::from_reader(std::fs::File::open(config_path)?)?;
+        // Check the configuration file against its schema first, the errors name the offending property
+        let value: Value = serde_json::from_reader(std::fs::File::open(&config_path)?)?;
+        let schema = schema_document::<AppConfig>(APP_CONFIG_FILE);
+        let errors = validate_json(&value, &schema);
+        if !errors.is_empty() {
+            return Err(std::io::Error::other(format!("Invalid configuration file {:?}: {}", config_path, errors.join("; "))));
+        }
+        for path in unknown_properties(&value, &schema) {
+            warn!("Ignoring unknown property {} of the configuration file {:?}", path, config_path);
+        }
+        let config: AppConfig = serde_json::from_value(value)?;
         debug!("Loaded config: {:#?}", config);
         Ok(config)
     }
@@ -67,12 +152,13 @@ impl AppConfig {
         }
     }
 
-    pub(crate) fn get_plotly_config(&self) -> &'static PlotlyConfig {
+    pub(crate) fn get_plotly_config(&self) -> Arc<PlotConfigHandle> {
         let plot_config_path = resolve_first_path(&[self.plot_config_path.as_str()]).unwrap();
         debug!("Using plot config path: {:#?}", plot_config_path);
-        let plot_config = init_plot_config(plot_config_path).unwrap().unwrap();
-        debug!("Loaded plot config: {:#?}", plot_config);
-        plot_config
+        let plot_config = init_plot_config(plot_config_path).unwrap();
+        debug!("Loaded plot config: {:#?}", plot_config.current());
+        log_config_warnings(&plot_config.current());
+        Arc::new(plot_config)
     }
 }
 
@@ -104,4 +190,32 @@ async fn get_gdrive_datasource_provider(config: &AppConfig) -> Arc<dyn DataSourc
             .await
             .expect("Failed to initialize GoogleDriveDataSource"),
     )
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests_app_config_schema {
+    use super::*;
+    use mteam_dashboard_plotly_processor::config::schema::schema_field_differences;
+
+    #[test]
+    fn bundled_config_matches_the_schema() {
+        let value: Value = serde_json::from_str(&std::fs::read_to_string("../config.json").unwrap()).unwrap();
+        assert_eq!(validate_json(&value, &schema_document::<AppConfig>(APP_CONFIG_FILE)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn schema_lists_every_field() {
+        assert_eq!(schema_field_differences::<AppConfig>(&AppConfig::json_schema()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn errors_name_the_property() {
+        let value = json!({
+            "gdriveCredentialsFile": "", "gdriveRootFolderId": "", "plotConfigPath": "plot-config", "fileSystemPath": "",
+            "dataSourceType": "S3", "port": 80800, "staticFilesPath": ""
+        });
+        assert_eq!(validate_json(&value, &AppConfig::json_schema()), vec![
+            "$.dataSourceType: expected one of \"LocalFile\", \"GoogleDrive\", found \"S3\"",
+            "$.port: must be at most 65535, found 80800",
+        ]);
+    }
+}
@@ -1,2 +1,3 @@
 pub(crate) mod config;
+pub(crate) mod plot_config_watcher;
 pub(crate) mod resolve_file_path;
\ No newline at end of file
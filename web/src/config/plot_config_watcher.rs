@@ -0,0 +1,39 @@
+use log::{error, info, warn};
+use mteam_dashboard_plotly_processor::config::init::{fingerprint, PlotConfigHandle};
+use mteam_dashboard_plotly_processor::config::plotly_mappings::PlotlyConfig;
+use mteam_dashboard_plotly_processor::config::validator::validate_config;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Configurations with errors are never loaded, the warnings are logged whenever one is.
+pub(crate) fn log_config_warnings(config: &PlotlyConfig) {
+    validate_config(config).warnings().for_each(|issue| warn!("plot-config {}", issue));
+}
+
+/// Polls the plot-config folder and reloads the configuration when its files change. A configuration that fails
+/// to load or validate is logged and the active one is kept until the files change again.
+pub(crate) fn watch_plot_config(handle: Arc<PlotConfigHandle>, interval: Duration) {
+    let mut last_seen = handle.version().fingerprint;
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        let current = match fingerprint(handle.config_dir()) {
+            Ok(current) => current,
+            Err(e) => {
+                warn!("Error checking plot-config at {:?} for changes: {}", handle.config_dir(), e);
+                continue;
+            }
+        };
+        if current == last_seen {
+            continue;
+        }
+        last_seen = current;
+        match handle.reload() {
+            Ok(version) => {
+                info!("Reloaded plot-config, version {} ({})", version.version, version.fingerprint);
+                log_config_warnings(&handle.current());
+            }
+            Err(e) => error!("{}, keeping version {}", e, handle.version().version),
+        }
+    });
+}
@@ -1,5 +1,6 @@
-use crate::config::config::DataSourceType;
+use crate::config::config::{DataSourceType, PlotType};
 use crate::data_source::DataSource;
+use crate::session_manifest::SessionManifest;
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::NaiveDate;
@@ -35,6 +36,16 @@ fn ordering_by_priority_list_then_alphabetically<'a>(a: &'a str, b: &'a str, pri
     }
 }
 
+/// Reads `session.json` of a data source folder if there is one.
+fn read_session_manifest(folder_path: &Path) -> Result<Option<SessionManifest>, String> {
+    let manifest_path = folder_path.join(SessionManifest::FILE_NAME);
+    if !manifest_path.is_file() {
+        return Ok(None);
+    }
+    let file = fs::File::open(&manifest_path).map_err(|e| format!("Error opening {:?}: {}", manifest_path, e))?;
+    SessionManifest::from_reader(file).map(Some)
+}
+
 /// A local file–system implementation of `DataSource`.
 ///
 /// The expected directory structure is:
@@ -52,6 +63,7 @@ fn ordering_by_priority_list_then_alphabetically<'a>(a: &'a str, b: &'a str, pri
 /// ```
 ///
 /// In this implementation the “file id” is simply the file’s relative path (or name).
+/// Files declared in an optional `session.json` in the date folder take precedence over the conventions above.
 pub struct LocalFileDataSource {
     root_dir: PathBuf,
 }
@@ -135,6 +147,9 @@ impl DataSource for LocalFileDataSource {
     async fn fetch_csv_reader(&self, date_folder_id: String) -> Result<Box<dyn Read + Send + Sync>, String> {
         let folder_path = self.root_dir.join(date_folder_id);
         let csv_file_path = task::spawn_blocking(move || {
+            if let Some(actions_file) = read_session_manifest(&folder_path)?.and_then(|manifest| manifest.actions_file) {
+                return Ok(folder_path.join(actions_file));
+            }
             let mut csv_path: Option<PathBuf> = None;
             for entry in fs::read_dir(&folder_path).map_err(|e| e.to_string())? {
                 let entry = entry.map_err(|e| e.to_string())?;
@@ -164,21 +179,23 @@ impl DataSource for LocalFileDataSource {
         let priority_list = priority_list_to_order.cloned();
 
         let file_map = task::spawn_blocking(move || -> Result<Vec<(String, String)>, String> {
+            let manifest = read_session_manifest(&root_dir.join(&date_folder_id))?;
+            if let Some(files) = manifest.as_ref().and_then(|manifest| manifest.plot_files(&category_folder_name)) {
+                return Ok(files);
+            }
             let folder_path = root_dir.join(&date_folder_id).join(&category_folder_name);
             let mut file_vec = Vec::new();
             for entry in fs::read_dir(&folder_path).map_err(|e| e.to_string())? {
                 let entry = entry.map_err(|e| e.to_string())?;
                 let path = entry.path();
                 if path.is_file() {
-                    if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-                        if ext.eq_ignore_ascii_case("json") {
-                            if let Some(file_name_os) = path.file_name() {
-                                if let Some(file_name) = file_name_os.to_str() {
-                                    let display_name = snake_case_file_to_title_case(file_name);
-                                    // Here we use the file name as its “ID.”
-                                    file_vec.push((display_name, file_name.to_string()));
-                                }
-                            }
+                    if let Some(file_name) = path.file_name().and_then(|s| s.to_str()) {
+                        if PlotType::is_data_file(&category_folder_name, file_name) {
+                            let display_name = manifest.as_ref()
+                                .and_then(|manifest| manifest.member_name_of_file(file_name))
+                                .map_or_else(|| snake_case_file_to_title_case(file_name), ToOwned::to_owned);
+                            // Here we use the file name as its “ID.”
+                            file_vec.push((display_name, file_name.to_string()));
                         }
                     }
                 }
@@ -197,6 +214,11 @@ impl DataSource for LocalFileDataSource {
         file_map.await.unwrap()
     }
 
+    async fn fetch_session_manifest(&self, date_folder_id: &str) -> Result<Option<SessionManifest>, String> {
+        let folder_path = self.root_dir.join(date_folder_id);
+        task::spawn_blocking(move || read_session_manifest(&folder_path)).await.unwrap()
+    }
+
     async fn stream_video(&self, folder_id: String, range: Option<String>) -> Result<
         (
             u16,    // HTTP status code (206 if a Range header was provided, otherwise 200)
@@ -210,18 +232,22 @@ impl DataSource for LocalFileDataSource {
         // 1. Compute the folder path.
         let dir_path = self.root_dir.join(&folder_id);
 
-        // 2. Search for the first video file (looking for common video extensions).
-        let video_file_path = fs::read_dir(&dir_path)
-            .map_err(|e| format!("Error reading directory {:?}: {}", dir_path, e))?
-            .filter_map(|entry| entry.ok().map(|e| e.path()))
-            .find(|path| {
-                path.is_file() &&
-                    path.extension()
-                        .and_then(|s| s.to_str())
-                        .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "mp4" | "webm" | "ogg" | "mov"))
-                        .unwrap_or(false)
-            })
-            .ok_or_else(|| format!("No video file found in folder {:?}", dir_path))?;
+        // 2. Use the video declared in the session manifest or search for the first video file (looking for common video extensions).
+        let declared_video_file = read_session_manifest(&dir_path)?.and_then(|manifest| manifest.video_file);
+        let video_file_path = match declared_video_file {
+            Some(video_file) => dir_path.join(video_file),
+            None => fs::read_dir(&dir_path)
+                .map_err(|e| format!("Error reading directory {:?}: {}", dir_path, e))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .find(|path| {
+                    path.is_file() &&
+                        path.extension()
+                            .and_then(|s| s.to_str())
+                            .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "mp4" | "webm" | "ogg" | "mov"))
+                            .unwrap_or(false)
+                })
+                .ok_or_else(|| format!("No video file found in folder {:?}", dir_path))?,
+        };
 
         // 3. Open the video file asynchronously.
         let file = File::open(&video_file_path)
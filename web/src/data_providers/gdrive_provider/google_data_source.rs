@@ -1,35 +1,50 @@
 use crate::data_source::DataSource;
+use crate::session_manifest::SessionManifest;
 use mteam_dashboard_utils::strings::snake_case_file_to_title_case;
 use serde_json::Value;
 use std::cmp::Ordering;
 use std::error::Error;
 use std::io::Read;
+use std::collections::HashMap;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::{Stream, StreamExt, TryStreamExt};
 use reqwest::Client;
-use crate::config::config::DataSourceType;
+use crate::config::config::{DataSourceType, PlotType};
 use crate::data_providers::gdrive_provider::data_source_name_parser::folder_to_data_location;
 use crate::data_providers::gdrive_provider::drive_hub_adapter::DriveHubAdapter;
 use crate::data_providers::gdrive_provider::google_drive_utils::build_drive_query;
 
+/// How long a session manifest is reused, the dashboard fetches several plots of a session at once and each of them
+/// reads the manifest.
+const MANIFEST_CACHE_DURATION: Duration = Duration::from_secs(60);
+
 pub struct GoogleDriveDataSource {
     hub: Arc<dyn DriveHubAdapter + Send + Sync>,
     main_folder_id: String,
+    /// Session manifest (or its absence) by session folder id with the time it was fetched.
+    manifests: Mutex<HashMap<String, (Instant, Option<SessionManifest>)>>,
+}
+
+/// Quotes a value for a Drive query, the backslash is the escape character of the query language.
+fn quote_query_value(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
 }
 
 impl GoogleDriveDataSource {
     pub async fn new(main_folder_id: String, hub_wrapper: Arc<dyn DriveHubAdapter + Send + Sync>) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
             hub: hub_wrapper,
-            main_folder_id
+            main_folder_id,
+            manifests: Mutex::new(HashMap::new()),
         })
     }
 
     async fn get_subfolder_id(&self, parent_folder_id: &str, subfolder_name: &str) -> Result<String, String> {
-        let query = format!("mimeType = 'application/vnd.google-apps.folder' and '{}' in parents and name = '{}' and trashed = false", parent_folder_id, subfolder_name);
+        let query = format!("mimeType = 'application/vnd.google-apps.folder' and '{}' in parents and name = {} and trashed = false", parent_folder_id, quote_query_value(subfolder_name));
 
         let subfolders = self.hub.fetch_files(query).await?;
         if subfolders.is_empty() {
@@ -44,13 +59,42 @@ impl GoogleDriveDataSource {
         Ok(subfolder_id.to_string())
     }
 
-    async fn get_json_file_name_map(&self, folder_id: String, priority_list_to_order: Option<&Vec<String>>) -> Result<Vec<(String, String)>, String> {
-        let query = format!("mimeType = 'application/json' and '{}' in parents and trashed = false", folder_id);
+    async fn get_file_id_by_name(&self, parent_folder_id: &str, file_name: &str) -> Result<Option<String>, String> {
+        let query = format!("name = {} and '{}' in parents and trashed = false", quote_query_value(file_name), parent_folder_id);
+        let files = self.hub.fetch_files(query).await?;
+        Ok(files.into_iter().next().and_then(|file| file.id))
+    }
+
+    /// Maps the team member names to the ids of the files the session manifest declares for them.
+    async fn get_declared_json_file_map(&self, folder_id: String, declared_files: Vec<(String, String)>) -> Result<Vec<(String, String)>, String> {
+        let query = format!("mimeType != 'application/vnd.google-apps.folder' and '{}' in parents and trashed = false", folder_id);
+        let files = self.hub.fetch_files(query).await?;
+
+        declared_files.into_iter()
+            .map(|(member_name, file_name)| {
+                files.iter()
+                    .find(|file| file.name.as_deref() == Some(file_name.as_str()))
+                    .and_then(|file| file.id.clone())
+                    .map(|file_id| (member_name, file_id))
+                    .ok_or_else(|| format!("File declared in {} not found: {}", SessionManifest::FILE_NAME, file_name))
+            })
+            .collect()
+    }
+
+    async fn get_json_file_name_map(&self, folder_id: String, category_folder_name: &str, manifest: Option<&SessionManifest>, priority_list_to_order: Option<&Vec<String>>) -> Result<Vec<(String, String)>, String> {
+        let query = format!("mimeType != 'application/vnd.google-apps.folder' and '{}' in parents and trashed = false", folder_id);
 
         let files = self.hub.fetch_files(query).await?;
 
         let mut file_vec: Vec<(String, String)> = files.into_iter()
-            .map(|file| (snake_case_file_to_title_case(file.name.unwrap_or_default().as_str()), file.id.unwrap_or_default()))
+            .filter(|file| file.name.as_deref().is_some_and(|name| PlotType::is_data_file(category_folder_name, name)))
+            .map(|file| {
+                let file_name = file.name.unwrap_or_default();
+                let display_name = manifest
+                    .and_then(|manifest| manifest.member_name_of_file(&file_name))
+                    .map_or_else(|| snake_case_file_to_title_case(&file_name), ToOwned::to_owned);
+                (display_name, file.id.unwrap_or_default())
+            })
             .collect();
 
         if let Some(list) = priority_list_to_order {
@@ -111,6 +155,12 @@ impl DataSource for GoogleDriveDataSource {
         Ok(Box::new(std::io::Cursor::new(data)))
     }
     async fn fetch_csv_reader(&self, folder_id: String) -> Result<Box<dyn Read + Send + Sync>, String> {
+        if let Some(actions_file) = self.fetch_session_manifest(&folder_id).await?.and_then(|manifest| manifest.actions_file) {
+            let csv_file_id = self.get_file_id_by_name(&folder_id, &actions_file).await?
+                .ok_or_else(|| format!("File declared in {} not found: {}", SessionManifest::FILE_NAME, actions_file))?;
+            let data = self.hub.fetch_file_data(csv_file_id).await?;
+            return Ok(Box::new(std::io::Cursor::new(data)));
+        }
         let query = format!(
             "mimeType contains 'text/' and '{}' in parents and trashed = false",
             &folder_id
@@ -130,8 +180,30 @@ impl DataSource for GoogleDriveDataSource {
         Ok(Box::new(std::io::Cursor::new(data)))
     }
     async fn fetch_json_file_map(&self, source_folder_id: &str, sub_folder_name: &str, priority_list_to_order: Option<&Vec<String>>) -> Result<Vec<(String, String)>, String> {
+        let manifest = self.fetch_session_manifest(source_folder_id).await?;
         let folder_id = self.get_subfolder_id(source_folder_id, sub_folder_name).await?;
-        self.get_json_file_name_map(folder_id, priority_list_to_order).await
+        match manifest.as_ref().and_then(|manifest| manifest.plot_files(sub_folder_name)) {
+            Some(files) => self.get_declared_json_file_map(folder_id, files).await,
+            None => self.get_json_file_name_map(folder_id, sub_folder_name, manifest.as_ref(), priority_list_to_order).await,
+        }
+    }
+
+    /// Reused for `MANIFEST_CACHE_DURATION`, so that the requests of a session don't each query Drive for it.
+    async fn fetch_session_manifest(&self, folder_id: &str) -> Result<Option<SessionManifest>, String> {
+        if let Some((fetched_at, manifest)) = self.manifests.lock().unwrap().get(folder_id) {
+            if fetched_at.elapsed() < MANIFEST_CACHE_DURATION {
+                return Ok(manifest.clone());
+            }
+        }
+        let manifest = match self.get_file_id_by_name(folder_id, SessionManifest::FILE_NAME).await? {
+            Some(manifest_file_id) => {
+                let data = self.hub.fetch_file_data(manifest_file_id).await?;
+                Some(SessionManifest::from_reader(data.as_slice())?)
+            }
+            None => None,
+        };
+        self.manifests.lock().unwrap().insert(folder_id.to_owned(), (Instant::now(), manifest.clone()));
+        Ok(manifest)
     }
 
     async fn stream_video(
@@ -148,11 +220,18 @@ impl DataSource for GoogleDriveDataSource {
         ),
         String
     > {
-        // 1. Build a query for video files in the target folder.
-        let query = format!(
-            "mimeType contains 'video/' and '{}' in parents and trashed = false",
-            folder_id
-        );
+        // 1. Build a query for the video declared in the session manifest or for video files in the target folder.
+        let query = match self.fetch_session_manifest(&folder_id).await?.and_then(|manifest| manifest.video_file) {
+            Some(video_file) => format!(
+                "name = {} and '{}' in parents and trashed = false",
+                quote_query_value(&video_file),
+                folder_id
+            ),
+            None => format!(
+                "mimeType contains 'video/' and '{}' in parents and trashed = false",
+                folder_id
+            ),
+        };
         let video_files = self.hub.fetch_files(query).await?;
         if video_files.is_empty() {
             return Err(format!("No video files found under folder: {}", folder_id));
@@ -264,4 +343,55 @@ impl DataSource for GoogleDriveDataSource {
             Ok((upstream_status, content_type, upstream_content_length, upstream_content_range, stream))
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests_google_data_source {
+    use super::*;
+    use google_drive3::api::File;
+    use std::future::Future;
+
+    /// Drive with a `session.json` in every folder, records the queries.
+    #[derive(Default)]
+    struct FakeDrive {
+        queries: Mutex<Vec<String>>,
+    }
+
+    impl DriveHubAdapter for FakeDrive {
+        fn fetch_files(&self, query: String) -> Pin<Box<dyn Future<Output = Result<Vec<File>, String>> + Send + '_>> {
+            let is_manifest_query = query.starts_with("name = 'session.json'");
+            self.queries.lock().unwrap().push(query);
+            Box::pin(async move {
+                let manifest = File { id: Some("manifest-id".to_owned()), name: Some("session.json".to_owned()), ..Default::default() };
+                Ok(if is_manifest_query { vec![manifest] } else { Vec::new() })
+            })
+        }
+
+        fn fetch_file_data(&self, _file_id: String) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, String>> + Send + '_>> {
+            Box::pin(async { Ok(br#"{"videoFile": "it's a video.mp4"}"#.to_vec()) })
+        }
+
+        fn get_access_token(&self) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
+            Box::pin(async { Err("offline".to_owned()) })
+        }
+    }
+
+    #[test]
+    fn query_values_are_escaped() {
+        assert_eq!(quote_query_value(r"it's a \ video.mp4"), r"'it\'s a \\ video.mp4'");
+    }
+
+    #[tokio::test]
+    async fn manifest_is_fetched_once_per_session() {
+        let drive = Arc::new(FakeDrive::default());
+        let data_source = GoogleDriveDataSource::new("root".to_owned(), drive.clone()).await.unwrap();
+        for _ in 0..3 {
+            let manifest = data_source.fetch_session_manifest("session-1").await.unwrap().unwrap();
+            assert_eq!(manifest.video_file.as_deref(), Some("it's a video.mp4"));
+        }
+        data_source.fetch_session_manifest("session-2").await.unwrap();
+        assert_eq!(drive.queries.lock().unwrap().len(), 2);
+
+        assert!(data_source.stream_video("session-1".to_owned(), None).await.is_err());
+        assert_eq!(drive.queries.lock().unwrap()[2], r"name = 'it\'s a video.mp4' and 'session-1' in parents and trashed = false");
+    }
+}
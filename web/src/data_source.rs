@@ -6,6 +6,7 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use futures::Stream;
 use crate::config::config::DataSourceType;
+use crate::session_manifest::SessionManifest;
 
 #[async_trait]
 pub trait DataSource: Send + Sync + 'static {
@@ -14,6 +15,8 @@ pub trait DataSource: Send + Sync + 'static {
     async fn fetch_json_reader(&self, file_id: String) -> Result<Box<dyn Read + Send + Sync>, String>;
     async fn fetch_csv_reader(&self, date_folder_id: String) -> Result<Box<dyn Read + Send + Sync>, String>;
     async fn fetch_json_file_map(&self, date_folder_id: &str, category_folder_name: &str, priority_list_to_order: Option<&Vec<String>>) -> Result<Vec<(String, String)>, String>;
+    /// Reads the optional `session.json` manifest of the data source folder, `Ok(None)` when the folder doesn't have one.
+    async fn fetch_session_manifest(&self, date_folder_id: &str) -> Result<Option<SessionManifest>, String>;
     /// Streams a video file.
     /// Returns a tuple of:
     ///   - status_code: e.g. 200 or 206,
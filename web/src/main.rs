@@ -1,8 +1,12 @@
+use crate::alignment_store::AlignmentStore;
 use crate::app_context::AppContext;
-use crate::config::config::{DataSourceType, PlotType};
+use crate::cohort_cache::CohortCache;
+use crate::config::config::{DataSourceType, PlotType, APP_CONFIG_FILE};
+use crate::config::plot_config_watcher::{log_config_warnings, watch_plot_config};
+use crate::session_manifest::SessionManifest;
 use actix_files as fs;
 use actix_web::error::ErrorInternalServerError;
-use actix_web::web::{Data, Path};
+use actix_web::web::{Data, Path, Query};
 use actix_web::{guard, middleware, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use async_stream::stream;
 use bytes::{Bytes, BytesMut};
@@ -10,20 +14,46 @@ use config::config::AppConfig;
 use data_source::DataSource;
 use futures::{stream, Stream};
 use futures::{StreamExt, TryStreamExt};
+use mteam_dashboard_action_processor::plot_structures::ActionPlotPoint;
 use mteam_dashboard_action_processor::process_csv;
-use mteam_dashboard_cognitive_load_processor::file_processor::process_cognitive_load_data;
+use mteam_dashboard_cognitive_load_processor::event_locked::EventLockedWindow;
+use mteam_dashboard_cognitive_load_processor::file_processor::{process_cognitive_load_data, read_cognitive_load_samples};
 use mteam_dashboard_plotly_processor::actions::plot_data::ActionsPlotData;
-use mteam_dashboard_plotly_processor::{actions, visual_attention};
-use serde_json::{json, to_string};
+use mteam_dashboard_plotly_processor::actions::renderer::{render_actions_png, render_actions_svg, RenderOptions, MAX_PNG_PIXELS};
+use mteam_dashboard_plotly_processor::config::init::ConfigVersion;
+use mteam_dashboard_plotly_processor::config::layout_settings::Theme;
+use mteam_dashboard_plotly_processor::config::plotly_mappings::{plot_config_schemas, PlotlyConfig};
+use mteam_dashboard_plotly_processor::config::schema::schema_document;
+use mteam_dashboard_plotly_processor::dashboard::transformers::{to_dashboard_plotly_data, DashboardMember};
+use mteam_dashboard_plotly_processor::figure::model::Figure;
+use mteam_dashboard_plotly_processor::figure::plotly::to_plotly;
+use mteam_dashboard_plotly_processor::figure::transformers::{to_actions_figure, to_cognitive_load_figure, to_visual_attention_figure};
+use mteam_dashboard_plotly_processor::figure::vega_lite::to_vega_lite;
+use mteam_dashboard_plotly_processor::{actions, cognitive_load, visual_attention};
+use mteam_dashboard_report_generator::cohort::{CohortReport, CohortSession, CohortSessionInput, SkippedSession};
+use mteam_dashboard_report_generator::session_comparison::{ComparisonInput, SessionComparison};
+use mteam_dashboard_report_generator::session_report::{SessionReport, SessionReportInput};
+use mteam_dashboard_report_generator::{html, pdf};
+use mteam_dashboard_visual_attention_processor::category::CategoryLevel;
+use mteam_dashboard_visual_attention_processor::events::{filter_time_range, run_length_encode};
+use mteam_dashboard_visual_attention_processor::file_processor::{normalize_visual_attention_load_data, process_visual_attention_data};
+use mteam_dashboard_utils::alignment::{align_action_plot_point, SessionAlignment, StreamOffset};
+use mteam_dashboard_utils::hash::StableHasher;
+use serde::Deserialize;
+use serde_json::{json, to_string, Value};
 use std::error::Error;
 use std::io;
 use std::io::Read;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
+mod alignment_store;
 mod app_context;
+mod cohort_cache;
 mod config;
 pub mod data_source;
+mod session_manifest;
 mod data_providers;
 
 
@@ -76,7 +106,8 @@ async fn data_sources(context: Data<AppContext>) -> impl Responder {
     }
 }
 async fn plot_sources(path: Path<(String, String)>, context: Data<AppContext>) -> impl Responder {
-    let source_order = &context.plotly_config.team_member_filter_settings.filter_selection_order;
+    let plotly_config = context.plotly_config();
+    let source_order = &plotly_config.team_member_filter_settings.filter_selection_order;
     let (data_source_id, plot_name) = path.into_inner();
     match context.datasource_provider.fetch_json_file_map(data_source_id.as_str(), plot_name.as_str(), Some(source_order)).await {
         Ok(file_name_vec) => {
@@ -146,15 +177,72 @@ async fn test_actions(data_source_id: Path<String>, context: Data<AppContext>) -
         .content_type("application/json")
         .streaming(body)
 }
-async fn actions(data_source_id: Path<String>, context: Data<AppContext>) -> impl Responder {
-    let reader = match context.datasource_provider.fetch_csv_reader(data_source_id.to_string()).await {
-        Ok(r) => r,
+/// The theme selected by the `theme` query parameter, an unknown theme is a 400 error.
+fn selected_theme<'a>(plotly_config: &'a PlotlyConfig, name: Option<&str>) -> Result<&'a Theme, HttpResponse> {
+    plotly_config.layout_settings.theme(name).map_err(|e| HttpResponse::BadRequest().body(e))
+}
+
+async fn load_action_points(data_source_id: &str, context: &AppContext) -> Result<impl Iterator<Item = Result<ActionPlotPoint, String>>, String> {
+    let reader = context.datasource_provider.fetch_csv_reader(data_source_id.to_string()).await?;
+    let alignment = load_alignment(data_source_id, context).await;
+    Ok(align_actions(process_csv(reader, 10), alignment.actions))
+}
+
+async fn load_actions_plot_data(data_source_id: &str, plotly_config: &PlotlyConfig, theme: &Theme, context: &AppContext) -> Result<ActionsPlotData, String> {
+    let actions_iterator = load_action_points(data_source_id, context).await?;
+    Ok(actions::transformers::to_plotly_data(plotly_config, theme, actions_iterator))
+}
+
+/// Plot data for the Plotly frontend (default), or the plot as a figure with the data inlined: a Vega-Lite spec or a
+/// standalone Plotly figure.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+enum PlotFormat {
+    #[default]
+    #[serde(rename = "plotly")]
+    Plotly,
+    #[serde(rename = "vega-lite")]
+    VegaLite,
+    #[serde(rename = "plotly-figure")]
+    PlotlyFigure,
+}
+
+impl PlotFormat {
+    fn is_figure(self) -> bool {
+        self != PlotFormat::Plotly
+    }
+
+    fn figure_response(self, figure: &Figure) -> HttpResponse {
+        match self {
+            PlotFormat::VegaLite => HttpResponse::Ok().json(to_vega_lite(figure)),
+            _ => HttpResponse::Ok().json(to_plotly(figure)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ActionsQuery {
+    theme: Option<String>,
+    format: Option<PlotFormat>,
+}
+
+async fn actions(data_source_id: Path<String>, query: Query<ActionsQuery>, context: Data<AppContext>) -> impl Responder {
+    let plotly_config = context.plotly_config();
+    let theme = match selected_theme(&plotly_config, query.theme.as_deref()) {
+        Ok(theme) => theme,
+        Err(response) => return response,
+    };
+    let format = query.format.unwrap_or_default();
+    if format.is_figure() {
+        return match load_action_points(&data_source_id, &context).await {
+            Ok(data_points) => format.figure_response(&to_actions_figure(&plotly_config, theme, data_points)),
+            Err(_) => HttpResponse::NotFound().body("Failed to get actions reader"),
+        };
+    }
+    let actions_plot_data = match load_actions_plot_data(&data_source_id, &plotly_config, theme, &context).await {
+        Ok(plot_data) => plot_data,
         Err(_) => return HttpResponse::NotFound().body("Failed to get actions reader"),
     };
 
-    let actions_iterator = process_csv(reader, 10);
-    let actions_plot_data: ActionsPlotData = actions::transformers::to_plotly_data(context.plotly_config, actions_iterator);
-
     match to_string(&actions_plot_data) {
         Ok(json) => HttpResponse::Ok()
             .content_type("application/json")
@@ -162,33 +250,129 @@ async fn actions(data_source_id: Path<String>, context: Data<AppContext>) -> imp
         Err(_) => HttpResponse::InternalServerError().body("Failed to serialize result"),
     }
 }
-async fn cognitive_load(path: Path<(String, String)>, context: Data<AppContext>) -> impl Responder {
+#[derive(Deserialize)]
+struct RenderQuery {
+    width: Option<u32>,
+    height: Option<u32>,
+    scale: Option<f32>,
+    theme: Option<String>,
+}
+
+impl RenderQuery {
+    /// The total number of output pixels is limited rather than each dimension, a larger image is a 400 error.
+    fn to_render_options(&self, context: &AppContext) -> Result<RenderOptions, String> {
+        let defaults = RenderOptions::default();
+        let options = RenderOptions {
+            width: self.width.unwrap_or(defaults.width).clamp(200, 8000),
+            height: self.height.unwrap_or(defaults.height).clamp(100, 4000),
+            scale: self.scale.unwrap_or(defaults.scale).clamp(0.5, 4.0),
+            icon_dir: Some(context.static_files_path.clone()),
+        };
+        if options.png_pixels() > MAX_PNG_PIXELS {
+            return Err(format!("Image of {} pixels (width x height x scale²) exceeds the limit of {} pixels", options.png_pixels(), MAX_PNG_PIXELS));
+        }
+        Ok(options)
+    }
+}
+
+async fn actions_svg(data_source_id: Path<String>, query: Query<RenderQuery>, context: Data<AppContext>) -> impl Responder {
+    let plotly_config = context.plotly_config();
+    let theme = match selected_theme(&plotly_config, query.theme.as_deref()) {
+        Ok(theme) => theme,
+        Err(response) => return response,
+    };
+    let options = match query.to_render_options(&context) {
+        Ok(options) => options,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let plot_data = match load_actions_plot_data(&data_source_id, &plotly_config, theme, &context).await {
+        Ok(plot_data) => plot_data,
+        Err(_) => return HttpResponse::NotFound().body("Failed to get actions reader"),
+    };
+    match web::block(move || render_actions_svg(&plot_data, &options)).await {
+        Ok(svg) => HttpResponse::Ok().content_type("image/svg+xml").body(svg),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+async fn actions_png(data_source_id: Path<String>, query: Query<RenderQuery>, context: Data<AppContext>) -> impl Responder {
+    let plotly_config = context.plotly_config();
+    let theme = match selected_theme(&plotly_config, query.theme.as_deref()) {
+        Ok(theme) => theme,
+        Err(response) => return response,
+    };
+    let options = match query.to_render_options(&context) {
+        Ok(options) => options,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let plot_data = match load_actions_plot_data(&data_source_id, &plotly_config, theme, &context).await {
+        Ok(plot_data) => plot_data,
+        Err(_) => return HttpResponse::NotFound().body("Failed to get actions reader"),
+    };
+    match web::block(move || render_actions_png(&plot_data, &options)).await {
+        Ok(Ok(png)) => HttpResponse::Ok().content_type("image/png").body(png),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct FormatQuery {
+    format: Option<PlotFormat>,
+}
+
+async fn cognitive_load(path: Path<(String, String)>, query: Query<FormatQuery>, context: Data<AppContext>) -> impl Responder {
+    let (data_source_id, file_id) = (path.0.clone(), path.1.clone());
+    let alignment = load_alignment(&data_source_id, &context).await.cognitive_load.for_file(&file_id);
     let mut file_reader = match get_json_file_reader(PlotType::CognitiveLoad, path, &context.datasource_provider).await{
         Ok(r) => r,
         Err(_) => return HttpResponse::NotFound().json(json!([]))
     };
-    match process_cognitive_load_data(&mut *file_reader).await {
+    let format = query.format.unwrap_or_default();
+    if format.is_figure() {
+        let member_name = member_name(&data_source_id, PlotType::CognitiveLoad, &file_id, &context).await;
+        return match read_cognitive_load_samples(&mut *file_reader, &alignment) {
+            Ok(samples) => {
+                let title = format!("Cognitive Load of {}", member_name);
+                format.figure_response(&to_cognitive_load_figure(&title, &[(member_name, samples)]))
+            }
+            Err(err) => HttpResponse::InternalServerError()
+                .json(json!({"error": "Failed to process cognitive load data", "details": err})),
+        };
+    }
+    match process_cognitive_load_data(&mut *file_reader, &alignment).await {
         Ok(iterator) => {
             let stream = stream! { // Start the JSON object
                 yield Ok(Bytes::from("{\"x\":[".to_string()));
                 let mut y_bytes = BytesMut::new();
                 y_bytes.extend_from_slice(b"],\"y\":[");
 
+                // Confidence is only added to the series when the file has any
+                let mut confidence_bytes = BytesMut::new();
+                confidence_bytes.extend_from_slice(b"],\"confidence\":[");
+                let mut has_confidence = false;
+
                 let mut first = true;
 
-                for (x, y) in iterator {
+                for (x, y, confidence) in iterator {
                     if !first {
                         y_bytes.extend_from_slice(b",");
+                        confidence_bytes.extend_from_slice(b",");
                         yield Ok(Bytes::from(",".to_string()));
                     }
                     first = false;
+                    has_confidence |= confidence.is_some();
                     let x_point = json!(x);
                     let y_point = json!(y);
                     y_bytes.extend_from_slice(to_string(&y_point).unwrap().as_bytes());
+                    confidence_bytes.extend_from_slice(to_string(&json!(confidence)).unwrap().as_bytes());
                     yield Ok(Bytes::from(to_string(&x_point).unwrap()));
                 }
 
                 yield Ok(y_bytes.freeze());
+                if has_confidence {
+                    yield Ok(confidence_bytes.freeze());
+                }
 
                 // Close the `y` array and add other fields
                 yield Ok(Bytes::from("],\"mode\":\"lines\",\"type\":\"scatter\"}".to_string()));
@@ -203,29 +387,109 @@ async fn cognitive_load(path: Path<(String, String)>, context: Data<AppContext>)
             .json(json!({"error": "Failed to process cognitive load data", "details": err})),
     }
 }
+#[derive(Deserialize)]
+struct EventLockedQuery {
+    pre: Option<u32>,
+    post: Option<u32>,
+}
+
+async fn cognitive_load_event_locked(path: Path<(String, String)>, query: Query<EventLockedQuery>, context: Data<AppContext>) -> impl Responder {
+    let default_window = EventLockedWindow::default();
+    let window = match EventLockedWindow::new(query.pre.unwrap_or(default_window.pre_secs), query.post.unwrap_or(default_window.post_secs)) {
+        Ok(window) => window,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let plotly_config = context.plotly_config();
+    let data_source_id = path.0.clone();
+    let file_id = path.1.clone();
+    let alignment = load_alignment(&data_source_id, &context).await;
+    let csv_reader = match context.datasource_provider.fetch_csv_reader(data_source_id).await {
+        Ok(r) => r,
+        Err(_) => return HttpResponse::NotFound().body("Failed to get actions reader"),
+    };
+    let mut file_reader = match get_json_file_reader(PlotType::CognitiveLoad, path, &context.datasource_provider).await {
+        Ok(r) => r,
+        Err(e) => return HttpResponse::NotFound().json(json!({"error": "Failed to get cognitive load data", "details": e})),
+    };
+
+    let actions_iterator = align_actions(process_csv(csv_reader, 10), alignment.actions);
+    let cognitive_load_alignment = alignment.cognitive_load.for_file(&file_id);
+    match cognitive_load::transformers::to_event_locked_plotly_data(&mut *file_reader, actions_iterator, &window, &cognitive_load_alignment, &plotly_config) {
+        Ok(event_locked_plot_data) => match to_string(&event_locked_plot_data) {
+            Ok(json) => HttpResponse::Ok()
+                .content_type("application/json")
+                .body(json),
+            Err(_) => HttpResponse::InternalServerError().body("Failed to serialize result"),
+        },
+        Err(_) => HttpResponse::InternalServerError().json(json!({
+            "error": "Source json data file couldn't be parsed."
+        })),
+    }
+}
 async fn get_json_file_reader(plot_type: PlotType, path: Path<(String, String)>, datasource_provider: &Arc<dyn DataSource>) -> Result<Box<dyn Read + Send + Sync>, String> {
     let (data_source_id, id) = path.into_inner();
+    get_plot_file_reader(plot_type, &data_source_id, id, datasource_provider).await
+}
+
+async fn get_plot_file_reader(plot_type: PlotType, data_source_id: &str, id: String, datasource_provider: &Arc<dyn DataSource>) -> Result<Box<dyn Read + Send + Sync>, String> {
     let json_file_id = match datasource_provider.data_source_type() {
         DataSourceType::LocalFile => format!("{}/{}/{}",data_source_id,plot_type.as_str(),id),
         DataSourceType::GoogleDrive => id,
     };
-    
+
     datasource_provider
         .fetch_json_reader(json_file_id)
         .await
         .map_err(|e| e.to_string())
 }
 
-async fn visual_attention(path: Path<(String, String)>, context: Data<AppContext>) -> impl Responder{
+#[derive(Deserialize)]
+struct VisualAttentionQuery {
+    level: Option<CategoryLevel>,
+}
+
+#[derive(Deserialize)]
+struct VisualAttentionPlotQuery {
+    level: Option<CategoryLevel>,
+    format: Option<PlotFormat>,
+    /// Returns `{"data": [...], "warnings": [...]}` rather than the bare array of traces.
+    #[serde(rename = "withWarnings")]
+    with_warnings: Option<bool>,
+}
+
+async fn visual_attention(path: Path<(String, String)>, query: Query<VisualAttentionPlotQuery>, context: Data<AppContext>) -> impl Responder{
+    let plotly_config = context.plotly_config();
+    let (data_source_id, file_id) = (path.0.clone(), path.1.clone());
+    let alignment = load_alignment(&data_source_id, &context).await.visual_attention.for_file(&file_id);
     let mut file_reader = match get_json_file_reader(PlotType::VisualAttention, path, &context.datasource_provider).await{
         Ok(r) => r,
         Err(e) => return HttpResponse::NotFound().json(json!({"error": "Failed to get visual attention data", "details": e})),
     };
-    let window_duration_secs = context.plotly_config.visual_attention_plot_settings.window_size_secs;
+    let window_settings = plotly_config.visual_attention_plot_settings.window_settings();
+    let category_mapping = plotly_config.visual_attention_plot_settings.category_mapping(query.level.unwrap_or_default());
 
-    match visual_attention::transformers::to_plotly_data(&mut file_reader, window_duration_secs, &context.plotly_config) {
+    let format = query.format.unwrap_or_default();
+    if format.is_figure() {
+        return match process_visual_attention_data(&mut file_reader, &window_settings, &category_mapping, &alignment) {
+            Ok(windows) => {
+                let windows: Vec<_> = windows.collect();
+                let member_name = member_name(&data_source_id, PlotType::VisualAttention, &file_id, &context).await;
+                let title = format!("Visual Attention of {}", member_name);
+                format.figure_response(&to_visual_attention_figure(&title, &windows, &plotly_config.visual_attention_plot_settings))
+            }
+            Err(_) => HttpResponse::InternalServerError().json(json!({
+                "error": "Source json data file couldn't be parsed."
+            })),
+        };
+    }
+    match visual_attention::transformers::to_plotly_data(&mut file_reader, &window_settings, &category_mapping, &alignment, &plotly_config) {
         Ok(visual_attention_plot_data) => {
-            match to_string(&visual_attention_plot_data) {
+            let json = if query.with_warnings.unwrap_or(false) {
+                to_string(&visual_attention_plot_data)
+            } else {
+                to_string(&visual_attention_plot_data.data)
+            };
+            match json {
                 Ok(json) => HttpResponse::Ok()
                     .content_type("application/json")
                     .body(json),
@@ -238,6 +502,540 @@ async fn visual_attention(path: Path<(String, String)>, context: Data<AppContext
     }
 }
 
+async fn visual_attention_analytics(path: Path<(String, String)>, query: Query<VisualAttentionQuery>, context: Data<AppContext>) -> impl Responder {
+    let plotly_config = context.plotly_config();
+    let alignment = load_alignment(&path.0, &context).await.visual_attention.for_file(&path.1);
+    let mut file_reader = match get_json_file_reader(PlotType::VisualAttention, path, &context.datasource_provider).await {
+        Ok(r) => r,
+        Err(e) => return HttpResponse::NotFound().json(json!({"error": "Failed to get visual attention data", "details": e})),
+    };
+    let settings = &plotly_config.visual_attention_plot_settings;
+    let category_mapping = settings.category_mapping(query.level.unwrap_or_default());
+
+    match visual_attention::transformers::to_analytics_plotly_data(&mut file_reader, &settings.window_settings(), &category_mapping, &alignment, &plotly_config) {
+        Ok(analytics) => HttpResponse::Ok().json(analytics),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": "Source json data file couldn't be parsed.", "details": e.to_string()})),
+    }
+}
+
+#[derive(Deserialize)]
+struct GazeEventsQuery {
+    from: Option<f64>,
+    to: Option<f64>,
+    rle: Option<bool>,
+    level: Option<CategoryLevel>,
+}
+
+async fn visual_attention_events(path: Path<(String, String)>, query: Query<GazeEventsQuery>, context: Data<AppContext>) -> impl Responder {
+    let plotly_config = context.plotly_config();
+    let alignment = load_alignment(&path.0, &context).await.visual_attention.for_file(&path.1);
+    let mut file_reader = match get_json_file_reader(PlotType::VisualAttention, path, &context.datasource_provider).await {
+        Ok(r) => r,
+        Err(e) => return HttpResponse::NotFound().json(json!({"error": "Failed to get visual attention data", "details": e})),
+    };
+    let settings = &plotly_config.visual_attention_plot_settings;
+    let category_mapping = settings.category_mapping(query.level.unwrap_or_default());
+
+    match normalize_visual_attention_load_data(&mut file_reader, &category_mapping, &alignment) {
+        Ok(events) => {
+            let events = filter_time_range(events, query.from, query.to);
+            if query.rle.unwrap_or(false) {
+                HttpResponse::Ok().json(json!({"runs": run_length_encode(&events, settings.max_sample_duration_secs)}))
+            } else {
+                HttpResponse::Ok().json(json!({"events": events}))
+            }
+        }
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": "Source json data file couldn't be parsed.", "details": e})),
+    }
+}
+
+#[derive(Deserialize)]
+struct JointAttentionQuery {
+    level: Option<CategoryLevel>,
+    resolution: Option<u32>,
+}
+
+async fn joint_attention(data_source_id: Path<String>, query: Query<JointAttentionQuery>, context: Data<AppContext>) -> impl Responder {
+    let plotly_config = context.plotly_config();
+    let source_order = &plotly_config.team_member_filter_settings.filter_selection_order;
+    let member_files = match context.datasource_provider.fetch_json_file_map(&data_source_id, PlotType::VisualAttention.as_str(), Some(source_order)).await {
+        Ok(files) => files,
+        Err(e) => return HttpResponse::NotFound().json(json!({"error": "Failed to get visual attention files", "details": e})),
+    };
+    let alignment = load_alignment(&data_source_id, &context).await.visual_attention;
+
+    let mut members = Vec::with_capacity(member_files.len());
+    for (member_name, file_id) in member_files {
+        let file_alignment = alignment.for_file(&file_id);
+        match get_plot_file_reader(PlotType::VisualAttention, &data_source_id, file_id, &context.datasource_provider).await {
+            Ok(reader) => members.push((member_name, reader, file_alignment)),
+            Err(e) => return HttpResponse::NotFound().json(json!({"error": "Failed to get visual attention data", "details": e})),
+        }
+    }
+    let category_mapping = plotly_config.visual_attention_plot_settings.category_mapping(query.level.unwrap_or_default());
+
+    match visual_attention::transformers::to_joint_attention_plotly_data(members, query.resolution.unwrap_or(1), &category_mapping, &plotly_config) {
+        Ok(joint_attention) => HttpResponse::Ok().json(joint_attention),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": "Source json data file couldn't be parsed.", "details": e.to_string()})),
+    }
+}
+
+/// Team member name and file id pairs of a plot folder, empty when the session doesn't have the folder.
+async fn member_files(data_source_id: &str, plot_type: PlotType, context: &AppContext) -> Vec<(String, String)> {
+    let plotly_config = context.plotly_config();
+    let source_order = &plotly_config.team_member_filter_settings.filter_selection_order;
+    context.datasource_provider.fetch_json_file_map(data_source_id, plot_type.as_str(), Some(source_order)).await.unwrap_or_else(|e| {
+        log::warn!("No {} files in {}: {}", plot_type.as_str(), data_source_id, e);
+        Vec::new()
+    })
+}
+
+/// Name of the team member of a plot file, the file id when the file isn't listed.
+async fn member_name(data_source_id: &str, plot_type: PlotType, file_id: &str, context: &AppContext) -> String {
+    member_files(data_source_id, plot_type, context).await.into_iter()
+        .find(|(_, id)| id == file_id)
+        .map_or_else(|| file_id.to_owned(), |(name, _)| name)
+}
+
+async fn load_scenario_name(data_source_id: &str, context: &AppContext) -> Option<String> {
+    match context.datasource_provider.fetch_session_manifest(data_source_id).await {
+        Ok(manifest) => manifest.and_then(|manifest| manifest.scenario.name),
+        Err(e) => {
+            log::warn!("Ignoring session manifest of {}: {}", data_source_id, e);
+            None
+        }
+    }
+}
+
+/// Team members whose files can't be read are left out of the report rather than failing it.
+async fn load_session_report(data_source_id: &str, context: &AppContext) -> Result<SessionReport, String> {
+    let plotly_config = context.plotly_config();
+    let reader = context.datasource_provider.fetch_csv_reader(data_source_id.to_string()).await?;
+    let alignment = load_alignment(data_source_id, context).await;
+    let action_points = align_actions(process_csv(reader, 10), alignment.actions.clone())
+        .filter_map(Result::ok)
+        .collect();
+
+    let mut cognitive_load = Vec::new();
+    for (member_name, file_id) in member_files(data_source_id, PlotType::CognitiveLoad, context).await {
+        let file_alignment = alignment.cognitive_load.for_file(&file_id);
+        let samples = match get_plot_file_reader(PlotType::CognitiveLoad, data_source_id, file_id, &context.datasource_provider).await {
+            Ok(mut reader) => read_cognitive_load_samples(&mut *reader, &file_alignment),
+            Err(e) => Err(e),
+        };
+        match samples {
+            Ok(samples) => cognitive_load.push((member_name, samples)),
+            Err(e) => log::warn!("Leaving cognitive load of {} out of the report: {}", member_name, e),
+        }
+    }
+
+    let window_settings = plotly_config.visual_attention_plot_settings.window_settings();
+    let category_mapping = plotly_config.visual_attention_plot_settings.category_mapping(CategoryLevel::default());
+    let mut visual_attention = Vec::new();
+    for (member_name, file_id) in member_files(data_source_id, PlotType::VisualAttention, context).await {
+        let file_alignment = alignment.visual_attention.for_file(&file_id);
+        let plot_data = match get_plot_file_reader(PlotType::VisualAttention, data_source_id, file_id, &context.datasource_provider).await {
+            Ok(mut reader) => visual_attention::transformers::to_plotly_data(&mut reader, &window_settings, &category_mapping, &file_alignment, &plotly_config)
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        match plot_data {
+            Ok(plot_data) => visual_attention.push((member_name, plot_data)),
+            Err(e) => log::warn!("Leaving visual attention of {} out of the report: {}", member_name, e),
+        }
+    }
+
+    let scenario_name = load_scenario_name(data_source_id, context).await;
+    let input = SessionReportInput {
+        session_name: data_source_id.to_owned(),
+        scenario_name,
+        generated_at: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+        action_points,
+        cognitive_load,
+        visual_attention,
+    };
+    Ok(SessionReport::new(input, &plotly_config))
+}
+
+/// Keeps ASCII letters, digits, `-`, `_` and `.` (other than leading dots) so that the name can be quoted in a
+/// Content-Disposition header as is.
+fn file_name_safe(name: &str) -> String {
+    let safe: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    safe.trim_start_matches('.').to_owned()
+}
+
+#[derive(Deserialize)]
+struct ReportQuery {
+    format: Option<String>,
+}
+
+async fn report(data_source_id: Path<String>, query: Query<ReportQuery>, context: Data<AppContext>) -> impl Responder {
+    let format = query.format.as_deref().unwrap_or("html");
+    if format != "html" && format != "pdf" {
+        return HttpResponse::BadRequest().json(json!({"error": format!("Unsupported report format: {}, expected html or pdf", format)}));
+    }
+    let session_report = match load_session_report(&data_source_id, &context).await {
+        Ok(session_report) => session_report,
+        Err(e) => return HttpResponse::NotFound().json(json!({"error": "Failed to read the session", "details": e})),
+    };
+    let content_disposition = format!("attachment; filename=\"{}-debrief.{}\"", file_name_safe(&data_source_id), format);
+    if format == "pdf" {
+        let icon_dir = context.static_files_path.clone();
+        match web::block(move || pdf::render_pdf(&session_report, Some(icon_dir.as_path()))).await {
+            Ok(Ok(document)) => HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("Content-Disposition", content_disposition))
+                .body(document),
+            Ok(Err(e)) => HttpResponse::InternalServerError().json(json!({"error": "Failed to render the pdf report", "details": e})),
+            Err(e) => HttpResponse::InternalServerError().json(json!({"error": "Failed to render the pdf report", "details": e.to_string()})),
+        }
+    } else {
+        HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .insert_header(("Content-Disposition", content_disposition))
+            .body(html::render_html(&session_report, Some(context.static_files_path.as_path())))
+    }
+}
+
+#[derive(Deserialize)]
+struct DashboardQuery {
+    members: Option<String>,
+    theme: Option<String>,
+    level: Option<CategoryLevel>,
+}
+
+/// Team members whose files can't be read are left out of the dashboard rather than failing it.
+async fn dashboard(data_source_id: Path<String>, query: Query<DashboardQuery>, context: Data<AppContext>) -> impl Responder {
+    let plotly_config = context.plotly_config();
+    let theme = match selected_theme(&plotly_config, query.theme.as_deref()) {
+        Ok(theme) => theme,
+        Err(response) => return response,
+    };
+    let actions_plot_data = match load_actions_plot_data(&data_source_id, &plotly_config, theme, &context).await {
+        Ok(plot_data) => plot_data,
+        Err(_) => return HttpResponse::NotFound().body("Failed to get actions reader"),
+    };
+
+    let cognitive_load_files = member_files(&data_source_id, PlotType::CognitiveLoad, &context).await;
+    let visual_attention_files = member_files(&data_source_id, PlotType::VisualAttention, &context).await;
+    let mut member_names: Vec<String> = Vec::new();
+    for (member_name, _) in cognitive_load_files.iter().chain(&visual_attention_files) {
+        if !member_names.contains(member_name) {
+            member_names.push(member_name.clone());
+        }
+    }
+    if let Some(selected) = &query.members {
+        let selected = split_list(selected);
+        if let Some(unknown) = selected.iter().find(|name| !member_names.contains(name)) {
+            return HttpResponse::BadRequest().json(json!({"error": format!("Unknown team member: {}", unknown), "members": member_names}));
+        }
+        member_names = selected;
+    }
+
+    let alignment = load_alignment(&data_source_id, &context).await;
+    let window_settings = plotly_config.visual_attention_plot_settings.window_settings();
+    let category_mapping = plotly_config.visual_attention_plot_settings.category_mapping(query.level.unwrap_or_default());
+    let mut members = Vec::with_capacity(member_names.len());
+    for member_name in member_names {
+        let mut member = DashboardMember { name: member_name, cognitive_load: None, visual_attention: None };
+        if let Some((_, file_id)) = cognitive_load_files.iter().find(|(name, _)| *name == member.name) {
+            let file_alignment = alignment.cognitive_load.for_file(file_id);
+            match get_plot_file_reader(PlotType::CognitiveLoad, &data_source_id, file_id.clone(), &context.datasource_provider).await {
+                Ok(mut reader) => match read_cognitive_load_samples(&mut *reader, &file_alignment) {
+                    Ok(samples) => member.cognitive_load = Some(samples),
+                    Err(e) => log::warn!("Leaving cognitive load of {} out of the dashboard: {}", member.name, e),
+                },
+                Err(e) => log::warn!("Leaving cognitive load of {} out of the dashboard: {}", member.name, e),
+            }
+        }
+        if let Some((_, file_id)) = visual_attention_files.iter().find(|(name, _)| *name == member.name) {
+            let file_alignment = alignment.visual_attention.for_file(file_id);
+            match get_plot_file_reader(PlotType::VisualAttention, &data_source_id, file_id.clone(), &context.datasource_provider).await {
+                Ok(mut reader) => match visual_attention::transformers::to_plotly_data(&mut reader, &window_settings, &category_mapping, &file_alignment, &plotly_config) {
+                    Ok(plot_data) => member.visual_attention = Some(plot_data),
+                    Err(e) => log::warn!("Leaving visual attention of {} out of the dashboard: {}", member.name, e),
+                },
+                Err(e) => log::warn!("Leaving visual attention of {} out of the dashboard: {}", member.name, e),
+            }
+        }
+        members.push(member);
+    }
+
+    HttpResponse::Ok().json(to_dashboard_plotly_data(actions_plot_data, members))
+}
+
+async fn load_comparison_input(data_source_id: &str, context: &AppContext) -> Result<ComparisonInput, String> {
+    let reader = context.datasource_provider.fetch_csv_reader(data_source_id.to_string()).await?;
+    let alignment = load_alignment(data_source_id, context).await;
+    let action_points = align_actions(process_csv(reader, 10), alignment.actions)
+        .filter_map(Result::ok)
+        .collect();
+    let scenario_name = load_scenario_name(data_source_id, context).await;
+    Ok(ComparisonInput { session_name: data_source_id.to_owned(), scenario_name, action_points })
+}
+
+#[derive(Deserialize)]
+struct ComparisonQuery {
+    sessions: String,
+    #[serde(rename = "keyActions")]
+    key_actions: Option<String>,
+    theme: Option<String>,
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',').map(|item| item.trim().to_owned()).filter(|item| !item.is_empty()).collect()
+}
+
+async fn compare_sessions(query: Query<ComparisonQuery>, context: Data<AppContext>) -> impl Responder {
+    let plotly_config = context.plotly_config();
+    let theme = match selected_theme(&plotly_config, query.theme.as_deref()) {
+        Ok(theme) => theme,
+        Err(response) => return response,
+    };
+    let mut inputs = Vec::new();
+    for data_source_id in split_list(&query.sessions) {
+        match load_comparison_input(&data_source_id, &context).await {
+            Ok(input) => inputs.push(input),
+            Err(e) => return HttpResponse::NotFound().json(json!({"error": format!("Failed to read the session {}", data_source_id), "details": e})),
+        }
+    }
+    let key_actions = query.key_actions.as_deref().map(split_list).unwrap_or_default();
+    match SessionComparison::new(inputs, &key_actions, &plotly_config, theme) {
+        Ok(comparison) => HttpResponse::Ok().json(comparison),
+        Err(e) => HttpResponse::BadRequest().json(json!({"error": e})),
+    }
+}
+
+/// Hash of the given contents, changes when any of them does. It is part of the persisted cache keys, so it has to
+/// stay the same across builds.
+fn content_hash(contents: &[&[u8]]) -> String {
+    let mut hasher = StableHasher::default();
+    contents.iter().for_each(|content| hasher.write_field(content));
+    format!("{:016x}", hasher.finish())
+}
+
+/// Cohort metrics of a session folder, from the cache unless `refresh` is set or the plot configuration, the
+/// alignment, the actions file or the manifest of the session changed since they were cached.
+async fn load_cohort_session(folder: &Value, refresh: bool, plot_config: (&PlotlyConfig, &ConfigVersion), context: &AppContext) -> Result<CohortSession, String> {
+    let (plotly_config, config_version) = plot_config;
+    let data_source_id = folder["id"].as_str().ok_or("Data source folder without id")?;
+    let date = folder["date"]["epoch"].as_i64()
+        .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+        .map(|date| date.date_naive())
+        .ok_or_else(|| format!("Data source folder {} without date", data_source_id))?;
+    let alignment = load_alignment(data_source_id, context).await;
+    let manifest = context.datasource_provider.fetch_session_manifest(data_source_id).await.unwrap_or_else(|e| {
+        log::warn!("Ignoring session manifest of {}: {}", data_source_id, e);
+        None
+    });
+    let mut actions_csv = Vec::new();
+    context.datasource_provider.fetch_csv_reader(data_source_id.to_string()).await?
+        .read_to_end(&mut actions_csv)
+        .map_err(|e| format!("Error reading the actions file of {}: {}", data_source_id, e))?;
+    let manifest_json = to_string(&manifest).map_err(|e| e.to_string())?;
+    let cache_key = format!(
+        "{} {} {}",
+        config_version.fingerprint,
+        to_string(&alignment.actions).map_err(|e| e.to_string())?,
+        content_hash(&[&actions_csv, manifest_json.as_bytes()])
+    );
+    if !refresh {
+        match context.cohort_cache.load(data_source_id, &cache_key).await {
+            Ok(Some(session)) => return Ok(session),
+            Ok(None) => {}
+            Err(e) => log::warn!("Ignoring the cached cohort metrics of {}: {}", data_source_id, e),
+        }
+    }
+
+    let action_points = align_actions(process_csv(actions_csv.as_slice(), 10), alignment.actions)
+        .filter_map(Result::ok)
+        .collect();
+    let input = CohortSessionInput {
+        session_name: folder["name"].as_str().unwrap_or(data_source_id).to_owned(),
+        date,
+        scenario_name: manifest.and_then(|manifest| manifest.scenario.name),
+        action_points,
+    };
+    let session = CohortSession::new(input, plotly_config);
+    if let Err(e) = context.cohort_cache.save(data_source_id, &cache_key, &session).await {
+        log::warn!("Failed to cache the cohort metrics of {}: {}", data_source_id, e);
+    }
+    Ok(session)
+}
+
+/// Sessions that can't be read are listed as skipped rather than failing the cohort, folders whose name isn't a
+/// date are not sessions. Every session is computed with the same plot configuration snapshot.
+async fn load_cohort(key_actions: &[String], refresh: bool, context: &AppContext) -> Result<CohortReport, String> {
+    let (plotly_config, config_version) = context.plot_config.current_with_version();
+    let folders = context.datasource_provider.get_main_folder_list().await.map_err(|e| e.to_string())?;
+    let mut sessions = Vec::with_capacity(folders.len());
+    let mut skipped = Vec::new();
+    for folder in folders.iter().filter(|folder| folder["date"]["epoch"].as_i64().unwrap_or_default() != 0) {
+        match load_cohort_session(folder, refresh, (&plotly_config, &config_version), context).await {
+            Ok(session) => sessions.push(session),
+            Err(e) => {
+                let session = folder["name"].as_str().unwrap_or_default().to_owned();
+                log::warn!("Leaving {} out of the cohort: {}", session, e);
+                skipped.push(SkippedSession { session, error: e });
+            }
+        }
+    }
+    Ok(CohortReport::new(sessions, skipped, key_actions))
+}
+
+#[derive(Deserialize)]
+struct CohortQuery {
+    #[serde(rename = "keyActions")]
+    key_actions: Option<String>,
+    format: Option<String>,
+    refresh: Option<bool>,
+}
+
+async fn cohort(query: Query<CohortQuery>, context: Data<AppContext>) -> impl Responder {
+    let format = query.format.as_deref().unwrap_or("json");
+    if format != "json" && format != "csv" {
+        return HttpResponse::BadRequest().json(json!({"error": format!("Unsupported cohort format: {}, expected json or csv", format)}));
+    }
+    let key_actions = query.key_actions.as_deref().map(split_list).unwrap_or_default();
+    let report = match load_cohort(&key_actions, query.refresh.unwrap_or(false), &context).await {
+        Ok(report) => report,
+        Err(e) => return HttpResponse::NotFound().json(json!({"error": "Failed to get data sources", "details": e})),
+    };
+    if format == "json" {
+        return HttpResponse::Ok().json(report);
+    }
+    match report.to_csv() {
+        Ok(csv) => HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(("Content-Disposition", "attachment; filename=\"cohort.csv\""))
+            .body(csv),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": "Failed to write the cohort csv", "details": e})),
+    }
+}
+
+/// `cohort [--key-actions=<action>,...] [--format=json|csv] [--output=<file>] [--refresh]` writes the cohort report
+/// to the file, or to the standard output.
+async fn run_cohort_command(args: &[String], context: &AppContext) -> io::Result<()> {
+    let option = |name: &str| args.iter().find_map(|arg| arg.strip_prefix(name)).map(str::to_owned);
+    let format = option("--format=").unwrap_or_else(|| "json".to_owned());
+    if format != "json" && format != "csv" {
+        return Err(io::Error::other(format!("Unsupported cohort format: {}, expected json or csv", format)));
+    }
+    let key_actions = option("--key-actions=").map(|list| split_list(&list)).unwrap_or_default();
+    let refresh = args.iter().any(|arg| arg == "--refresh");
+    let report = load_cohort(&key_actions, refresh, context).await.map_err(io::Error::other)?;
+    let content = if format == "csv" { report.to_csv().map_err(io::Error::other)? } else { serde_json::to_string_pretty(&report)? };
+    match option("--output=") {
+        Some(output) => std::fs::write(output, content),
+        None => {
+            println!("{}", content);
+            Ok(())
+        }
+    }
+}
+
+/// The saved alignment takes precedence over the one declared in the session manifest.
+async fn resolve_alignment(data_source_id: &str, context: &AppContext) -> Result<SessionAlignment, String> {
+    if let Some(alignment) = context.alignment_store.load(data_source_id).await? {
+        return Ok(alignment);
+    }
+    let manifest = context.datasource_provider.fetch_session_manifest(data_source_id).await?;
+    Ok(manifest.and_then(|manifest| manifest.alignment).unwrap_or_default())
+}
+
+/// Falls back to no correction when the alignment can't be read so the plots are still served.
+async fn load_alignment(data_source_id: &str, context: &AppContext) -> SessionAlignment {
+    resolve_alignment(data_source_id, context).await.unwrap_or_else(|e| {
+        log::warn!("Ignoring time alignment of {}: {}", data_source_id, e);
+        SessionAlignment::default()
+    })
+}
+
+fn align_actions<'a>(actions_iterator: Box<dyn Iterator<Item = Result<ActionPlotPoint, String>> + 'a>, offset: StreamOffset) -> impl Iterator<Item = Result<ActionPlotPoint, String>> + 'a {
+    actions_iterator.map(move |point| point.and_then(|point| align_action_plot_point(point, &offset)))
+}
+
+async fn get_alignment(data_source_id: Path<String>, context: Data<AppContext>) -> impl Responder {
+    match resolve_alignment(&data_source_id, &context).await {
+        Ok(alignment) => HttpResponse::Ok().json(alignment),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": "Failed to read time alignment", "details": e})),
+    }
+}
+
+async fn update_alignment(request: HttpRequest, data_source_id: Path<String>, alignment: web::Json<SessionAlignment>, context: Data<AppContext>) -> impl Responder {
+    if let Some(denied) = admin_request_denied(&request, &context) {
+        return denied;
+    }
+    let alignment = alignment.into_inner();
+    match context.alignment_store.save(&data_source_id, &alignment).await {
+        Ok(()) => HttpResponse::Ok().json(alignment),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": "Failed to save time alignment", "details": e})),
+    }
+}
+
+async fn session_manifest(data_source_id: Path<String>, context: Data<AppContext>) -> impl Responder {
+    match context.datasource_provider.fetch_session_manifest(&data_source_id).await {
+        Ok(Some(manifest)) => HttpResponse::Ok().json(manifest),
+        Ok(None) => HttpResponse::NotFound().json(json!({"error": format!("No {} found for the selected data source", SessionManifest::FILE_NAME)})),
+        Err(e) => HttpResponse::InternalServerError().json(json!({"error": "Failed to read session manifest", "details": e})),
+    }
+}
+
+/// The response of an admin request without the admin token, `None` when it may go ahead.
+fn admin_request_denied(request: &HttpRequest, context: &AppContext) -> Option<HttpResponse> {
+    if context.admin_token.is_none() {
+        return Some(HttpResponse::Forbidden().json(json!({"error": format!("The admin endpoints are disabled, set adminToken in {} to enable them", APP_CONFIG_FILE)})));
+    }
+    if !context.is_admin(request) {
+        return Some(HttpResponse::Unauthorized()
+            .insert_header(("WWW-Authenticate", "Bearer"))
+            .json(json!({"error": "Missing or wrong admin token, send it as Authorization: Bearer <adminToken>"})));
+    }
+    None
+}
+
+async fn plot_config_version(request: HttpRequest, context: Data<AppContext>) -> impl Responder {
+    if let Some(denied) = admin_request_denied(&request, &context) {
+        return denied;
+    }
+    HttpResponse::Ok().json(context.plot_config.version())
+}
+
+async fn reload_plot_config(request: HttpRequest, context: Data<AppContext>) -> impl Responder {
+    if let Some(denied) = admin_request_denied(&request, &context) {
+        return denied;
+    }
+    match context.plot_config.reload() {
+        Ok(version) => {
+            log::info!("Reloaded plot-config, version {} ({})", version.version, version.fingerprint);
+            log_config_warnings(&context.plot_config.current());
+            HttpResponse::Ok().json(version)
+        }
+        Err(e) => HttpResponse::UnprocessableEntity().json(json!({"error": "Failed to reload the plot-config, the active version is kept", "details": e, "active": context.plot_config.version()})),
+    }
+}
+
+fn config_schemas() -> Vec<(&'static str, serde_json::Value)> {
+    let mut schemas = vec![(APP_CONFIG_FILE, schema_document::<AppConfig>(APP_CONFIG_FILE))];
+    schemas.extend(plot_config_schemas());
+    schemas
+}
+
+async fn schemas() -> impl Responder {
+    let file_names: Vec<&str> = config_schemas().into_iter().map(|(file_name, _)| file_name).collect();
+    HttpResponse::Ok().json(file_names)
+}
+
+async fn schema(path: Path<String>) -> impl Responder {
+    let file_name = path.into_inner();
+    match config_schemas().into_iter().find(|(name, _)| *name == file_name) {
+        Some((_, schema)) => HttpResponse::Ok().content_type("application/schema+json").json(schema),
+        None => HttpResponse::NotFound().body(format!("No schema for configuration file {}", file_name)),
+    }
+}
+
 const CREDENTIALS_FILE_HOME: &str =
     "/Users/gunalmel/Downloads/mteam-dashboard-447216-9836ce4f74a2.json";
 
@@ -245,13 +1043,24 @@ const CREDENTIALS_FILE_HOME: &str =
 async fn main() -> io::Result<()> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
     
-    let config = AppConfig::new("config.json")?;
-    let plotly_config = config.get_plotly_config();
+    let config = AppConfig::new(APP_CONFIG_FILE)?;
+    let plot_config = config.get_plotly_config();
     let datasource_provider = config.get_data_provider().await;
     let context = Data::new(AppContext {
         datasource_provider: datasource_provider.clone(),
-        plotly_config
+        plot_config: plot_config.clone(),
+        alignment_store: AlignmentStore::new(&config.alignment_path),
+        cohort_cache: CohortCache::new(&config.cohort_cache_path),
+        static_files_path: config.static_files_path.clone().into(),
+        admin_token: config.admin_token.clone().filter(|token| !token.is_empty())
     });
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).is_some_and(|command| command == "cohort") {
+        return run_cohort_command(&args[2..], &context).await;
+    }
+    if config.plot_config_reload_interval_secs > 0 {
+        watch_plot_config(plot_config, Duration::from_secs(config.plot_config_reload_interval_secs));
+    }
     HttpServer::new(move || {
         App::new()
             .service(
@@ -264,11 +1073,28 @@ async fn main() -> io::Result<()> {
             .app_data(context.clone()) //To achieve globally shared state, it must be created outside the closure passed to HttpServer::new and moved/cloned in.
             .route("/data-sources/{data_source_id}/video", web::get().to(stream_video_handler))
             .route("/data-sources", web::get().to(data_sources))
+            .route("/compare", web::get().to(compare_sessions))
+            .route("/cohort", web::get().to(cohort))
+            .route("/admin/plot-config", web::get().to(plot_config_version))
+            .route("/admin/plot-config/reload", web::post().to(reload_plot_config))
+            .route("/schemas", web::get().to(schemas))
+            .route("/schemas/{file_name}", web::get().to(schema))
             .route("/data-sources/{data_source_id}/actions", web::get().to(actions))
             .route("/data-sources/{data_source_id}/actions/raw", web::get().to(test_actions))
+            .route("/data-sources/{data_source_id}/actions.svg", web::get().to(actions_svg))
+            .route("/data-sources/{data_source_id}/actions.png", web::get().to(actions_png))
+            .route("/data-sources/{data_source_id}/alignment", web::get().to(get_alignment))
+            .route("/data-sources/{data_source_id}/alignment", web::put().to(update_alignment))
+            .route("/data-sources/{data_source_id}/session", web::get().to(session_manifest))
+            .route("/data-sources/{data_source_id}/joint-attention", web::get().to(joint_attention))
+            .route("/data-sources/{data_source_id}/report", web::get().to(report))
+            .route("/data-sources/{data_source_id}/dashboard", web::get().to(dashboard))
             .route("/data-sources/{data_source_id}/{plot_name}", web::get().to(plot_sources))
             .route("/data-sources/{data_source_id}/cognitive-load/{id}", web::get().to(cognitive_load))
+            .route("/data-sources/{data_source_id}/cognitive-load/{id}/event-locked", web::get().to(cognitive_load_event_locked))
             .route("/data-sources/{data_source_id}/visual-attention/{id}", web::get().to(visual_attention))
+            .route("/data-sources/{data_source_id}/visual-attention/{id}/analytics", web::get().to(visual_attention_analytics))
+            .route("/data-sources/{data_source_id}/visual-attention/{id}/events", web::get().to(visual_attention_events))
 
         )})
         .bind(("0.0.0.0", config.port))?
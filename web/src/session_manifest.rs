@@ -0,0 +1,177 @@
+use crate::config::config::PlotType;
+use mteam_dashboard_utils::alignment::SessionAlignment;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::Read;
+use std::path::{Component, Path};
+
+/// Optional `session.json` in a data source folder describing the session explicitly.
+///
+/// Every field is optional, whatever is not declared is discovered by the folder layout conventions
+/// (first CSV file, first video file, JSON files in the `cognitive-load` and `visual-attention` subfolders).
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SessionManifest {
+    #[serde(default)]
+    pub scenario: ScenarioMetadata,
+    /// File name of the actions CSV in the data source folder.
+    #[serde(rename = "actionsFile", skip_serializing_if = "Option::is_none")]
+    pub actions_file: Option<String>,
+    /// File name of the video in the data source folder.
+    #[serde(rename = "videoFile", skip_serializing_if = "Option::is_none")]
+    pub video_file: Option<String>,
+    #[serde(rename = "teamMembers", default)]
+    pub team_members: Vec<TeamMember>,
+    /// Used as the time alignment of the session until one is saved through the alignment api.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<SessionAlignment>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ScenarioMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TeamMember {
+    /// Used as the filter option name of the member's cognitive load and visual attention plots.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Username of the member in the actions CSV `Username` column, data files named after it (e.g. `umich1.json`)
+    /// are listed under the member name when the member doesn't declare a file.
+    #[serde(rename = "csvUsername", skip_serializing_if = "Option::is_none")]
+    pub csv_username: Option<String>,
+    /// File name in the `cognitive-load` subfolder.
+    #[serde(rename = "cognitiveLoadFile", skip_serializing_if = "Option::is_none")]
+    pub cognitive_load_file: Option<String>,
+    /// File name in the `visual-attention` subfolder.
+    #[serde(rename = "visualAttentionFile", skip_serializing_if = "Option::is_none")]
+    pub visual_attention_file: Option<String>,
+}
+
+impl SessionManifest {
+    pub const FILE_NAME: &'static str = "session.json";
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, String> {
+        let manifest: Self = serde_json::from_reader(reader).map_err(|e| format!("Error deserializing {}: {}", Self::FILE_NAME, e))?;
+        manifest.check_file_names()?;
+        Ok(manifest)
+    }
+
+    /// The declared files are joined onto the session folder, anything but a plain file name could point outside of
+    /// it, e.g. `../../config.json` or `/etc/passwd`.
+    fn check_file_names(&self) -> Result<(), String> {
+        let members = self.team_members.iter().flat_map(|member| [
+            ("cognitiveLoadFile", &member.cognitive_load_file),
+            ("visualAttentionFile", &member.visual_attention_file),
+        ]);
+        let declared = [("actionsFile", &self.actions_file), ("videoFile", &self.video_file)].into_iter().chain(members);
+        for (key, file_name) in declared.filter_map(|(key, file_name)| file_name.as_ref().map(|file_name| (key, file_name))) {
+            let mut components = Path::new(file_name).components();
+            let plain = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) && !file_name.contains(['/', '\\']);
+            if !plain {
+                return Err(format!("Error in {}: {} must be a file name in the session folder, found {}", Self::FILE_NAME, key, file_name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Name of the team member whose `csvUsername` is the name of the data file without its extension, ignoring case.
+    pub fn member_name_of_file(&self, file_name: &str) -> Option<&str> {
+        let stem = Path::new(file_name).file_stem()?.to_str()?;
+        self.team_members.iter()
+            .find(|member| member.csv_username.as_deref().is_some_and(|username| username.eq_ignore_ascii_case(stem)))
+            .map(|member| member.name.as_str())
+    }
+
+    /// Team member name and file name pairs declared for the plot folder in the order of the manifest.
+    /// `None` when no member declares a file for it, in which case the folder listing should be used.
+    pub fn plot_files(&self, category_folder_name: &str) -> Option<Vec<(String, String)>> {
+        let files: Vec<(String, String)> = self.team_members.iter()
+            .filter_map(|member| {
+                let file_name = if category_folder_name == PlotType::CognitiveLoad.as_str() {
+                    member.cognitive_load_file.as_ref()
+                } else if category_folder_name == PlotType::VisualAttention.as_str() {
+                    member.visual_attention_file.as_ref()
+                } else {
+                    None
+                };
+                file_name.map(|file_name| (member.name.clone(), file_name.clone()))
+            })
+            .collect();
+        if files.is_empty() {
+            None
+        } else {
+            Some(files)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_session_manifest {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+        "scenario": {"name": "Cardiac Arrest", "instructor": "Dr. Smith"},
+        "actionsFile": "timeline.csv",
+        "teamMembers": [
+            {"name": "Team Lead", "role": "Leader", "csvUsername": "umich1", "cognitiveLoadFile": "lead.json", "visualAttentionFile": "lead_gaze.json"},
+            {"name": "Airway", "csvUsername": "umich2", "cognitiveLoadFile": "airway.json"}
+        ],
+        "alignment": {"video": {"offsetSeconds": 2}}
+    }"#;
+
+    #[test]
+    fn parse() {
+        let manifest = SessionManifest::from_reader(MANIFEST.as_bytes()).unwrap();
+        assert_eq!(manifest.scenario.name, Some("Cardiac Arrest".to_owned()));
+        assert_eq!(manifest.scenario.extra["instructor"], "Dr. Smith");
+        assert_eq!(manifest.actions_file, Some("timeline.csv".to_owned()));
+        assert_eq!(manifest.video_file, None);
+        assert_eq!(manifest.team_members[1].csv_username, Some("umich2".to_owned()));
+        assert_eq!(manifest.alignment.unwrap().video.offset_secs, 2.0);
+    }
+
+    #[test]
+    fn plot_files_follow_manifest_order() {
+        let manifest = SessionManifest::from_reader(MANIFEST.as_bytes()).unwrap();
+        assert_eq!(
+            manifest.plot_files("cognitive-load"),
+            Some(vec![("Team Lead".to_owned(), "lead.json".to_owned()), ("Airway".to_owned(), "airway.json".to_owned())])
+        );
+        assert_eq!(manifest.plot_files("visual-attention"), Some(vec![("Team Lead".to_owned(), "lead_gaze.json".to_owned())]));
+    }
+
+    #[test]
+    fn plot_files_fall_back_without_declared_files() {
+        let manifest = SessionManifest::from_reader(r#"{"teamMembers": [{"name": "Team Lead"}]}"#.as_bytes()).unwrap();
+        assert_eq!(manifest.plot_files("cognitive-load"), None);
+    }
+
+    #[test]
+    fn files_named_after_the_csv_username() {
+        let manifest = SessionManifest::from_reader(MANIFEST.as_bytes()).unwrap();
+        assert_eq!(manifest.member_name_of_file("UMICH2.json"), Some("Airway"));
+        assert_eq!(manifest.member_name_of_file("umich3.json"), None);
+    }
+
+    #[test]
+    fn declared_files_stay_in_the_session_folder() {
+        for file_name in ["../../config.json", "/etc/passwd", "cognitive-load/../x.json", "..", "a\\..\\b.csv", ""] {
+            let manifest = serde_json::json!({"actionsFile": "timeline.csv", "teamMembers": [{"name": "Team Lead", "cognitiveLoadFile": file_name}]});
+            let error = SessionManifest::from_reader(manifest.to_string().as_bytes()).unwrap_err();
+            assert!(error.contains("cognitiveLoadFile must be a file name"), "{}", error);
+        }
+        assert!(SessionManifest::from_reader(r#"{"videoFile": "../video.mp4"}"#.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn invalid_manifest() {
+        assert!(SessionManifest::from_reader("{\"teamMembers\": 1}".as_bytes()).is_err());
+    }
+}